@@ -0,0 +1,90 @@
+//! Compares the search functions of `RawStr` to naive implementations.
+//!
+//! Run with `cargo bench --bench search`.
+
+extern crate raw_string;
+
+use raw_string::{RawFinder, RawStr};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 20;
+
+fn bench<F: FnMut() -> Option<usize>>(name: &str, mut f: F) -> Duration {
+	let start = Instant::now();
+	for _ in 0..ITERATIONS {
+		black_box(f());
+	}
+	let time = start.elapsed() / ITERATIONS;
+	println!("{:>32}: {:>10.3?}", name, time);
+	time
+}
+
+fn compare<F, G>(name: &str, naive: F, fast: G)
+where
+	F: FnMut() -> Option<usize>,
+	G: FnMut() -> Option<usize>,
+{
+	let naive = bench(&format!("{} (naive)", name), naive);
+	let fast = bench(name, fast);
+	println!("{:>32}  {:.1}x", "", naive.as_secs_f64() / fast.as_secs_f64());
+}
+
+/// Some megabytes of text-like bytes, without `#`, `@`, `~` or `needle`.
+fn haystack() -> Vec<u8> {
+	let words: &[&[u8]] = &[b"the ", b"quick ", b"brown ", b"fox ", b"jumps ", b"over ", b"\xE2\x82\xAC ", b"lazy ", b"dog\n"];
+	let mut state = 1u32;
+	let mut haystack = Vec::new();
+	while haystack.len() < 8 << 20 {
+		state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+		haystack.extend_from_slice(words[(state >> 16) as usize % words.len()]);
+	}
+	haystack
+}
+
+fn main() {
+	let mut bytes = haystack();
+	let len = bytes.len();
+	bytes[len - 10..].copy_from_slice(b"#@~needle!");
+	let haystack = RawStr::from_bytes(&bytes);
+
+	compare(
+		"find_byte",
+		|| bytes.iter().position(|&b| b == b'#'),
+		|| haystack.find_byte(b'#'),
+	);
+	compare(
+		"rfind_byte",
+		|| bytes.iter().rposition(|&b| b == b'&'),
+		|| haystack.rfind_byte(b'&'),
+	);
+	compare(
+		"find_byte2",
+		|| bytes.iter().position(|&b| b == b'@' || b == b'#'),
+		|| haystack.find_byte2(b'@', b'#'),
+	);
+	compare(
+		"find_byte3",
+		|| bytes.iter().position(|&b| b == b'~' || b == b'@' || b == b'#'),
+		|| haystack.find_byte3(b'~', b'@', b'#'),
+	);
+
+	let needle = b"needle";
+	compare(
+		"find",
+		|| bytes.windows(needle.len()).position(|w| w == needle),
+		|| haystack.find("needle"),
+	);
+	compare(
+		"rfind",
+		|| bytes.windows(8).rposition(|w| w == b"the fox!"),
+		|| haystack.rfind("the fox!"),
+	);
+
+	let finder = RawFinder::new("lazy dog\nthe lazy cat");
+	compare(
+		"RawFinder::find (repetitive)",
+		|| bytes.windows(finder.needle().len()).position(|w| w == finder.needle().as_bytes()),
+		|| finder.find(haystack),
+	);
+}
@@ -0,0 +1,29 @@
+#!/usr/bin/env perl
+#
+# Generates src/str/case_tables.rs, the simple case folding table.
+#
+# Usage: scripts/gen-case-tables.pl > src/str/case_tables.rs
+#
+# The mappings come from Perl's Unicode::UCD module, which includes
+# CaseFolding.txt. Only the simple (C and S) foldings are used, which map a
+# single character to a single character.
+
+use strict;
+use warnings;
+use Unicode::UCD ();
+
+my $folds = Unicode::UCD::all_casefolds();
+my @entries;
+for my $c (sort { $a <=> $b } keys %$folds) {
+	my $simple = $folds->{$c}{simple};
+	push @entries, sprintf("('\\u{%04X}', '\\u{%s}'),", $c, $simple) if $simple ne "";
+}
+
+print "// Generated by scripts/gen-case-tables.pl. Do not edit.\n";
+print "//\n";
+printf "// Simple case folding from Unicode %s, sorted by the first character.\n", Unicode::UCD::UnicodeVersion();
+print "\npub const SIMPLE_CASE_FOLDING: [(char, char); ", scalar(@entries), "] = [\n";
+while (my @row = splice(@entries, 0, 4)) {
+	print "\t", join(" ", @row), "\n";
+}
+print "];\n";
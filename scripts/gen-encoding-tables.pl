@@ -0,0 +1,57 @@
+#!/usr/bin/env perl
+#
+# Generates src/encoding/tables.rs, the tables for the single byte encodings.
+#
+# Usage: scripts/gen-encoding-tables.pl > src/encoding/tables.rs
+#
+# The mappings come from Perl's Encode module. As the WHATWG Encoding
+# Standard specifies, bytes 80 to 9F that Windows leaves undefined are mapped
+# to the C1 control characters with the same value.
+
+use strict;
+use warnings;
+use Encode ();
+
+my @encodings = (
+	# [ Rust name, Encode name, fill undefined C1 range ]
+	["ASCII", "ascii", 0],
+	["LATIN1", "iso-8859-1", 0],
+	(map { ["ISO_8859_$_", "iso-8859-$_", 0] } 2 .. 11, 13 .. 16),
+	["WINDOWS_874", "cp874", 1],
+	(map { ["WINDOWS_$_", "cp$_", 1] } 1250 .. 1258),
+);
+
+print "// Generated by scripts/gen-encoding-tables.pl. Do not edit.\n";
+print "//\n";
+print "// For every encoding, the code points for the bytes 80 to FF (zero if\n";
+print "// unmapped), and the reverse mapping of those code points to bytes, sorted\n";
+print "// by code point.\n";
+
+for my $e (@encodings) {
+	my ($name, $encode_name, $fill_c1) = @$e;
+	my @high;
+	for my $b (0x80 .. 0xFF) {
+		my $s = Encode::decode($encode_name, chr($b), sub { "" });
+		my $c = length($s) == 1 ? ord($s) : 0;
+		$c = $b if $c == 0 && $fill_c1 && $b < 0xA0;
+		push @high, $c;
+	}
+	print "\npub static $name: [u16; 128] = [\n";
+	for my $row (0 .. 15) {
+		print "\t", join(" ", map { sprintf "0x%04X,", $_ } @high[$row * 8 .. $row * 8 + 7]), "\n";
+	}
+	print "];\n";
+
+	# If several bytes map to the same code point, encode it as the first.
+	my %reverse;
+	for my $i (reverse 0 .. 127) {
+		$reverse{$high[$i]} = 0x80 + $i if $high[$i];
+	}
+	my @reverse = map { sprintf "(0x%04X, 0x%02X),", $_, $reverse{$_} } sort { $a <=> $b } keys %reverse;
+	printf "\npub static %s_REVERSE: [(u16, u8); %d] = [", $name, scalar @reverse;
+	print "\n" if @reverse;
+	while (my @row = splice @reverse, 0, 6) {
+		print "\t", join(" ", @row), "\n";
+	}
+	print "];\n";
+}
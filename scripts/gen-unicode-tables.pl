@@ -0,0 +1,183 @@
+#!/usr/bin/env perl
+#
+# Generates src/unicode/tables.rs, the tables for the `unicode` feature.
+#
+# Usage: scripts/gen-unicode-tables.pl > src/unicode/tables.rs
+#
+# The data comes from Perl's Unicode::UCD and Unicode::Normalize modules.
+# Hangul syllables are left out of the decomposition and composition tables,
+# as they are decomposed and composed algorithmically. The segmentation
+# properties use the official values, not Perl's tailored ones.
+
+use strict;
+use warnings;
+use Unicode::Normalize ();
+use Unicode::UCD ();
+
+sub ch { sprintf "'\\u{%04X}'", $_[0] }
+sub chars { "&[" . join(", ", map { ch(ord) } split //, $_[0]) . "]" }
+sub is_hangul_syllable { $_[0] >= 0xAC00 && $_[0] <= 0xD7A3 }
+sub is_surrogate { $_[0] >= 0xD800 && $_[0] <= 0xDFFF }
+
+sub print_table {
+	my ($comment, $name, $type, $per_row, @entries) = @_;
+	print "\n$comment";
+	print "pub static $name: [$type; ", scalar(@entries), "] = [\n";
+	while (my @row = splice(@entries, 0, $per_row)) {
+		print "\t", join(" ", map { "$_," } @row), "\n";
+	}
+	print "];\n";
+}
+
+# Ranges of code points for which `$f` returns the same true value, as
+# [first, last, value].
+sub ranges {
+	my ($f) = @_;
+	my @ranges;
+	for my $c (0 .. 0x10FFFF) {
+		next if is_surrogate($c);
+		my $v = $f->($c);
+		next unless $v;
+		if (@ranges && $ranges[-1][1] == $c - 1 && $ranges[-1][2] eq $v) {
+			$ranges[-1][1] = $c;
+		} else {
+			push @ranges, [$c, $c, $v];
+		}
+	}
+	@ranges;
+}
+
+# The ranges of an inversion list, as [first, last].
+sub invlist_ranges {
+	my @list = Unicode::UCD::prop_invlist($_[0]);
+	my @ranges;
+	while (my ($first, $end) = splice(@list, 0, 2)) {
+		push @ranges, [$first, ($end // 0x110000) - 1];
+	}
+	@ranges;
+}
+
+# The union of the given ranges, sorted and merged.
+sub merge_ranges {
+	my @merged;
+	for my $r (sort { $a->[0] <=> $b->[0] } @_) {
+		if (@merged && $r->[0] <= $merged[-1][1] + 1) {
+			$merged[-1][1] = $r->[1] if $r->[1] > $merged[-1][1];
+		} else {
+			push @merged, [@$r];
+		}
+	}
+	@merged;
+}
+
+sub print_ranges {
+	my ($comment, $name, @ranges) = @_;
+	print_table($comment, $name, "(char, char)", 4, map { "(" . ch($_->[0]) . ", " . ch($_->[1]) . ")" } @ranges);
+}
+
+# Print an enum of the given property values, and a table of the ranges of
+# characters with each value. All other characters have the value Other.
+sub print_property {
+	my ($property, $table, @values) = @_;
+	(my $type = $property) =~ s/_//g;
+	print "
+#[allow(clippy::upper_case_acronyms)]
+";
+	print "#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+";
+	print "pub enum $type {
+";
+	for my $value (@values, "Other") {
+		(my $variant = $value) =~ s/_//g;
+		print "\t$variant,\n";
+	}
+	print "}
+";
+	my @ranges;
+	for my $value (@values) {
+		(my $variant = $value) =~ s/_//g;
+		push @ranges, map { [@$_, "${type}::$variant"] } invlist_ranges("$property=$value");
+	}
+	@ranges = sort { $a->[0] <=> $b->[0] } @ranges;
+	print_table(
+		"// The ranges of characters with a $property other than Other.\n",
+		$table, "(char, char, $type)", 2,
+		map { "(" . ch($_->[0]) . ", " . ch($_->[1]) . ", $_->[2])" } @ranges,
+	);
+}
+
+my (@canonical, @compatibility, @composition);
+for my $c (0 .. 0x10FFFF) {
+	next if is_surrogate($c) || is_hangul_syllable($c);
+	my $canon = Unicode::Normalize::getCanon($c);
+	my $compat = Unicode::Normalize::getCompat($c);
+	push @canonical, "(" . ch($c) . ", " . chars($canon) . ")" if defined $canon;
+	push @compatibility, "(" . ch($c) . ", " . chars($compat) . ")"
+		if defined $compat && (!defined $canon || $compat ne $canon);
+	if (defined $canon && !Unicode::Normalize::isComp_Ex($c)) {
+		my @mapping = map { hex } split / /, Unicode::UCD::charinfo($c)->{decomposition};
+		push @composition, [@mapping, $c] if @mapping == 2;
+	}
+}
+@composition = sort { $a->[0] <=> $b->[0] || $a->[1] <=> $b->[1] } @composition;
+
+print "// Generated by scripts/gen-unicode-tables.pl. Do not edit.\n";
+print "//\n";
+printf "// Unicode %s.\n", Unicode::UCD::UnicodeVersion();
+
+print_table(
+	"// The ranges of characters with a non-zero canonical combining class.\n",
+	"CANONICAL_COMBINING_CLASS", "(char, char, u8)", 3,
+	map { "(" . ch($_->[0]) . ", " . ch($_->[1]) . ", $_->[2])" }
+		ranges(sub { Unicode::Normalize::getCombinClass($_[0]) }),
+);
+print_table(
+	"// The full canonical decompositions.\n",
+	"CANONICAL_DECOMPOSITION", "(char, &[char])", 2, @canonical,
+);
+print_table(
+	"// The full compatibility decompositions, where they differ from the\n// canonical ones.\n",
+	"COMPATIBILITY_DECOMPOSITION", "(char, &[char])", 2, @compatibility,
+);
+print_table(
+	"// The primary composites, by the two characters they are composed of.\n",
+	"COMPOSITION", "(char, char, char)", 3,
+	map { "(" . join(", ", map { ch($_) } @$_) . ")" } @composition,
+);
+for my $qc (["NO", "N"], ["MAYBE", "M"]) {
+	my ($name, $value) = @$qc;
+	print_ranges(
+		"// The ranges of characters with NFC_Quick_Check=$name.\n",
+		"NFC_QUICK_CHECK_$name", invlist_ranges("NFC_QC=$value"),
+	);
+}
+
+print_property(
+	"Grapheme_Cluster_Break", "GRAPHEME_CLUSTER_BREAK",
+	qw(CR LF Control Extend ZWJ Regional_Indicator Prepend SpacingMark L V T LV LVT),
+);
+print_property(
+	"Word_Break", "WORD_BREAK",
+	qw(CR LF Newline Extend ZWJ Regional_Indicator Format Katakana Hebrew_Letter ALetter
+		Single_Quote Double_Quote MidNumLet MidLetter MidNum Numeric ExtendNumLet WSegSpace),
+);
+print_ranges(
+	"// The ranges of characters with Extended_Pictographic=Yes.\n",
+	"EXTENDED_PICTOGRAPHIC", invlist_ranges("Extended_Pictographic"),
+);
+
+print_ranges(
+	"// The ranges of characters that take no space: nonspacing and enclosing\n// marks, format characters, and Hangul medial vowels and final consonants.\n",
+	"ZERO_WIDTH", merge_ranges(
+		(map { invlist_ranges("General_Category=$_") } qw(Mn Me Cf)),
+		[0x1160, 0x11FF], [0xD7B0, 0xD7FF],
+	),
+);
+print_ranges(
+	"// The ranges of characters with East_Asian_Width=Wide or Fullwidth.\n",
+	"WIDE", merge_ranges(map { invlist_ranges("East_Asian_Width=$_") } qw(W F)),
+);
+print_ranges(
+	"// The ranges of characters with East_Asian_Width=Ambiguous.\n",
+	"AMBIGUOUS", invlist_ranges("East_Asian_Width=A"),
+);
@@ -0,0 +1,638 @@
+//! ANSI escape sequences, as found in terminal output.
+//!
+//! [`RawStr::ansi_tokens`](../struct.RawStr.html#method.ansi_tokens) splits a
+//! string into text and escape sequences, following the syntax of ECMA-48:
+//!
+//! - Control Sequences (CSI), `ESC [`, followed by parameter bytes,
+//!   intermediate bytes and a final byte. These include colors and styles
+//!   ([`Csi::sgr`](struct.Csi.html#method.sgr)) and cursor movement
+//!   ([`Csi::cursor_move`](struct.Csi.html#method.cursor_move)).
+//! - Operating System Commands (OSC), `ESC ]`, terminated by `BEL` or
+//!   `ESC \`. These include hyperlinks
+//!   ([`Osc::hyperlink`](struct.Osc.html#method.hyperlink)).
+//! - All other escape sequences, including the `DCS`, `SOS`, `PM` and `APC`
+//!   strings, and malformed or unterminated sequences.
+//!
+//! Only the 7-bit forms starting with `ESC` are recognised. The 8-bit C1
+//! forms are not, because those bytes usually mean something else.
+//!
+//! # Example
+//!
+//! ```
+//! # use raw_string::RawStr;
+//! # use raw_string::ansi::{Color, Sgr, Token};
+//! let s = RawStr::from_bytes(b"\x1b[1;31merror\x1b[0m: \xFF");
+//! let tokens: Vec<Token> = s.ansi_tokens().collect();
+//! assert_eq!(tokens.len(), 4);
+//! match tokens[0] {
+//!     Token::Csi(csi) => assert_eq!(csi.sgr(), Some(vec![Sgr::Bold, Sgr::Foreground(Color::Indexed(1))])),
+//!     _ => panic!(),
+//! }
+//! assert_eq!(tokens[1], Token::Text(RawStr::from_str("error")));
+//! assert_eq!(&*s.strip_ansi(), RawStr::from_bytes(b"error: \xFF"));
+//! ```
+
+use std::borrow::Cow;
+use std::iter::FusedIterator;
+use str::{memchr, RawStr};
+use string::RawString;
+
+const ESC: u8 = 0x1B;
+const BEL: u8 = 0x07;
+
+// Tokens {{{
+
+/// A piece of a string: text or an escape sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token<'a> {
+	/// Text without any escape sequences.
+	Text(&'a RawStr),
+	/// A control sequence: `ESC [ ...`.
+	Csi(Csi<'a>),
+	/// An operating system command: `ESC ] ...`.
+	Osc(Osc<'a>),
+	/// Any other escape sequence, or a malformed one.
+	Escape(&'a RawStr),
+}
+
+impl<'a> Token<'a> {
+	/// The part of the string this token was parsed from.
+	pub fn as_raw_str(&self) -> &'a RawStr {
+		match *self {
+			Token::Text(s) | Token::Escape(s) => s,
+			Token::Csi(csi) => csi.raw,
+			Token::Osc(osc) => osc.raw,
+		}
+	}
+
+	/// Whether this is [`Text`](#variant.Text).
+	pub fn is_text(&self) -> bool {
+		matches!(self, Token::Text(_))
+	}
+}
+
+/// Iterator over the text and escape sequences of a `RawStr`.
+///
+/// See [`RawStr::ansi_tokens`](../struct.RawStr.html#method.ansi_tokens).
+#[derive(Clone)]
+pub struct Tokens<'a> {
+	rest: &'a RawStr,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+	type Item = Token<'a>;
+	fn next(&mut self) -> Option<Token<'a>> {
+		let bytes = self.rest.as_bytes();
+		let token = match bytes.first() {
+			None => return None,
+			Some(&ESC) => parse_escape(self.rest),
+			Some(_) => Token::Text(&self.rest[..memchr(ESC, bytes).unwrap_or(bytes.len())]),
+		};
+		self.rest = &self.rest[token.as_raw_str().len()..];
+		Some(token)
+	}
+}
+
+impl<'a> FusedIterator for Tokens<'a> {}
+
+fn count(bytes: &[u8], range: ::std::ops::RangeInclusive<u8>) -> usize {
+	bytes.iter().take_while(|b| range.contains(b)).count()
+}
+
+/// Find the end of a control string that starts at `start`.
+///
+/// Returns the end of the contents and the end of the terminator, or the end
+/// of the malformed string if it isn't terminated properly.
+fn string_end(bytes: &[u8], start: usize) -> Result<(usize, usize), usize> {
+	for i in start..bytes.len() {
+		match bytes[i] {
+			BEL => return Ok((i, i + 1)),
+			ESC if bytes.get(i + 1) == Some(&b'\\') => return Ok((i, i + 2)),
+			ESC => return Err(i),
+			_ => {}
+		}
+	}
+	Err(bytes.len())
+}
+
+/// Parse the escape sequence at the start of `s`, which starts with `ESC`.
+fn parse_escape(s: &RawStr) -> Token<'_> {
+	let bytes = s.as_bytes();
+	match bytes.get(1) {
+		Some(b'[') => {
+			let params_end = 2 + count(&bytes[2..], 0x30..=0x3F);
+			let intermediates_end = params_end + count(&bytes[params_end..], 0x20..=0x2F);
+			match bytes.get(intermediates_end) {
+				Some(&final_byte) if (0x40..=0x7E).contains(&final_byte) => Token::Csi(Csi {
+					raw: &s[..intermediates_end + 1],
+					params: &s[2..params_end],
+					intermediates: &s[params_end..intermediates_end],
+					final_byte,
+				}),
+				_ => Token::Escape(&s[..intermediates_end]),
+			}
+		}
+		Some(b']') => match string_end(bytes, 2) {
+			Ok((data_end, end)) => Token::Osc(Osc {
+				raw: &s[..end],
+				data: &s[2..data_end],
+			}),
+			Err(end) => Token::Escape(&s[..end]),
+		},
+		Some(b'P' | b'X' | b'^' | b'_') => {
+			let end = string_end(bytes, 2).map_or_else(|end| end, |(_, end)| end);
+			Token::Escape(&s[..end])
+		}
+		_ => {
+			let intermediates_end = 1 + count(&bytes[1..], 0x20..=0x2F);
+			match bytes.get(intermediates_end) {
+				Some(0x30..=0x7E) => Token::Escape(&s[..intermediates_end + 1]),
+				_ => Token::Escape(&s[..intermediates_end]),
+			}
+		}
+	}
+}
+
+// }}}
+
+// Control sequences {{{
+
+/// A control sequence: `ESC [`, parameter bytes, intermediate bytes and a
+/// final byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Csi<'a> {
+	raw: &'a RawStr,
+	params: &'a RawStr,
+	intermediates: &'a RawStr,
+	final_byte: u8,
+}
+
+impl<'a> Csi<'a> {
+	/// The whole sequence, including the `ESC [`.
+	#[inline]
+	pub fn as_raw_str(&self) -> &'a RawStr {
+		self.raw
+	}
+
+	/// The parameter bytes, like `1;31` in `ESC [ 1 ; 3 1 m`.
+	#[inline]
+	pub fn parameter_bytes(&self) -> &'a RawStr {
+		self.params
+	}
+
+	/// The intermediate bytes (`0x20` to `0x2F`), usually empty.
+	#[inline]
+	pub fn intermediates(&self) -> &'a RawStr {
+		self.intermediates
+	}
+
+	/// The final byte, which determines the function, like `m` for SGR.
+	#[inline]
+	pub fn final_byte(&self) -> u8 {
+		self.final_byte
+	}
+
+	/// Whether the parameters start with a private marker (`<`, `=`, `>` or
+	/// `?`), like in `ESC [ ? 2 5 h`.
+	#[inline]
+	pub fn is_private(&self) -> bool {
+		matches!(self.params.as_bytes().first(), Some(b'<'..=b'?'))
+	}
+
+	/// The numeric parameters, separated by `;` or `:`. Empty or invalid
+	/// parameters are `None`.
+	pub fn params(&self) -> impl Iterator<Item = Option<u32>> + 'a {
+		let params = if self.is_private() {
+			&self.params[1..]
+		} else {
+			self.params
+		};
+		let empty = params.is_empty();
+		params
+			.as_bytes()
+			.split(|&b| b == b';' || b == b':')
+			.filter(move |_| !empty)
+			.map(parse_param)
+	}
+
+	/// The parameters as groups of sub-parameters: separated by `;`, with the
+	/// sub-parameters separated by `:`.
+	fn param_groups(&self) -> Vec<Vec<Option<u32>>> {
+		if self.params.is_empty() {
+			return Vec::new();
+		}
+		self.params
+			.as_bytes()
+			.split(|&b| b == b';')
+			.map(|group| group.split(|&b| b == b':').map(parse_param).collect())
+			.collect()
+	}
+
+	fn is_plain(&self, final_byte: u8) -> bool {
+		self.final_byte == final_byte && self.intermediates.is_empty() && !self.is_private()
+	}
+
+	/// The attributes set by an SGR (Select Graphic Rendition) sequence,
+	/// `ESC [ ... m`, or `None` if this is something else.
+	///
+	/// Extended colors are understood in both the `38;5;n` and the `38:5:n`
+	/// form.
+	pub fn sgr(&self) -> Option<Vec<Sgr>> {
+		if !self.is_plain(b'm') {
+			return None;
+		}
+		let groups = self.param_groups();
+		if groups.is_empty() {
+			return Some(vec![Sgr::Reset]);
+		}
+		let mut result = Vec::new();
+		let mut groups = groups.iter();
+		while let Some(group) = groups.next() {
+			let code = group[0].unwrap_or(0);
+			let color = |groups: &mut ::std::slice::Iter<Vec<Option<u32>>>| {
+				if group.len() > 1 {
+					extended_color(&group[1..])
+				} else {
+					let kind = groups.next().and_then(|g| g[0]);
+					let n = if kind == Some(2) { 3 } else { 1 };
+					let args: Vec<Option<u32>> = groups.by_ref().take(n).map(|g| g[0]).collect();
+					extended_color(&[&[kind][..], &args].concat())
+				}
+			};
+			result.push(match code {
+				0 => Sgr::Reset,
+				1 => Sgr::Bold,
+				2 => Sgr::Faint,
+				3 => Sgr::Italic,
+				4 => Sgr::Underline,
+				5 | 6 => Sgr::Blink,
+				7 => Sgr::Inverse,
+				8 => Sgr::Hidden,
+				9 => Sgr::Strikethrough,
+				22 => Sgr::NormalIntensity,
+				23 => Sgr::NotItalic,
+				24 => Sgr::NotUnderlined,
+				25 => Sgr::NotBlinking,
+				27 => Sgr::NotInverse,
+				28 => Sgr::NotHidden,
+				29 => Sgr::NotStrikethrough,
+				30..=37 => Sgr::Foreground(Color::Indexed((code - 30) as u8)),
+				38 => color(&mut groups).map_or(Sgr::Other(code), Sgr::Foreground),
+				39 => Sgr::DefaultForeground,
+				40..=47 => Sgr::Background(Color::Indexed((code - 40) as u8)),
+				48 => color(&mut groups).map_or(Sgr::Other(code), Sgr::Background),
+				49 => Sgr::DefaultBackground,
+				90..=97 => Sgr::Foreground(Color::Indexed((code - 90 + 8) as u8)),
+				100..=107 => Sgr::Background(Color::Indexed((code - 100 + 8) as u8)),
+				_ => Sgr::Other(code),
+			});
+		}
+		Some(result)
+	}
+
+	/// The cursor movement of a `CUU`, `CUD`, `CUF`, `CUB`, `CNL`, `CPL`,
+	/// `CHA`, `CUP` or `HVP` sequence, or `None` if this is something else.
+	pub fn cursor_move(&self) -> Option<CursorMove> {
+		if !self.intermediates.is_empty() || self.is_private() {
+			return None;
+		}
+		let params: Vec<u32> = self.params().map(|p| p.unwrap_or(0).max(1)).collect();
+		let n = params.first().cloned().unwrap_or(1);
+		Some(match self.final_byte {
+			b'A' => CursorMove::Up(n),
+			b'B' => CursorMove::Down(n),
+			b'C' => CursorMove::Forward(n),
+			b'D' => CursorMove::Back(n),
+			b'E' => CursorMove::NextLine(n),
+			b'F' => CursorMove::PreviousLine(n),
+			b'G' => CursorMove::Column(n),
+			b'H' | b'f' => CursorMove::Position {
+				row: n,
+				column: params.get(1).cloned().unwrap_or(1),
+			},
+			_ => return None,
+		})
+	}
+}
+
+fn parse_param(param: &[u8]) -> Option<u32> {
+	RawStr::from_bytes(param).to_str().ok()?.parse().ok()
+}
+
+/// Parse the arguments of SGR 38 or 48: `5;n` or `2;[colorspace;]r;g;b`.
+fn extended_color(args: &[Option<u32>]) -> Option<Color> {
+	let byte = |arg: Option<&Option<u32>>| match arg {
+		Some(&Some(n)) if n < 256 => Some(n as u8),
+		Some(&None) => Some(0),
+		_ => None,
+	};
+	match args.first() {
+		Some(&Some(5)) => byte(args.get(1)).map(Color::Indexed),
+		Some(&Some(2)) => {
+			// The colon form may have a color space identifier first.
+			let rgb = if args.len() > 4 { &args[2..] } else { &args[1..] };
+			Some(Color::Rgb(byte(rgb.first())?, byte(rgb.get(1))?, byte(rgb.get(2))?))
+		}
+		_ => None,
+	}
+}
+
+/// A color in an SGR sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+	/// A palette color: 0 to 7 are the standard colors (black, red, green,
+	/// yellow, blue, magenta, cyan, white), 8 to 15 their bright versions.
+	Indexed(u8),
+	/// A 24-bit color.
+	Rgb(u8, u8, u8),
+}
+
+/// An attribute set by an SGR sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sgr {
+	Reset,
+	Bold,
+	Faint,
+	Italic,
+	Underline,
+	Blink,
+	Inverse,
+	Hidden,
+	Strikethrough,
+	/// Neither bold nor faint.
+	NormalIntensity,
+	NotItalic,
+	NotUnderlined,
+	NotBlinking,
+	NotInverse,
+	NotHidden,
+	NotStrikethrough,
+	Foreground(Color),
+	Background(Color),
+	DefaultForeground,
+	DefaultBackground,
+	/// Any other code, or a malformed extended color.
+	Other(u32),
+}
+
+/// A cursor movement, by a number of cells or to a (1-based) position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CursorMove {
+	Up(u32),
+	Down(u32),
+	Forward(u32),
+	Back(u32),
+	/// Down, to the start of the line.
+	NextLine(u32),
+	/// Up, to the start of the line.
+	PreviousLine(u32),
+	Column(u32),
+	Position { row: u32, column: u32 },
+}
+
+// }}}
+
+// Operating system commands {{{
+
+/// An operating system command: `ESC ]`, followed by data and a `BEL` or
+/// `ESC \`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Osc<'a> {
+	raw: &'a RawStr,
+	data: &'a RawStr,
+}
+
+impl<'a> Osc<'a> {
+	/// The whole sequence, including the `ESC ]` and the terminator.
+	#[inline]
+	pub fn as_raw_str(&self) -> &'a RawStr {
+		self.raw
+	}
+
+	/// The data between the `ESC ]` and the terminator.
+	#[inline]
+	pub fn data(&self) -> &'a RawStr {
+		self.data
+	}
+
+	/// The number at the start of the data, which selects the command.
+	pub fn number(&self) -> Option<u32> {
+		let end = self.data.find(b';').unwrap_or(self.data.len());
+		parse_param(self.data[..end].as_bytes())
+	}
+
+	/// The hyperlink of an `OSC 8` sequence, or `None` if this is something
+	/// else.
+	pub fn hyperlink(&self) -> Option<Hyperlink<'a>> {
+		let mut parts = self.data.splitn(3, b';');
+		if parts.next()? != "8" {
+			return None;
+		}
+		Some(Hyperlink {
+			params: parts.next()?,
+			uri: parts.next()?,
+		})
+	}
+}
+
+/// A hyperlink: `OSC 8 ; params ; uri`.
+///
+/// The text up to the next hyperlink with an empty URI is the link text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hyperlink<'a> {
+	params: &'a RawStr,
+	uri: &'a RawStr,
+}
+
+impl<'a> Hyperlink<'a> {
+	/// The URI, which is empty at the end of a link.
+	#[inline]
+	pub fn uri(&self) -> &'a RawStr {
+		self.uri
+	}
+
+	/// The `:`-separated `key=value` parameters.
+	#[inline]
+	pub fn params(&self) -> &'a RawStr {
+		self.params
+	}
+
+	/// The `id` parameter, which connects separate pieces of the same link.
+	pub fn id(&self) -> Option<&'a RawStr> {
+		self.params.split(b':').find_map(|param| {
+			if param.starts_with("id=") {
+				Some(&param[3..])
+			} else {
+				None
+			}
+		})
+	}
+}
+
+// }}}
+
+/// ANSI escape sequences.
+impl RawStr {
+	/// Split into text and ANSI escape sequences.
+	///
+	/// See the [`ansi`](ansi/index.html) module.
+	#[inline]
+	pub fn ansi_tokens(&self) -> Tokens<'_> {
+		Tokens { rest: self }
+	}
+
+	/// Remove all ANSI escape sequences.
+	///
+	/// This borrows from `self` if there are none.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_str("\x1b]8;;https://example.com\x07link\x1b]8;;\x07 \x1b[4munderlined\x1b[m");
+	/// assert_eq!(&*s.strip_ansi(), "link underlined");
+	/// ```
+	pub fn strip_ansi(&self) -> Cow<'_, RawStr> {
+		if memchr(ESC, self.as_bytes()).is_none() {
+			return Cow::Borrowed(self);
+		}
+		let mut result = RawString::with_capacity(self.len());
+		for token in self.ansi_tokens() {
+			if let Token::Text(text) = token {
+				result.push_str(text);
+			}
+		}
+		Cow::Owned(result)
+	}
+}
+
+// Tests {{{
+
+#[cfg(test)]
+fn tokens(s: &[u8]) -> Vec<Token<'_>> {
+	RawStr::from_bytes(s).ansi_tokens().collect()
+}
+
+#[cfg(test)]
+fn csi(s: &[u8]) -> Csi<'_> {
+	match tokens(s)[..] {
+		[Token::Csi(csi)] => csi,
+		ref t => panic!("not a single CSI: {:?}", t),
+	}
+}
+
+#[test]
+fn test_ansi_tokens() {
+	let raw = |t: &Token| t.as_raw_str().as_bytes().to_vec();
+	let s = b"a\x1b[31mb\x1b]0;title\x1b\\c\x1b(Bd\x1bPq#0\x07e\x1b[\x1b";
+	let t = tokens(s);
+	let parts: Vec<Vec<u8>> = t.iter().map(raw).collect();
+	assert_eq!(
+		parts,
+		[
+			&b"a"[..],
+			b"\x1b[31m",
+			b"b",
+			b"\x1b]0;title\x1b\\",
+			b"c",
+			b"\x1b(B",
+			b"d",
+			b"\x1bPq#0\x07",
+			b"e",
+			b"\x1b[",
+			b"\x1b",
+		]
+	);
+	assert!(t[0].is_text());
+	assert!(matches!(t[1], Token::Csi(_)));
+	assert!(matches!(t[3], Token::Osc(osc) if osc.data() == "0;title" && osc.number() == Some(0)));
+	assert!(matches!(t[5], Token::Escape(_)));
+	assert!(matches!(t[9], Token::Escape(_)));
+
+	// Unterminated and interrupted strings.
+	let parts: Vec<Vec<u8>> = tokens(b"\x1b]8;;x\x1b[1mA\x1b]2;t").iter().map(raw).collect();
+	assert_eq!(parts, [&b"\x1b]8;;x"[..], b"\x1b[1m", b"A", b"\x1b]2;t"]);
+	// Invalid bytes end a control sequence.
+	let parts: Vec<Vec<u8>> = tokens(b"\x1b[1\xFFm").iter().map(raw).collect();
+	assert_eq!(parts, [&b"\x1b[1"[..], b"\xFFm"]);
+	assert!(tokens(b"").is_empty());
+}
+
+#[test]
+fn test_ansi_sgr() {
+	use self::Color::*;
+	assert_eq!(csi(b"\x1b[m").sgr(), Some(vec![Sgr::Reset]));
+	assert_eq!(csi(b"\x1b[0;1;4;22m").sgr(), Some(vec![Sgr::Reset, Sgr::Bold, Sgr::Underline, Sgr::NormalIntensity]));
+	assert_eq!(
+		csi(b"\x1b[32;41;93;104;39;49m").sgr(),
+		Some(vec![
+			Sgr::Foreground(Indexed(2)),
+			Sgr::Background(Indexed(1)),
+			Sgr::Foreground(Indexed(11)),
+			Sgr::Background(Indexed(12)),
+			Sgr::DefaultForeground,
+			Sgr::DefaultBackground,
+		])
+	);
+	assert_eq!(
+		csi(b"\x1b[38;5;208;48;2;1;2;3;1m").sgr(),
+		Some(vec![Sgr::Foreground(Indexed(208)), Sgr::Background(Rgb(1, 2, 3)), Sgr::Bold])
+	);
+	assert_eq!(
+		csi(b"\x1b[38:2::10:20:30;48:2:1:2:3;38:5:7m").sgr(),
+		Some(vec![Sgr::Foreground(Rgb(10, 20, 30)), Sgr::Background(Rgb(1, 2, 3)), Sgr::Foreground(Indexed(7))])
+	);
+	assert_eq!(csi(b"\x1b[38;5m").sgr(), Some(vec![Sgr::Other(38)]));
+	assert_eq!(csi(b"\x1b[1;53m").sgr(), Some(vec![Sgr::Bold, Sgr::Other(53)]));
+	assert_eq!(csi(b"\x1b[1A").sgr(), None);
+	assert_eq!(csi(b"\x1b[?1m").sgr(), None);
+}
+
+#[test]
+fn test_ansi_cursor_and_params() {
+	assert_eq!(csi(b"\x1b[A").cursor_move(), Some(CursorMove::Up(1)));
+	assert_eq!(csi(b"\x1b[0B").cursor_move(), Some(CursorMove::Down(1)));
+	assert_eq!(csi(b"\x1b[12C").cursor_move(), Some(CursorMove::Forward(12)));
+	assert_eq!(csi(b"\x1b[3G").cursor_move(), Some(CursorMove::Column(3)));
+	assert_eq!(csi(b"\x1b[5;10H").cursor_move(), Some(CursorMove::Position { row: 5, column: 10 }));
+	assert_eq!(csi(b"\x1b[;7f").cursor_move(), Some(CursorMove::Position { row: 1, column: 7 }));
+	assert_eq!(csi(b"\x1b[H").cursor_move(), Some(CursorMove::Position { row: 1, column: 1 }));
+	assert_eq!(csi(b"\x1b[2J").cursor_move(), None);
+
+	let c = csi(b"\x1b[?25;;1 h");
+	assert!(c.is_private());
+	assert_eq!(c.parameter_bytes(), "?25;;1");
+	assert_eq!(c.intermediates(), " ");
+	assert_eq!(c.final_byte(), b'h');
+	assert_eq!(c.params().collect::<Vec<_>>(), [Some(25), None, Some(1)]);
+	assert_eq!(csi(b"\x1b[m").params().count(), 0);
+}
+
+#[test]
+fn test_ansi_hyperlink() {
+	let t = tokens(b"\x1b]8;id=a:x=y;http://x/\xFF\x1b\\text\x1b]8;;\x07");
+	let links: Vec<Hyperlink> = t
+		.iter()
+		.filter_map(|t| match *t {
+			Token::Osc(osc) => osc.hyperlink(),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(links.len(), 2);
+	assert_eq!(links[0].uri(), &b"http://x/\xFF"[..]);
+	assert_eq!(links[0].params(), "id=a:x=y");
+	assert_eq!(links[0].id(), Some(RawStr::from_str("a")));
+	assert_eq!(links[1].uri(), "");
+	assert_eq!(links[1].id(), None);
+	assert!(matches!(tokens(b"\x1b]0;x\x07")[0], Token::Osc(osc) if osc.hyperlink().is_none()));
+}
+
+#[test]
+fn test_strip_ansi() {
+	let s = RawStr::from_bytes(b"plain \xFF");
+	assert!(matches!(s.strip_ansi(), Cow::Borrowed(b) if b == s));
+	let s = RawStr::from_bytes(b"\x1b[1;31m\xFFerr\x1b[0m\x1b[2K: x\x1b");
+	assert_eq!(&*s.strip_ansi(), RawStr::from_bytes(b"\xFFerr: x"));
+}
+
+// }}}
@@ -0,0 +1,128 @@
+use super::*;
+
+/// A byte order mark: the encoding of U+FEFF at the start of a text.
+///
+/// See [`RawStr::strip_bom`](../struct.RawStr.html#method.strip_bom) and
+/// [`RawString::prepend_bom`](../struct.RawString.html#method.prepend_bom).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Bom {
+	/// `EF BB BF`
+	Utf8,
+	/// `FF FE`
+	Utf16Le,
+	/// `FE FF`
+	Utf16Be,
+	/// `FF FE 00 00`
+	Utf32Le,
+	/// `00 00 FE FF`
+	Utf32Be,
+}
+
+impl Bom {
+	/// The bytes of this byte order mark.
+	pub fn as_bytes(self) -> &'static [u8] {
+		match self {
+			Bom::Utf8 => b"\xEF\xBB\xBF",
+			Bom::Utf16Le => b"\xFF\xFE",
+			Bom::Utf16Be => b"\xFE\xFF",
+			Bom::Utf32Le => b"\xFF\xFE\0\0",
+			Bom::Utf32Be => b"\0\0\xFE\xFF",
+		}
+	}
+
+	/// The encoding this byte order mark indicates.
+	pub fn encoding(self) -> &'static dyn Encoding {
+		match self {
+			Bom::Utf8 => &UTF_8,
+			Bom::Utf16Le => &UTF_16LE,
+			Bom::Utf16Be => &UTF_16BE,
+			Bom::Utf32Le => &UTF_32LE,
+			Bom::Utf32Be => &UTF_32BE,
+		}
+	}
+
+	/// Find the byte order mark at the start of `bytes`, if any.
+	///
+	/// `FF FE 00 00` is taken as UTF-32LE, although it could also be UTF-16LE
+	/// text starting with a NUL character.
+	fn sniff(bytes: &[u8]) -> Option<Bom> {
+		Some(match *bytes {
+			[0xEF, 0xBB, 0xBF, ..] => Bom::Utf8,
+			[0xFF, 0xFE, 0, 0, ..] => Bom::Utf32Le,
+			[0, 0, 0xFE, 0xFF, ..] => Bom::Utf32Be,
+			[0xFF, 0xFE, ..] => Bom::Utf16Le,
+			[0xFE, 0xFF, ..] => Bom::Utf16Be,
+			_ => return None,
+		})
+	}
+}
+
+impl RawStr {
+	/// Split off the byte order mark at the start, if there is one.
+	///
+	/// Returns the byte order mark, and the rest of the string.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// # use raw_string::encoding::Bom;
+	/// let (bom, rest) = RawStr::from_bytes(b"\xEF\xBB\xBFname,value").strip_bom();
+	/// assert_eq!(bom, Some(Bom::Utf8));
+	/// assert!(rest.starts_with("name"));
+	///
+	/// let (bom, rest) = RawStr::from_str("name,value").strip_bom();
+	/// assert_eq!(bom, None);
+	/// assert_eq!(rest, "name,value");
+	/// ```
+	pub fn strip_bom(&self) -> (Option<Bom>, &RawStr) {
+		match Bom::sniff(self.as_bytes()) {
+			Some(bom) => (Some(bom), &self[bom.as_bytes().len()..]),
+			None => (None, self),
+		}
+	}
+}
+
+impl RawString {
+	/// Insert the given byte order mark at the start.
+	///
+	/// This does not check whether there already is one.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawString;
+	/// # use raw_string::encoding::Bom;
+	/// let mut s = RawString::from("hi");
+	/// s.prepend_bom(Bom::Utf8);
+	/// assert_eq!(s, &b"\xEF\xBB\xBFhi"[..]);
+	/// ```
+	pub fn prepend_bom(&mut self, bom: Bom) {
+		self.insert_str(0, RawStr::from_bytes(bom.as_bytes()));
+	}
+}
+
+// Tests {{{
+
+#[test]
+fn test_strip_bom() {
+	let boms = [Bom::Utf8, Bom::Utf16Le, Bom::Utf16Be, Bom::Utf32Le, Bom::Utf32Be];
+	for &bom in &boms {
+		let mut s = RawString::from("\u{1}x");
+		s.prepend_bom(bom);
+		assert_eq!(s.strip_bom(), (Some(bom), RawStr::from_str("\u{1}x")));
+	}
+
+	// Too short to be a BOM.
+	let s = RawStr::from_bytes(b"\xEF\xBB");
+	assert_eq!(s.strip_bom(), (None, s));
+	let s = RawStr::from_bytes(b"\0\0\xFE");
+	assert_eq!(s.strip_bom(), (None, s));
+	assert_eq!(RawStr::from_str("").strip_bom(), (None, RawStr::from_str("")));
+
+	// Only at the start.
+	let s = RawStr::from_bytes(b"x\xEF\xBB\xBF");
+	assert_eq!(s.strip_bom(), (None, s));
+}
+
+// }}}
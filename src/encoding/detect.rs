@@ -0,0 +1,493 @@
+use super::*;
+use str::{RawStr, Utf8Chunk, Utf8ErrorKind};
+
+/// A possible encoding of some text, as returned by
+/// [`RawStr::detect_encoding`](../struct.RawStr.html#method.detect_encoding).
+#[derive(Clone, Copy, Debug)]
+pub struct Candidate {
+	/// The name of the encoding.
+	///
+	/// For encodings defined by the WHATWG Encoding Standard, this is the name
+	/// used by that standard.
+	pub name: &'static str,
+
+	/// The encoding, if it is built in.
+	///
+	/// `None` for the CJK encodings (Shift_JIS, EUC-JP, EUC-KR, GBK and Big5),
+	/// which can be detected but not decoded by this crate.
+	pub encoding: Option<&'static dyn Encoding>,
+
+	/// How likely this candidate is, from 0.0 to 1.0.
+	pub confidence: f32,
+}
+
+impl Candidate {
+	fn new(encoding: &'static dyn Encoding, confidence: f32) -> Candidate {
+		Candidate {
+			name: encoding.name(),
+			encoding: Some(encoding),
+			confidence,
+		}
+	}
+}
+
+/// The single byte encodings that are considered, most common first.
+static SINGLE_BYTE: [&SingleByteEncoding; 13] = [
+	&WINDOWS_1252,
+	&WINDOWS_1250,
+	&WINDOWS_1251,
+	&WINDOWS_1253,
+	&WINDOWS_1254,
+	&WINDOWS_1255,
+	&WINDOWS_1256,
+	&WINDOWS_1257,
+	&WINDOWS_1258,
+	&WINDOWS_874,
+	&ISO_8859_2,
+	&ISO_8859_5,
+	&ISO_8859_7,
+];
+
+impl RawStr {
+	/// Guess the encoding of this text.
+	///
+	/// Returns the plausible encodings, most likely first. The result is empty
+	/// for empty input, and for data that does not look like text in any of
+	/// the supported encodings, such as mostly NUL bytes.
+	///
+	/// This uses heuristics, in this order:
+	///
+	///  - A byte order mark is decisive.
+	///  - Valid UTF-8 containing non-ASCII characters is almost certainly UTF-8.
+	///  - Many zero bytes at alternating positions indicate UTF-16 or UTF-32.
+	///  - Otherwise, the bytes above `7F` are decoded with the common single
+	///    byte encodings, and scored on whether they produce plausible letters
+	///    for a single script. The byte patterns of the common CJK multi-byte
+	///    encodings are checked as well.
+	///
+	/// The longer the text, the more reliable the result. Pure ASCII text is
+	/// reported as UTF-8.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"\xcf\xf0\xe8\xe2\xe5\xf2, \xec\xe8\xf0!");
+	/// assert_eq!(s.detect_encoding()[0].name, "windows-1251");
+	/// assert_eq!(s.decode_with(s.detect_encoding()[0].encoding.unwrap()), "Привет, мир!");
+	/// ```
+	pub fn detect_encoding(&self) -> Vec<Candidate> {
+		let bytes = self.as_bytes();
+		if bytes.is_empty() {
+			return Vec::new();
+		}
+
+		if let (Some(bom), _) = self.strip_bom() {
+			return vec![Candidate::new(bom.encoding(), 1.0)];
+		}
+
+		let mut candidates = Vec::new();
+
+		let (multi_byte, broken) = count_utf8(self);
+		let nul_ratio = bytes.iter().filter(|&&b| b == 0).count() as f32 / bytes.len() as f32;
+		let utf8 = if broken == 0 && multi_byte > 0 {
+			0.99
+		} else {
+			// A few broken sequences might be damage in otherwise UTF-8 text.
+			let total = (multi_byte + 4 * broken).max(1) as f32;
+			let ascii_bonus = if multi_byte == 0 && broken == 0 { 1.0 } else { 0.0 };
+			(0.9 * multi_byte as f32 / total).max(ascii_bonus)
+		};
+		// NUL bytes hardly ever appear in text.
+		candidates.push(Candidate::new(&UTF_8, utf8 * (1.0 - 2.0 * nul_ratio).max(0.0)));
+
+		candidates.extend(detect_utf16_utf32(bytes));
+
+		if bytes.iter().any(|&b| b >= 0x80) {
+			// Legacy encodings are unlikely if the text is valid UTF-8.
+			let factor = if broken == 0 { 0.5 } else { 1.0 } * (1.0 - 2.0 * nul_ratio).max(0.0);
+			// These come first, so that they win ties: texts in single byte
+			// encodings rarely follow their byte patterns.
+			for (name, confidence) in detect_cjk(bytes) {
+				candidates.push(Candidate {
+					name,
+					encoding: None,
+					confidence: confidence * factor,
+				});
+			}
+			for &encoding in SINGLE_BYTE.iter() {
+				let confidence = score_single_byte(encoding, bytes) * factor;
+				candidates.push(Candidate::new(encoding, confidence));
+			}
+		}
+
+		candidates.retain(|c| c.confidence > 0.0);
+		// A stable sort, so ties are resolved by how common the encoding is.
+		candidates.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
+		candidates
+	}
+}
+
+/// Count the valid non-ASCII characters, and the broken sequences.
+///
+/// A truncated sequence at the very end is not counted, as the text might
+/// have been cut off.
+fn count_utf8(s: &RawStr) -> (usize, usize) {
+	let mut multi_byte = 0;
+	let mut broken = 0;
+	for chunk in s.utf8_chunks() {
+		let Utf8Chunk { valid, broken: b } = chunk;
+		multi_byte += valid.chars().filter(|&c| c >= '\u{80}').count();
+		let at_end = chunk.offset_in(s) + valid.len() + b.len() == s.len();
+		let cut_off = at_end && chunk.error_in(s) == Some(Utf8ErrorKind::TruncatedSequence);
+		if !b.is_empty() && !cut_off {
+			broken += 1;
+		}
+	}
+	(multi_byte, broken)
+}
+
+/// Detect UTF-16 and UTF-32 without BOM, by the zero bytes in mostly-ASCII text.
+fn detect_utf16_utf32(bytes: &[u8]) -> Vec<Candidate> {
+	let mut candidates = Vec::new();
+	if bytes.len() < 4 {
+		return candidates;
+	}
+	// The fraction of zero bytes at each position modulo 4.
+	let mut zeros = [0.0f32; 4];
+	for (i, &b) in bytes.iter().enumerate() {
+		if b == 0 {
+			zeros[i % 4] += 1.0;
+		}
+	}
+	let per_position = (bytes.len() / 4) as f32;
+	for z in zeros.iter_mut() {
+		*z = (*z / per_position).min(1.0);
+	}
+	if bytes.len() % 4 == 0 {
+		let le = (zeros[2] + zeros[3]) / 2.0 - zeros[0];
+		let be = (zeros[0] + zeros[1]) / 2.0 - zeros[3];
+		if le > 0.5 && valid_utf32(bytes, false) {
+			candidates.push(Candidate::new(&UTF_32LE, le));
+		}
+		if be > 0.5 && valid_utf32(bytes, true) {
+			candidates.push(Candidate::new(&UTF_32BE, be));
+		}
+	}
+	if bytes.len() % 2 == 0 {
+		let even = (zeros[0] + zeros[2]) / 2.0;
+		let odd = (zeros[1] + zeros[3]) / 2.0;
+		// UTF-32 has zeros at both positions, which cancel out here.
+		if odd - even > 0.2 && valid_utf16(bytes, false) {
+			candidates.push(Candidate::new(&UTF_16LE, odd - even));
+		}
+		if even - odd > 0.2 && valid_utf16(bytes, true) {
+			candidates.push(Candidate::new(&UTF_16BE, even - odd));
+		}
+	}
+	candidates
+}
+
+fn valid_utf16(bytes: &[u8], big_endian: bool) -> bool {
+	let units = bytes.chunks(2).map(|b| {
+		if big_endian {
+			u16::from_be_bytes([b[0], b[1]])
+		} else {
+			u16::from_le_bytes([b[0], b[1]])
+		}
+	});
+	::std::char::decode_utf16(units).all(|c| c.is_ok())
+}
+
+fn valid_utf32(bytes: &[u8], big_endian: bool) -> bool {
+	bytes.chunks(4).all(|b| {
+		let b = [b[0], b[1], b[2], b[3]];
+		let c = if big_endian {
+			u32::from_be_bytes(b)
+		} else {
+			u32::from_le_bytes(b)
+		};
+		::std::char::from_u32(c).is_some()
+	})
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Script {
+	Latin,
+	Greek,
+	Cyrillic,
+	Hebrew,
+	Arabic,
+	Thai,
+}
+
+/// The script of a letter. `None` for ASCII, and for anything that is not a letter.
+fn script(c: char) -> Option<Script> {
+	if !c.is_alphabetic() {
+		return None;
+	}
+	match c as u32 {
+		0x00C0..=0x024F | 0x1E00..=0x1EFF => Some(Script::Latin),
+		0x0370..=0x03FF => Some(Script::Greek),
+		0x0400..=0x04FF => Some(Script::Cyrillic),
+		0x0590..=0x05FF => Some(Script::Hebrew),
+		0x0600..=0x06FF => Some(Script::Arabic),
+		0x0E00..=0x0E7F => Some(Script::Thai),
+		_ => None,
+	}
+}
+
+/// Whether this is one of the most frequent letters of the languages using
+/// its script.
+///
+/// Only the scripts that are shared by several of the encodings that are
+/// considered need this. For the others, all letters are common.
+fn is_common_letter(script: Script, c: char) -> bool {
+	let letters = match script {
+		Script::Latin => "éèàáíóúñçäöüßâêôãõøåæœčšžěřýůłąęśćźżőűğışİăîțș",
+		Script::Cyrillic => "оеаинтсрвлкмд",
+		Script::Greek => "αοιετσνηυρπκάέίό",
+		_ => return true,
+	};
+	letters.contains(c) || c.to_lowercase().all(|c| letters.contains(c))
+}
+
+/// Hebrew has letters with a separate form at the end of a word.
+fn hebrew_final_form_penalty(prev: char, c: char) -> f32 {
+	let is_final = |c| "ךםןףץ".contains(c);
+	let has_final = |c| "כמנפצ".contains(c);
+	let is_hebrew = |c| script(c) == Some(Script::Hebrew);
+	if (is_final(prev) && is_hebrew(c)) || (has_final(prev) && !is_hebrew(c)) {
+		3.0
+	} else {
+		0.0
+	}
+}
+
+/// Score how plausible it is that `bytes` is encoded in `encoding`.
+///
+/// Only the bytes above `7F` are considered, as all candidates agree on the
+/// rest.
+fn score_single_byte(encoding: &SingleByteEncoding, bytes: &[u8]) -> f32 {
+	let ascii_letters = bytes.iter().filter(|b| b.is_ascii_alphabetic()).count();
+	let mut high = 0;
+	let mut high_letters = 0;
+	let mut scripts = [0usize; 6];
+	let mut score = 0.0;
+	let mut prev = ' ';
+	for &b in bytes {
+		let c = match encoding.decode_byte(b) {
+			Some(c) => c,
+			None => {
+				high += 1;
+				score -= 5.0;
+				prev = ' ';
+				continue;
+			}
+		};
+		if b >= 0x80 {
+			high += 1;
+			if let Some(s) = script(c) {
+				high_letters += 1;
+				scripts[s as usize] += 1;
+				score += if is_common_letter(s, c) { 2.0 } else { 1.0 };
+			} else if ('\u{80}'..'\u{A0}').contains(&c) {
+				// C1 control characters are not used in text.
+				score -= 5.0;
+			} else if !"€‚„…†‡‰‹›‘’“”•–—™¡¢£¤¥§¨©«¬\u{AD}®°±²³´µ¶·¸¹»¼½¾¿×÷\u{A0}".contains(c) {
+				score -= 1.0;
+			}
+		}
+		// Upper case directly after lower case is rare.
+		if prev.is_lowercase() && c.is_uppercase() {
+			score -= 2.0;
+		}
+		// So are words mixing ASCII letters with other scripts.
+		let non_latin = |c| script(c).is_some_and(|s| s != Script::Latin);
+		if (prev.is_ascii_alphabetic() && non_latin(c)) || (non_latin(prev) && c.is_ascii_alphabetic()) {
+			score -= 3.0;
+		}
+		score -= hebrew_final_form_penalty(prev, c);
+		prev = c;
+	}
+	score -= hebrew_final_form_penalty(prev, ' ');
+	if high == 0 {
+		return 0.0;
+	}
+	// Letters should mostly be from a single script.
+	let main_script = scripts.iter().max().cloned().unwrap_or(0);
+	score -= 2.0 * (high_letters - main_script) as f32;
+	// Languages written in Latin script use mostly ASCII letters.
+	let latin = scripts[Script::Latin as usize];
+	if latin > 0 && latin * 2 > ascii_letters + latin {
+		score -= 3.0 * latin as f32;
+	}
+	(score / (2 * high) as f32).clamp(0.0, 1.0) * 0.9
+}
+
+/// Check the byte patterns of the common CJK encodings.
+///
+/// Returns the names of the plausible encodings and their confidence.
+fn detect_cjk(bytes: &[u8]) -> Vec<(&'static str, f32)> {
+	let mut candidates = Vec::new();
+	if let Some(c) = score_shift_jis(bytes) {
+		candidates.push(("Shift_JIS", c));
+	}
+	if let Some(pairs) = euc_pairs(bytes) {
+		// Rows 1 to 3 contain symbols and full-width ASCII, which are not
+		// specific to any language.
+		let total = pairs.iter().filter(|&&(lead, _)| lead >= 0xA4).count().max(1) as f32;
+		let ratio = |f: &dyn Fn(&(u8, u8)) -> bool| pairs.iter().filter(|p| f(p)).count() as f32 / total;
+		// Japanese text has lots of kana, in rows 4 and 5.
+		let kana = ratio(&|&(lead, _)| lead == 0xA4 || lead == 0xA5);
+		candidates.push(("EUC-JP", (2.0 * kana).min(1.0)));
+		// Korean text is mostly hangul, in rows 16 to 40.
+		let hangul = ratio(&|&(lead, _)| (0xB0..=0xC8).contains(&lead));
+		let beyond_hangul = ratio(&|&(lead, _)| (0xC9..=0xF7).contains(&lead));
+		candidates.push(("EUC-KR", (hangul - beyond_hangul - kana).max(0.0)));
+		// Chinese hanzi are spread over rows 16 to 87.
+		let hanzi = ratio(&|&(lead, _)| (0xB0..=0xF7).contains(&lead));
+		candidates.push(("GBK", (hanzi - kana).max(0.0) * (3.0 * beyond_hangul).min(1.0)));
+	}
+	if let Some(c) = score_big5(bytes) {
+		candidates.push(("Big5", c));
+	}
+	// Texts in these encodings consist mostly of non-ASCII characters, while
+	// accented letters in Latin text are surrounded by ASCII letters.
+	let high = bytes.iter().filter(|&&b| b >= 0x80).count() as f32;
+	let ascii_letters = bytes.iter().filter(|b| b.is_ascii_alphabetic()).count() as f32;
+	let density = high / (high + ascii_letters);
+	// Text that follows these byte patterns is hardly ever in any other
+	// encoding, so this can be a bit more confident than the single byte
+	// encodings.
+	for c in &mut candidates {
+		c.1 *= (1.5 * density).min(1.0) * 0.95;
+	}
+	candidates
+}
+
+/// The double byte characters of EUC-JP, EUC-KR and GB2312, if the bytes
+/// follow that pattern.
+fn euc_pairs(bytes: &[u8]) -> Option<Vec<(u8, u8)>> {
+	let mut pairs = Vec::new();
+	let mut i = 0;
+	while i < bytes.len() {
+		match bytes[i] {
+			0x00..=0x7F => i += 1,
+			lead @ 0xA1..=0xFE => {
+				match bytes.get(i + 1) {
+					Some(&trail @ 0xA1..=0xFE) => pairs.push((lead, trail)),
+					Some(_) => return None,
+					None => {}
+				}
+				i += 2;
+			}
+			_ => return None,
+		}
+	}
+	Some(pairs)
+}
+
+fn score_shift_jis(bytes: &[u8]) -> Option<f32> {
+	let mut pairs = 0;
+	let mut kana = 0;
+	let mut i = 0;
+	while i < bytes.len() {
+		match bytes[i] {
+			0x00..=0x7F => i += 1,
+			// Half-width katakana, which are rarely used.
+			0xA1..=0xDF => {
+				pairs += 1;
+				i += 1;
+			}
+			lead @ 0x81..=0x9F | lead @ 0xE0..=0xFC => {
+				match bytes.get(i + 1) {
+					Some(&trail @ 0x40..=0x7E) | Some(&trail @ 0x80..=0xFC) => {
+						pairs += 1;
+						if (lead == 0x82 && trail >= 0x9F) || (lead == 0x83 && trail <= 0x96) {
+							kana += 1;
+						}
+					}
+					Some(_) => return None,
+					None => {}
+				}
+				i += 2;
+			}
+			_ => return None,
+		}
+	}
+	Some((2.0 * kana as f32 / pairs.max(1) as f32).min(1.0))
+}
+
+fn score_big5(bytes: &[u8]) -> Option<f32> {
+	let mut pairs = 0;
+	let mut low_trail = 0;
+	let mut i = 0;
+	while i < bytes.len() {
+		match bytes[i] {
+			0x00..=0x7F => i += 1,
+			0x81..=0xFE => {
+				match bytes.get(i + 1) {
+					Some(&(0x40..=0x7E)) => {
+						pairs += 1;
+						low_trail += 1;
+					}
+					Some(&(0xA1..=0xFE)) => pairs += 1,
+					Some(_) => return None,
+					None => {}
+				}
+				i += 2;
+			}
+			_ => return None,
+		}
+	}
+	// About 40% of the characters have a trail byte below 7F, which never
+	// happens in the EUC encodings. Most of the others have a high trail byte.
+	let low_ratio = low_trail as f32 / pairs.max(1) as f32;
+	Some((2.5 * low_ratio).min(1.0) * (2.0 * (1.0 - low_ratio)).min(1.0))
+}
+
+// Tests {{{
+
+#[cfg(test)]
+fn detect(bytes: &[u8]) -> Vec<&'static str> {
+	RawStr::from_bytes(bytes)
+		.detect_encoding()
+		.iter()
+		.map(|c| c.name)
+		.collect()
+}
+
+#[test]
+fn test_detect_encoding() {
+	assert_eq!(detect(b""), Vec::<&str>::new());
+	assert_eq!(detect(b"\0\0\0"), Vec::<&str>::new());
+	assert_eq!(detect(b"\xEF\xBB\xBFabc"), ["UTF-8"]);
+	assert_eq!(detect(b"\xFF\xFEa\x00"), ["UTF-16LE"]);
+	assert_eq!(detect(b"\xFF\xFE\x00\x00a\x00\x00\x00"), ["UTF-32LE"]);
+	assert_eq!(detect(b"plain ascii")[0], "UTF-8");
+	assert_eq!(detect("Grüße, 世界".as_bytes())[0], "UTF-8");
+	assert_eq!(detect(b"h\x00e\x00l\x00l\x00o\x00")[0], "UTF-16LE");
+	assert_eq!(detect(b"\x00h\x00e\x00l\x00l\x00o")[0], "UTF-16BE");
+	assert_eq!(detect(b"h\x00\x00\x00i\x00\x00\x00")[0], "UTF-32LE");
+	assert_eq!(detect(b"Gr\xFC\xDFe aus K\xF6ln, sch\xF6ne Gr\xFC\xDFe")[0], "windows-1252");
+	assert_eq!(detect(b"\x93Quoted\x94 \x96 caf\xE9")[0], "windows-1252");
+	assert_eq!(detect(b"\xcf\xf0\xe8\xe2\xe5\xf2, \xec\xe8\xf0!")[0], "windows-1251");
+	assert_eq!(detect(b"\xc3\xe5\xe9\xdc \xf3\xef\xf5 \xea\xfc\xf3\xec\xe5")[0], "windows-1253");
+	let czech = b"P\xf8\xedli\x9a \x9elu\x9dou\xe8k\xfd k\xf9\xf2 \xfap\xecl \xef\xe1belsk\xe9 \xf3dy";
+	assert_eq!(detect(czech)[0], "windows-1250");
+	assert_eq!(detect(b"\xdcnl\xfc \xfeark\xfdc\xfd \xddstanbul'da")[0], "windows-1254");
+	assert_eq!(detect(b"\xf9\xec\xe5\xed \xf2\xe5\xec\xed, \xee\xe4 \xf9\xec\xe5\xee\xea")[0], "windows-1255");
+	assert_eq!(detect(b"\xe3\xd1\xcd\xc8\xc7 \xc8\xc7\xe1\xda\xc7\xe1\xe3")[0], "windows-1256");
+	// "konnichiwa" in hiragana.
+	assert_eq!(detect(b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd")[0], "Shift_JIS");
+	assert_eq!(detect(b"\xa4\xb3\xa4\xf3\xa4\xcb\xa4\xc1\xa4\xcf")[0], "EUC-JP");
+	// "annyeonghaseyo" in hangul.
+	assert_eq!(detect(b"\xbe\xc8\xb3\xe7\xc7\xcf\xbc\xbc\xbf\xe4")[0], "EUC-KR");
+	let gbk = b"\xc4\xe3\xba\xc3\xa3\xac\xca\xc0\xbd\xe7\xa1\xa3\xce\xd2\xc3\xc7\xca\xc7\xd6\xd0\xb9\xfa\xc8\xcb";
+	assert_eq!(detect(gbk)[0], "GBK");
+	let big5 = b"\xa7\x41\xa6\x6e\xa1\x41\xa5\x40\xac\xc9\xa1\x43\xa7\xda\xad\xcc\xac\x4f\xa4\xa4\xb0\xea\xa4\x48";
+	assert_eq!(detect(big5)[0], "Big5");
+}
+
+// }}}
@@ -0,0 +1,287 @@
+//! Conversions between `RawStr` and other character encodings.
+//!
+//! All encodings implement the [`Encoding`](trait.Encoding.html) trait.
+//! The built-in ones are available as statics in this module, and can be
+//! looked up by their WHATWG label with [`for_label`](fn.for_label.html).
+//!
+//! # Example
+//!
+//! ```
+//! # use raw_string::{RawStr, RawString};
+//! # use raw_string::encoding;
+//! let raw = RawStr::from_bytes(b"caf\xE9 \x80");
+//! assert_eq!(raw.decode_windows1252(), "café €");
+//!
+//! let latin2 = encoding::for_label("latin2").unwrap();
+//! assert_eq!(latin2.name(), "ISO-8859-2");
+//! assert_eq!(raw.decode_with(latin2), "café \u{80}");
+//!
+//! let encoded = RawString::encode_latin1("café").unwrap();
+//! assert_eq!(encoded, &b"caf\xE9"[..]);
+//! assert!(RawString::encode_latin1("€").is_err());
+//! ```
+
+use std;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use str::RawStr;
+use string::RawString;
+
+mod bom;
+mod detect;
+mod single_byte;
+mod tables;
+mod utf;
+
+pub use self::bom::*;
+pub use self::detect::*;
+pub use self::single_byte::*;
+pub use self::utf::*;
+
+/// A character encoding.
+///
+/// Implemented by all built-in encodings, such as [`UTF_8`](static.UTF_8.html)
+/// and [`WINDOWS_1252`](static.WINDOWS_1252.html), but can also be implemented
+/// for other encodings.
+///
+/// Only `name`, `new_decoder` and `new_encoder` need to be implemented.
+/// The other methods have default implementations based on those.
+pub trait Encoding: Debug + Sync {
+	/// The canonical name of this encoding.
+	///
+	/// For encodings defined by the WHATWG Encoding Standard, this is the name
+	/// used by that standard, such as `"UTF-8"` or `"windows-1252"`.
+	fn name(&self) -> &'static str;
+
+	/// Create a decoder, to decode input that arrives in pieces.
+	fn new_decoder(&self) -> Box<dyn Decoder>;
+
+	/// Create an encoder, to encode input that arrives in pieces.
+	fn new_encoder(&self) -> Box<dyn Encoder>;
+
+	/// Decode the given bytes.
+	///
+	/// Malformed input is decoded as U+FFFD, the replacement character.
+	fn decode<'a>(&self, input: &'a RawStr) -> Cow<'a, str> {
+		let mut output = String::with_capacity(input.len());
+		self.new_decoder().decode(input, &mut output, true);
+		Cow::Owned(output)
+	}
+
+	/// Encode the given string.
+	///
+	/// Fails on the first character that can not be represented in this
+	/// encoding.
+	fn encode(&self, input: &str) -> Result<RawString, EncodeError> {
+		let mut output = RawString::with_capacity(input.len());
+		self.new_encoder().encode(input, &mut output)?;
+		Ok(output)
+	}
+}
+
+/// The state of decoding a stream of bytes.
+///
+/// See [`Encoding::new_decoder`](trait.Encoding.html#tymethod.new_decoder).
+pub trait Decoder {
+	/// Decode the next piece of input, and append it to `output`.
+	///
+	/// A character that is split over multiple pieces is kept until the rest of
+	/// it arrives. Set `last` for the last piece, so that any incomplete
+	/// character left at the end is decoded as U+FFFD instead of being kept.
+	fn decode(&mut self, input: &RawStr, output: &mut String, last: bool);
+}
+
+/// The state of encoding a stream of characters.
+///
+/// See [`Encoding::new_encoder`](trait.Encoding.html#tymethod.new_encoder).
+pub trait Encoder {
+	/// Encode the next piece of input, and append it to `output`.
+	///
+	/// On failure, everything before the character that could not be encoded
+	/// has been appended to `output`. The index in the error is relative to
+	/// this piece of input.
+	fn encode(&mut self, input: &str, output: &mut RawString) -> Result<(), EncodeError>;
+}
+
+/// The error returned when a character cannot be encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncodeError {
+	index: usize,
+	character: char,
+	encoding: &'static str,
+}
+
+impl EncodeError {
+	/// The byte index in the input `str` of the character that could not be encoded.
+	#[inline]
+	pub fn index(&self) -> usize {
+		self.index
+	}
+
+	/// The character that could not be encoded.
+	#[inline]
+	pub fn character(&self) -> char {
+		self.character
+	}
+
+	/// The name of the encoding that could not represent the character.
+	#[inline]
+	pub fn encoding(&self) -> &'static str {
+		self.encoding
+	}
+}
+
+impl Display for EncodeError {
+	fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+		write!(
+			f,
+			"character {:?} (U+{:04X}) at index {} cannot be encoded in {}",
+			self.character, self.character as u32, self.index, self.encoding
+		)
+	}
+}
+
+impl Error for EncodeError {}
+
+/// Look up an encoding by one of its labels, as defined by the WHATWG
+/// Encoding Standard.
+///
+/// Leading and trailing ASCII whitespace is ignored, and the comparison is
+/// ASCII case insensitive. Returns `None` for unknown labels, and for
+/// encodings that are not built in.
+///
+/// Note that, as the standard specifies, `"ascii"`, `"latin1"` and
+/// `"iso-8859-1"` are labels of [`WINDOWS_1252`](static.WINDOWS_1252.html),
+/// and `"iso-8859-9"` is a label of [`WINDOWS_1254`](static.WINDOWS_1254.html).
+/// The encodings that are not part of the standard, such as
+/// [`LATIN1`](static.LATIN1.html) and [`UTF_32LE`](static.UTF_32LE.html),
+/// can only be used through their statics.
+pub fn for_label(label: &str) -> Option<&'static dyn Encoding> {
+	let label = label.trim_matches(|c| "\t\n\x0C\r ".contains(c));
+	if label.len() > 20 {
+		return None;
+	}
+	let label = label.to_ascii_lowercase();
+	Some(match &label[..] {
+		"unicode-1-1-utf-8" | "unicode11utf8" | "unicode20utf8" | "utf-8" | "utf8"
+		| "x-unicode20utf8" => &UTF_8,
+		"csisolatin2" | "iso-8859-2" | "iso-ir-101" | "iso8859-2" | "iso88592" | "iso_8859-2"
+		| "iso_8859-2:1987" | "l2" | "latin2" => &ISO_8859_2,
+		"csisolatin3" | "iso-8859-3" | "iso-ir-109" | "iso8859-3" | "iso88593" | "iso_8859-3"
+		| "iso_8859-3:1988" | "l3" | "latin3" => &ISO_8859_3,
+		"csisolatin4" | "iso-8859-4" | "iso-ir-110" | "iso8859-4" | "iso88594" | "iso_8859-4"
+		| "iso_8859-4:1988" | "l4" | "latin4" => &ISO_8859_4,
+		"csisolatincyrillic" | "cyrillic" | "iso-8859-5" | "iso-ir-144" | "iso8859-5"
+		| "iso88595" | "iso_8859-5" | "iso_8859-5:1988" => &ISO_8859_5,
+		"arabic" | "asmo-708" | "csiso88596e" | "csiso88596i" | "csisolatinarabic"
+		| "ecma-114" | "iso-8859-6" | "iso-8859-6-e" | "iso-8859-6-i" | "iso-ir-127"
+		| "iso8859-6" | "iso88596" | "iso_8859-6" | "iso_8859-6:1987" => &ISO_8859_6,
+		"csisolatingreek" | "ecma-118" | "elot_928" | "greek" | "greek8" | "iso-8859-7"
+		| "iso-ir-126" | "iso8859-7" | "iso88597" | "iso_8859-7" | "iso_8859-7:1987"
+		| "sun_eu_greek" => &ISO_8859_7,
+		"csiso88598e" | "csisolatinhebrew" | "hebrew" | "iso-8859-8" | "iso-8859-8-e"
+		| "iso-ir-138" | "iso8859-8" | "iso88598" | "iso_8859-8" | "iso_8859-8:1988"
+		| "visual" => &ISO_8859_8,
+		"csiso88598i" | "iso-8859-8-i" | "logical" => &ISO_8859_8_I,
+		"csisolatin6" | "iso-8859-10" | "iso-ir-157" | "iso8859-10" | "iso885910" | "l6"
+		| "latin6" => &ISO_8859_10,
+		"iso-8859-13" | "iso8859-13" | "iso885913" => &ISO_8859_13,
+		"iso-8859-14" | "iso8859-14" | "iso885914" => &ISO_8859_14,
+		"csisolatin9" | "iso-8859-15" | "iso8859-15" | "iso885915" | "iso_8859-15" | "l9" => {
+			&ISO_8859_15
+		}
+		"iso-8859-16" => &ISO_8859_16,
+		"dos-874" | "iso-8859-11" | "iso8859-11" | "iso885911" | "tis-620" | "windows-874" => {
+			&WINDOWS_874
+		}
+		"cp1250" | "windows-1250" | "x-cp1250" => &WINDOWS_1250,
+		"cp1251" | "windows-1251" | "x-cp1251" => &WINDOWS_1251,
+		"ansi_x3.4-1968" | "ascii" | "cp1252" | "cp819" | "csisolatin1" | "ibm819"
+		| "iso-8859-1" | "iso-ir-100" | "iso8859-1" | "iso88591" | "iso_8859-1"
+		| "iso_8859-1:1987" | "l1" | "latin1" | "us-ascii" | "windows-1252" | "x-cp1252" => {
+			&WINDOWS_1252
+		}
+		"cp1253" | "windows-1253" | "x-cp1253" => &WINDOWS_1253,
+		"cp1254" | "csisolatin5" | "iso-8859-9" | "iso-ir-148" | "iso8859-9" | "iso88599"
+		| "iso_8859-9" | "iso_8859-9:1989" | "l5" | "latin5" | "windows-1254" | "x-cp1254" => {
+			&WINDOWS_1254
+		}
+		"cp1255" | "windows-1255" | "x-cp1255" => &WINDOWS_1255,
+		"cp1256" | "windows-1256" | "x-cp1256" => &WINDOWS_1256,
+		"cp1257" | "windows-1257" | "x-cp1257" => &WINDOWS_1257,
+		"cp1258" | "windows-1258" | "x-cp1258" => &WINDOWS_1258,
+		"unicodefffe" | "utf-16be" => &UTF_16BE,
+		"csunicode" | "iso-10646-ucs-2" | "ucs-2" | "unicode" | "unicodefeff" | "utf-16"
+		| "utf-16le" => &UTF_16LE,
+		_ => return None,
+	})
+}
+
+impl RawStr {
+	/// Decode using the given encoding.
+	///
+	/// Malformed input is decoded as U+FFFD, the replacement character.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// # use raw_string::encoding;
+	/// let s = RawStr::from_bytes(b"h\0i\0");
+	/// assert_eq!(s.decode_with(&encoding::UTF_16LE), "hi");
+	/// ```
+	#[inline]
+	pub fn decode_with(&self, encoding: &dyn Encoding) -> Cow<'_, str> {
+		encoding.decode(self)
+	}
+}
+
+impl RawString {
+	/// Encode a string using the given encoding.
+	///
+	/// Fails on the first character that can not be represented in the
+	/// encoding.
+	#[inline]
+	pub fn encode_with(encoding: &dyn Encoding, s: &str) -> Result<RawString, EncodeError> {
+		encoding.encode(s)
+	}
+}
+
+/// The length of the ASCII prefix of `bytes`.
+#[inline]
+fn ascii_len(bytes: &[u8]) -> usize {
+	bytes.iter().position(|&b| b >= 0x80).unwrap_or(bytes.len())
+}
+
+// Tests {{{
+
+#[test]
+fn test_for_label() {
+	assert_eq!(for_label(" UTF8\n").unwrap().name(), "UTF-8");
+	assert_eq!(for_label("latin1").unwrap().name(), "windows-1252");
+	assert_eq!(for_label("ISO-8859-9").unwrap().name(), "windows-1254");
+	assert_eq!(for_label("utf-16").unwrap().name(), "UTF-16LE");
+	assert_eq!(for_label("koi8-r").map(|e| e.name()), None);
+	assert_eq!(for_label("").map(|e| e.name()), None);
+}
+
+#[test]
+fn test_streaming() {
+	let input = "€ and 𝄞";
+	let all: &[&dyn Encoding] = &[&UTF_8, &UTF_16LE, &UTF_16BE, &UTF_32LE, &UTF_32BE];
+	for &encoding in all {
+		let encoded = encoding.encode(input).unwrap();
+		assert_eq!(encoding.decode(&encoded), input);
+		// Feed the decoder one byte at a time.
+		let mut decoder = encoding.new_decoder();
+		let mut output = String::new();
+		for i in 0..encoded.len() {
+			decoder.decode(&encoded[i..i + 1], &mut output, false);
+		}
+		decoder.decode(RawStr::from_str(""), &mut output, true);
+		assert_eq!(output, input, "{}", encoding.name());
+	}
+}
+
+// }}}
@@ -0,0 +1,517 @@
+use std;
+use std::borrow::Cow;
+use std::fmt::{Debug, Display, Formatter, Write};
+use std::str::from_utf8_unchecked;
+
+use super::{ascii_len, tables, Decoder, EncodeError, Encoder, Encoding};
+use str::{RawStr, Utf8Chunk};
+use string::RawString;
+
+/// A character encoding that maps every byte to at most one character.
+///
+/// All of them are a superset of ASCII: only the bytes `80` to `FF` differ
+/// between them. Unmapped bytes are decoded as U+FFFD.
+///
+/// For the Windows code pages, the bytes `80` to `9F` that Windows leaves
+/// undefined map to the C1 control characters with the same value, as the
+/// WHATWG Encoding Standard specifies.
+#[derive(Clone)]
+pub struct SingleByteEncoding {
+	name: &'static str,
+	/// The code points for the bytes `80` to `FF`. Zero if unmapped.
+	high: &'static [u16; 128],
+	/// The mapped code points with their bytes, sorted by code point.
+	reverse: &'static [(u16, u8)],
+}
+
+/// ASCII.
+///
+/// Not part of the WHATWG Encoding Standard, where `"ascii"` is a label of
+/// Windows-1252. The bytes `80` to `FF` are not mapped.
+pub static ASCII: SingleByteEncoding = SingleByteEncoding {
+	name: "US-ASCII",
+	high: &tables::ASCII,
+	reverse: &tables::ASCII_REVERSE,
+};
+
+/// ISO-8859-1, also known as Latin-1.
+///
+/// Every byte maps to the code point with the same value.
+/// Not part of the WHATWG Encoding Standard, where `"iso-8859-1"` is a label
+/// of Windows-1252.
+pub static LATIN1: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-1",
+	high: &tables::LATIN1,
+	reverse: &tables::LATIN1_REVERSE,
+};
+
+/// ISO-8859-2, also known as Latin-2 (Central European).
+pub static ISO_8859_2: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-2",
+	high: &tables::ISO_8859_2,
+	reverse: &tables::ISO_8859_2_REVERSE,
+};
+
+/// ISO-8859-3, also known as Latin-3 (South European).
+pub static ISO_8859_3: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-3",
+	high: &tables::ISO_8859_3,
+	reverse: &tables::ISO_8859_3_REVERSE,
+};
+
+/// ISO-8859-4, also known as Latin-4 (North European).
+pub static ISO_8859_4: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-4",
+	high: &tables::ISO_8859_4,
+	reverse: &tables::ISO_8859_4_REVERSE,
+};
+
+/// ISO-8859-5 (Cyrillic).
+pub static ISO_8859_5: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-5",
+	high: &tables::ISO_8859_5,
+	reverse: &tables::ISO_8859_5_REVERSE,
+};
+
+/// ISO-8859-6 (Arabic).
+pub static ISO_8859_6: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-6",
+	high: &tables::ISO_8859_6,
+	reverse: &tables::ISO_8859_6_REVERSE,
+};
+
+/// ISO-8859-7 (Greek).
+pub static ISO_8859_7: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-7",
+	high: &tables::ISO_8859_7,
+	reverse: &tables::ISO_8859_7_REVERSE,
+};
+
+/// ISO-8859-8 (Hebrew, visual order).
+pub static ISO_8859_8: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-8",
+	high: &tables::ISO_8859_8,
+	reverse: &tables::ISO_8859_8_REVERSE,
+};
+
+/// ISO-8859-8-I (Hebrew, logical order).
+///
+/// The same mapping as ISO-8859-8.
+pub static ISO_8859_8_I: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-8-I",
+	high: &tables::ISO_8859_8,
+	reverse: &tables::ISO_8859_8_REVERSE,
+};
+
+/// ISO-8859-9, also known as Latin-5 (Turkish).
+///
+/// Not part of the WHATWG Encoding Standard, where `"iso-8859-9"` is a label
+/// of Windows-1254.
+pub static ISO_8859_9: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-9",
+	high: &tables::ISO_8859_9,
+	reverse: &tables::ISO_8859_9_REVERSE,
+};
+
+/// ISO-8859-10, also known as Latin-6 (Nordic).
+pub static ISO_8859_10: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-10",
+	high: &tables::ISO_8859_10,
+	reverse: &tables::ISO_8859_10_REVERSE,
+};
+
+/// ISO-8859-11 (Thai).
+///
+/// Not part of the WHATWG Encoding Standard, where `"iso-8859-11"` is a
+/// label of Windows-874.
+pub static ISO_8859_11: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-11",
+	high: &tables::ISO_8859_11,
+	reverse: &tables::ISO_8859_11_REVERSE,
+};
+
+/// ISO-8859-13, also known as Latin-7 (Baltic).
+pub static ISO_8859_13: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-13",
+	high: &tables::ISO_8859_13,
+	reverse: &tables::ISO_8859_13_REVERSE,
+};
+
+/// ISO-8859-14, also known as Latin-8 (Celtic).
+pub static ISO_8859_14: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-14",
+	high: &tables::ISO_8859_14,
+	reverse: &tables::ISO_8859_14_REVERSE,
+};
+
+/// ISO-8859-15, also known as Latin-9 (Western European with €).
+pub static ISO_8859_15: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-15",
+	high: &tables::ISO_8859_15,
+	reverse: &tables::ISO_8859_15_REVERSE,
+};
+
+/// ISO-8859-16, also known as Latin-10 (South-Eastern European).
+pub static ISO_8859_16: SingleByteEncoding = SingleByteEncoding {
+	name: "ISO-8859-16",
+	high: &tables::ISO_8859_16,
+	reverse: &tables::ISO_8859_16_REVERSE,
+};
+
+/// Windows-874 (Thai).
+pub static WINDOWS_874: SingleByteEncoding = SingleByteEncoding {
+	name: "windows-874",
+	high: &tables::WINDOWS_874,
+	reverse: &tables::WINDOWS_874_REVERSE,
+};
+
+/// Windows-1250 (Central European).
+pub static WINDOWS_1250: SingleByteEncoding = SingleByteEncoding {
+	name: "windows-1250",
+	high: &tables::WINDOWS_1250,
+	reverse: &tables::WINDOWS_1250_REVERSE,
+};
+
+/// Windows-1251 (Cyrillic).
+pub static WINDOWS_1251: SingleByteEncoding = SingleByteEncoding {
+	name: "windows-1251",
+	high: &tables::WINDOWS_1251,
+	reverse: &tables::WINDOWS_1251_REVERSE,
+};
+
+/// Windows-1252 (Western European).
+///
+/// This is Latin-1 with printable characters instead of most of the C1
+/// control characters.
+pub static WINDOWS_1252: SingleByteEncoding = SingleByteEncoding {
+	name: "windows-1252",
+	high: &tables::WINDOWS_1252,
+	reverse: &tables::WINDOWS_1252_REVERSE,
+};
+
+/// Windows-1253 (Greek).
+pub static WINDOWS_1253: SingleByteEncoding = SingleByteEncoding {
+	name: "windows-1253",
+	high: &tables::WINDOWS_1253,
+	reverse: &tables::WINDOWS_1253_REVERSE,
+};
+
+/// Windows-1254 (Turkish).
+pub static WINDOWS_1254: SingleByteEncoding = SingleByteEncoding {
+	name: "windows-1254",
+	high: &tables::WINDOWS_1254,
+	reverse: &tables::WINDOWS_1254_REVERSE,
+};
+
+/// Windows-1255 (Hebrew).
+pub static WINDOWS_1255: SingleByteEncoding = SingleByteEncoding {
+	name: "windows-1255",
+	high: &tables::WINDOWS_1255,
+	reverse: &tables::WINDOWS_1255_REVERSE,
+};
+
+/// Windows-1256 (Arabic).
+pub static WINDOWS_1256: SingleByteEncoding = SingleByteEncoding {
+	name: "windows-1256",
+	high: &tables::WINDOWS_1256,
+	reverse: &tables::WINDOWS_1256_REVERSE,
+};
+
+/// Windows-1257 (Baltic).
+pub static WINDOWS_1257: SingleByteEncoding = SingleByteEncoding {
+	name: "windows-1257",
+	high: &tables::WINDOWS_1257,
+	reverse: &tables::WINDOWS_1257_REVERSE,
+};
+
+/// Windows-1258 (Vietnamese).
+pub static WINDOWS_1258: SingleByteEncoding = SingleByteEncoding {
+	name: "windows-1258",
+	high: &tables::WINDOWS_1258,
+	reverse: &tables::WINDOWS_1258_REVERSE,
+};
+
+impl SingleByteEncoding {
+	/// The name of this encoding.
+	#[inline]
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	/// Decode a single byte, if it is mapped.
+	#[inline]
+	pub fn decode_byte(&self, b: u8) -> Option<char> {
+		if b < 0x80 {
+			Some(char::from(b))
+		} else {
+			match self.high[usize::from(b - 0x80)] {
+				0 => None,
+				c => std::char::from_u32(u32::from(c)),
+			}
+		}
+	}
+
+	/// Encode a single character, if it can be represented.
+	#[inline]
+	pub fn encode_char(&self, c: char) -> Option<u8> {
+		let c = c as u32;
+		if c < 0x80 {
+			return Some(c as u8);
+		}
+		// Most encodings map most of their upper half to the same code point.
+		if c < 0x100 && u32::from(self.high[c as usize - 0x80]) == c {
+			return Some(c as u8);
+		}
+		self.reverse
+			.binary_search_by_key(&c, |&(h, _)| u32::from(h))
+			.ok()
+			.map(|i| self.reverse[i].1)
+	}
+
+	/// Decode the given bytes.
+	///
+	/// Unmapped bytes are decoded as U+FFFD, the replacement character.
+	///
+	/// Returns a borrowed `str` if the input is all ASCII.
+	pub fn decode<'a>(&self, s: &'a RawStr) -> Cow<'a, str> {
+		let bytes = s.as_bytes();
+		let n = ascii_len(bytes);
+		if n == bytes.len() {
+			return Cow::Borrowed(unsafe { from_utf8_unchecked(bytes) });
+		}
+		let mut out = String::with_capacity(bytes.len() + (bytes.len() - n));
+		out.push_str(unsafe { from_utf8_unchecked(&bytes[..n]) });
+		let mut rest = &bytes[n..];
+		while let Some((&b, tail)) = rest.split_first() {
+			out.push(self.decode_byte(b).unwrap_or(std::char::REPLACEMENT_CHARACTER));
+			let n = ascii_len(tail);
+			out.push_str(unsafe { from_utf8_unchecked(&tail[..n]) });
+			rest = &tail[n..];
+		}
+		Cow::Owned(out)
+	}
+
+	/// Encode the given string.
+	///
+	/// Fails on the first character that can not be represented in this encoding.
+	pub fn encode(&self, s: &str) -> Result<RawString, EncodeError> {
+		let mut out = Vec::with_capacity(s.len());
+		self.encode_into(s, &mut out)?;
+		Ok(RawString::from_bytes(out))
+	}
+
+	/// Encode the given string, appending to `out`.
+	///
+	/// On failure, everything before the unrepresentable character has been
+	/// appended.
+	fn encode_into(&self, s: &str, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+		let bytes = s.as_bytes();
+		let mut i = 0;
+		loop {
+			let n = ascii_len(&bytes[i..]);
+			out.extend_from_slice(&bytes[i..i + n]);
+			i += n;
+			let c = match s[i..].chars().next() {
+				Some(c) => c,
+				None => break,
+			};
+			match self.encode_char(c) {
+				Some(b) => out.push(b),
+				None => {
+					return Err(EncodeError {
+						index: i,
+						character: c,
+						encoding: self.name,
+					})
+				}
+			}
+			i += c.len_utf8();
+		}
+		Ok(())
+	}
+}
+
+impl Debug for SingleByteEncoding {
+	fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+		f.debug_tuple("SingleByteEncoding").field(&self.name).finish()
+	}
+}
+
+impl Encoding for SingleByteEncoding {
+	fn name(&self) -> &'static str {
+		self.name
+	}
+
+	fn new_decoder(&self) -> Box<dyn Decoder> {
+		Box::new(SingleByteCoder {
+			encoding: self.clone(),
+		})
+	}
+
+	fn new_encoder(&self) -> Box<dyn Encoder> {
+		Box::new(SingleByteCoder {
+			encoding: self.clone(),
+		})
+	}
+
+	fn decode<'a>(&self, input: &'a RawStr) -> Cow<'a, str> {
+		SingleByteEncoding::decode(self, input)
+	}
+
+	fn encode(&self, input: &str) -> Result<RawString, EncodeError> {
+		SingleByteEncoding::encode(self, input)
+	}
+}
+
+/// Decoder and encoder for a `SingleByteEncoding`, which need no state.
+///
+/// Cloning the encoding only copies references to its static tables.
+struct SingleByteCoder {
+	encoding: SingleByteEncoding,
+}
+
+impl Decoder for SingleByteCoder {
+	fn decode(&mut self, input: &RawStr, output: &mut String, _last: bool) {
+		output.push_str(&self.encoding.decode(input));
+	}
+}
+
+impl Encoder for SingleByteCoder {
+	fn encode(&mut self, input: &str, output: &mut RawString) -> Result<(), EncodeError> {
+		self.encoding.encode_into(input, output.as_mut_bytes())
+	}
+}
+
+impl RawStr {
+	/// Decode as ISO-8859-1 (Latin-1).
+	///
+	/// Never fails, as every byte maps to the code point with the same value.
+	/// Returns a borrowed `str` if the contents are all ASCII.
+	#[inline]
+	pub fn decode_latin1(&self) -> Cow<'_, str> {
+		LATIN1.decode(self)
+	}
+
+	/// Decode as Windows-1252.
+	///
+	/// Never fails. See [`WINDOWS_1252`](encoding/static.WINDOWS_1252.html).
+	/// Returns a borrowed `str` if the contents are all ASCII.
+	#[inline]
+	pub fn decode_windows1252(&self) -> Cow<'_, str> {
+		WINDOWS_1252.decode(self)
+	}
+
+	/// Display as UTF-8, but decode broken bytes using a single byte encoding
+	/// instead of showing replacement characters.
+	///
+	/// This is useful for data that is mostly UTF-8, but contains some
+	/// fragments in a legacy encoding.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// # use raw_string::encoding::WINDOWS_1252;
+	/// let s = RawStr::from_bytes(b"\xE2\x82\xAC 5 or \x80 5");
+	/// assert_eq!(s.display_fallback(&WINDOWS_1252).to_string(), "€ 5 or € 5");
+	/// ```
+	#[inline]
+	pub fn display_fallback<'a>(&'a self, encoding: &'a SingleByteEncoding) -> DisplayFallback<'a> {
+		DisplayFallback {
+			raw: self,
+			encoding,
+		}
+	}
+}
+
+impl RawString {
+	/// Encode a string as ISO-8859-1 (Latin-1).
+	///
+	/// Fails on the first character above U+00FF.
+	#[inline]
+	pub fn encode_latin1(s: &str) -> Result<RawString, EncodeError> {
+		LATIN1.encode(s)
+	}
+
+	/// Encode a string as Windows-1252.
+	///
+	/// Fails on the first character that is not in Windows-1252.
+	#[inline]
+	pub fn encode_windows1252(s: &str) -> Result<RawString, EncodeError> {
+		WINDOWS_1252.encode(s)
+	}
+}
+
+/// Displays a `RawStr` with a fallback encoding for broken UTF-8.
+///
+/// See [`RawStr::display_fallback`](../struct.RawStr.html#method.display_fallback).
+pub struct DisplayFallback<'a> {
+	raw: &'a RawStr,
+	encoding: &'a SingleByteEncoding,
+}
+
+impl<'a> Display for DisplayFallback<'a> {
+	fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+		for Utf8Chunk { valid, broken } in self.raw.utf8_chunks() {
+			f.write_str(valid)?;
+			for &b in broken {
+				let c = self.encoding.decode_byte(b);
+				f.write_char(c.unwrap_or(std::char::REPLACEMENT_CHARACTER))?;
+			}
+		}
+		Ok(())
+	}
+}
+
+// Tests {{{
+
+#[test]
+fn test_latin1() {
+	let s = RawStr::from_bytes(b"caf\xE9 \x80\xFF");
+	assert_eq!(s.decode_latin1(), "café \u{80}ÿ");
+	assert_eq!(RawString::encode_latin1("café \u{80}ÿ").unwrap(), s);
+	assert!(match RawStr::from_str("plain").decode_latin1() {
+		Cow::Borrowed(s) => s == "plain",
+		Cow::Owned(_) => false,
+	});
+	let e = RawString::encode_latin1("a€").unwrap_err();
+	assert_eq!((e.index(), e.character()), (1, '€'));
+}
+
+#[test]
+fn test_windows1252() {
+	let s = RawStr::from_bytes(b"\x93quoted\x94 \x80 \x81 \xE9");
+	assert_eq!(s.decode_windows1252(), "\u{201C}quoted\u{201D} € \u{81} é");
+	assert_eq!(RawString::encode_windows1252("\u{201C}quoted\u{201D} € \u{81} é").unwrap(), s);
+	for b in 0..=255 {
+		let c = WINDOWS_1252.decode_byte(b).unwrap();
+		assert_eq!(WINDOWS_1252.encode_char(c), Some(b));
+	}
+	let e = RawString::encode_windows1252("ab\u{80}").unwrap_err();
+	assert_eq!((e.index(), e.character()), (2, '\u{80}'));
+}
+
+#[test]
+fn test_encode_char() {
+	for encoding in &[&ASCII, &ISO_8859_7, &WINDOWS_874, &WINDOWS_1251, &WINDOWS_1255] {
+		for b in 0x80..=0xFF {
+			if let Some(c) = encoding.decode_byte(b) {
+				assert_eq!(encoding.encode_char(c), Some(b), "{}", encoding.name());
+			}
+		}
+		assert_eq!(encoding.encode_char('\u{FFFD}'), None);
+	}
+}
+
+#[test]
+fn test_encoder_partial_failure() {
+	let mut out = RawString::new();
+	let e = WINDOWS_1252.new_encoder().encode("é✓", &mut out).unwrap_err();
+	assert_eq!((e.index(), e.character()), (2, '✓'));
+	assert_eq!(out, &b"\xE9"[..]);
+	let e = ISO_8859_5.new_encoder().encode("abЖ€x", &mut out).unwrap_err();
+	assert_eq!(e.index(), 4);
+	assert_eq!(out, &b"\xE9ab\xB6"[..]);
+}
+
+// }}}
@@ -0,0 +1,347 @@
+use std::borrow::Cow;
+use std::char::REPLACEMENT_CHARACTER;
+use std::mem::take;
+
+use super::{Decoder, EncodeError, Encoder, Encoding};
+use str::{RawStr, Utf8Chunk, Utf8ErrorKind};
+use string::RawString;
+
+/// The type of [`UTF_8`](static.UTF_8.html).
+#[derive(Debug)]
+pub struct Utf8Encoding(());
+
+/// UTF-8.
+///
+/// Decoding borrows the input if it is valid UTF-8.
+/// Encoding never fails.
+pub static UTF_8: Utf8Encoding = Utf8Encoding(());
+
+/// The type of [`UTF_16LE`](static.UTF_16LE.html) and [`UTF_16BE`](static.UTF_16BE.html).
+#[derive(Debug)]
+pub struct Utf16Encoding {
+	big_endian: bool,
+}
+
+/// UTF-16, little endian.
+///
+/// Unpaired surrogates are decoded as U+FFFD. A byte order mark is not
+/// treated specially. Encoding never fails.
+pub static UTF_16LE: Utf16Encoding = Utf16Encoding { big_endian: false };
+
+/// UTF-16, big endian.
+///
+/// Unpaired surrogates are decoded as U+FFFD. A byte order mark is not
+/// treated specially. Encoding never fails.
+pub static UTF_16BE: Utf16Encoding = Utf16Encoding { big_endian: true };
+
+/// The type of [`UTF_32LE`](static.UTF_32LE.html) and [`UTF_32BE`](static.UTF_32BE.html).
+#[derive(Debug)]
+pub struct Utf32Encoding {
+	big_endian: bool,
+}
+
+/// UTF-32, little endian.
+///
+/// Not part of the WHATWG Encoding Standard.
+/// Values that are not valid code points are decoded as U+FFFD.
+/// Encoding never fails.
+pub static UTF_32LE: Utf32Encoding = Utf32Encoding { big_endian: false };
+
+/// UTF-32, big endian.
+///
+/// Not part of the WHATWG Encoding Standard.
+/// Values that are not valid code points are decoded as U+FFFD.
+/// Encoding never fails.
+pub static UTF_32BE: Utf32Encoding = Utf32Encoding { big_endian: true };
+
+// UTF-8 {{{
+
+impl Encoding for Utf8Encoding {
+	fn name(&self) -> &'static str {
+		"UTF-8"
+	}
+
+	fn new_decoder(&self) -> Box<dyn Decoder> {
+		Box::new(Utf8Decoder {
+			incomplete: Vec::new(),
+		})
+	}
+
+	fn new_encoder(&self) -> Box<dyn Encoder> {
+		Box::new(Utf8Encoder)
+	}
+
+	fn decode<'a>(&self, input: &'a RawStr) -> Cow<'a, str> {
+		match input.to_str() {
+			Ok(s) => Cow::Borrowed(s),
+			Err(_) => {
+				let mut output = String::with_capacity(input.len());
+				self.new_decoder().decode(input, &mut output, true);
+				Cow::Owned(output)
+			}
+		}
+	}
+}
+
+struct Utf8Decoder {
+	incomplete: Vec<u8>,
+}
+
+impl Decoder for Utf8Decoder {
+	fn decode(&mut self, input: &RawStr, output: &mut String, last: bool) {
+		let mut joined;
+		let input = if self.incomplete.is_empty() {
+			input
+		} else {
+			joined = take(&mut self.incomplete);
+			joined.extend_from_slice(input.as_bytes());
+			RawStr::from_bytes(&joined)
+		};
+		for chunk in input.utf8_chunks() {
+			let Utf8Chunk { valid, broken } = chunk;
+			output.push_str(valid);
+			let at_end = chunk.offset_in(input) + valid.len() + broken.len() == input.len();
+			if !last && at_end && chunk.error_in(input) == Some(Utf8ErrorKind::TruncatedSequence) {
+				self.incomplete.extend_from_slice(broken);
+			} else if !broken.is_empty() {
+				output.push(REPLACEMENT_CHARACTER);
+			}
+		}
+	}
+}
+
+struct Utf8Encoder;
+
+impl Encoder for Utf8Encoder {
+	fn encode(&mut self, input: &str, output: &mut RawString) -> Result<(), EncodeError> {
+		output.push_str(input);
+		Ok(())
+	}
+}
+
+// }}}
+
+// UTF-16 {{{
+
+impl Encoding for Utf16Encoding {
+	fn name(&self) -> &'static str {
+		if self.big_endian {
+			"UTF-16BE"
+		} else {
+			"UTF-16LE"
+		}
+	}
+
+	fn new_decoder(&self) -> Box<dyn Decoder> {
+		Box::new(Utf16Decoder {
+			big_endian: self.big_endian,
+			byte: None,
+			high_surrogate: None,
+		})
+	}
+
+	fn new_encoder(&self) -> Box<dyn Encoder> {
+		Box::new(Utf16Encoder {
+			big_endian: self.big_endian,
+		})
+	}
+}
+
+struct Utf16Decoder {
+	big_endian: bool,
+	/// The first byte of an incomplete code unit.
+	byte: Option<u8>,
+	/// A high surrogate that still needs its low surrogate.
+	high_surrogate: Option<u16>,
+}
+
+impl Utf16Decoder {
+	fn push_unit(&mut self, unit: u16, output: &mut String) {
+		match (self.high_surrogate.take(), unit) {
+			(Some(high), 0xDC00..=0xDFFF) => {
+				let c = 0x10000 + ((u32::from(high) - 0xD800) << 10) + (u32::from(unit) - 0xDC00);
+				output.push(::std::char::from_u32(c).unwrap());
+			}
+			(high, _) => {
+				if high.is_some() {
+					output.push(REPLACEMENT_CHARACTER);
+				}
+				match unit {
+					0xD800..=0xDBFF => self.high_surrogate = Some(unit),
+					0xDC00..=0xDFFF => output.push(REPLACEMENT_CHARACTER),
+					_ => output.push(::std::char::from_u32(u32::from(unit)).unwrap()),
+				}
+			}
+		}
+	}
+
+	#[inline]
+	fn unit(&self, bytes: [u8; 2]) -> u16 {
+		if self.big_endian {
+			u16::from_be_bytes(bytes)
+		} else {
+			u16::from_le_bytes(bytes)
+		}
+	}
+}
+
+impl Decoder for Utf16Decoder {
+	fn decode(&mut self, input: &RawStr, output: &mut String, last: bool) {
+		let mut bytes = input.as_bytes();
+		if let Some(first) = self.byte {
+			if let Some((&second, rest)) = bytes.split_first() {
+				self.byte = None;
+				let unit = self.unit([first, second]);
+				self.push_unit(unit, output);
+				bytes = rest;
+			}
+		}
+		for pair in bytes.chunks(2) {
+			if pair.len() == 2 {
+				let unit = self.unit([pair[0], pair[1]]);
+				self.push_unit(unit, output);
+			} else {
+				self.byte = Some(pair[0]);
+			}
+		}
+		if last {
+			if self.high_surrogate.take().is_some() {
+				output.push(REPLACEMENT_CHARACTER);
+			}
+			if self.byte.take().is_some() {
+				output.push(REPLACEMENT_CHARACTER);
+			}
+		}
+	}
+}
+
+struct Utf16Encoder {
+	big_endian: bool,
+}
+
+impl Encoder for Utf16Encoder {
+	fn encode(&mut self, input: &str, output: &mut RawString) -> Result<(), EncodeError> {
+		output.reserve(input.len() * 2);
+		for unit in input.encode_utf16() {
+			let bytes = if self.big_endian {
+				unit.to_be_bytes()
+			} else {
+				unit.to_le_bytes()
+			};
+			output.push_str(&bytes[..]);
+		}
+		Ok(())
+	}
+}
+
+// }}}
+
+// UTF-32 {{{
+
+impl Encoding for Utf32Encoding {
+	fn name(&self) -> &'static str {
+		if self.big_endian {
+			"UTF-32BE"
+		} else {
+			"UTF-32LE"
+		}
+	}
+
+	fn new_decoder(&self) -> Box<dyn Decoder> {
+		Box::new(Utf32Decoder {
+			big_endian: self.big_endian,
+			bytes: [0; 4],
+			len: 0,
+		})
+	}
+
+	fn new_encoder(&self) -> Box<dyn Encoder> {
+		Box::new(Utf32Encoder {
+			big_endian: self.big_endian,
+		})
+	}
+}
+
+struct Utf32Decoder {
+	big_endian: bool,
+	/// An incomplete code unit.
+	bytes: [u8; 4],
+	len: usize,
+}
+
+impl Decoder for Utf32Decoder {
+	fn decode(&mut self, input: &RawStr, output: &mut String, last: bool) {
+		output.reserve(input.len() / 4);
+		for b in input.bytes() {
+			self.bytes[self.len] = b;
+			self.len += 1;
+			if self.len == 4 {
+				self.len = 0;
+				let unit = if self.big_endian {
+					u32::from_be_bytes(self.bytes)
+				} else {
+					u32::from_le_bytes(self.bytes)
+				};
+				output.push(::std::char::from_u32(unit).unwrap_or(REPLACEMENT_CHARACTER));
+			}
+		}
+		if last && self.len > 0 {
+			self.len = 0;
+			output.push(REPLACEMENT_CHARACTER);
+		}
+	}
+}
+
+struct Utf32Encoder {
+	big_endian: bool,
+}
+
+impl Encoder for Utf32Encoder {
+	fn encode(&mut self, input: &str, output: &mut RawString) -> Result<(), EncodeError> {
+		output.reserve(input.len() * 4);
+		for c in input.chars() {
+			let bytes = if self.big_endian {
+				(c as u32).to_be_bytes()
+			} else {
+				(c as u32).to_le_bytes()
+			};
+			output.push_str(&bytes[..]);
+		}
+		Ok(())
+	}
+}
+
+// }}}
+
+// Tests {{{
+
+#[test]
+fn test_utf8() {
+	let s = RawStr::from_bytes(b"a\xFFb\xE2\x82");
+	assert_eq!(UTF_8.decode(s), "a\u{FFFD}b\u{FFFD}");
+	let mut decoder = UTF_8.new_decoder();
+	let mut output = String::new();
+	decoder.decode(s, &mut output, false);
+	assert_eq!(output, "a\u{FFFD}b");
+	decoder.decode(RawStr::from_bytes(b"\xAC!"), &mut output, true);
+	assert_eq!(output, "a\u{FFFD}b€!");
+}
+
+#[test]
+fn test_utf16() {
+	// An unpaired high surrogate, an unpaired low surrogate, and a pair.
+	let s = RawStr::from_bytes(b"\x3D\xD8a\x00\x00\xDC\x3D\xD8\x00\xDEz");
+	assert_eq!(UTF_16LE.decode(s), "\u{FFFD}a\u{FFFD}😀\u{FFFD}");
+	let s = RawStr::from_bytes(b"\xD8\x3D\xD8\x3D\xDE\x00");
+	assert_eq!(UTF_16BE.decode(s), "\u{FFFD}😀");
+	assert_eq!(UTF_16BE.encode("a😀").unwrap(), &b"\x00a\xD8\x3D\xDE\x00"[..]);
+}
+
+#[test]
+fn test_utf32() {
+	let s = RawStr::from_bytes(b"a\0\0\0\0\xD8\0\0\0\0\x11\0\x00\xF6\x01\0!");
+	assert_eq!(UTF_32LE.decode(s), "a\u{FFFD}\u{FFFD}😀\u{FFFD}");
+	assert_eq!(UTF_32BE.encode("a😀").unwrap(), &b"\0\0\0a\0\x01\xF6\x00"[..]);
+}
+
+// }}}
@@ -0,0 +1,209 @@
+use std::borrow::Cow;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::iter::FusedIterator;
+use str::RawStr;
+use string::RawString;
+
+#[cfg(not(unix))]
+use std::str::from_utf8;
+#[cfg(unix)]
+use unix::RawStrExt;
+
+#[cfg(unix)]
+pub(crate) fn from_os(s: OsString) -> RawString {
+	RawString::from_osstring(s)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn from_os(s: OsString) -> RawString {
+	RawString::from_bytes(s.into_encoded_bytes())
+}
+
+/// Convert back what `from_os` returned.
+///
+/// On Unix, this is without loss. On Windows, WTF-8 (UTF-8 that may also
+/// contain surrogates, like the `OsStr`s of Windows do) is converted without
+/// loss, and anything else lossily. On other platforms, anything that isn't
+/// valid UTF-8 is converted lossily.
+#[cfg(unix)]
+pub(crate) fn to_os(s: &RawStr) -> Cow<'_, OsStr> {
+	Cow::Borrowed(s.as_osstr())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn to_os(s: &RawStr) -> Cow<'_, OsStr> {
+	match from_utf8(s.as_bytes()) {
+		Ok(s) => Cow::Borrowed(OsStr::new(s)),
+		Err(_) => Cow::Owned(to_os_owned(s.as_bytes())),
+	}
+}
+
+#[cfg(windows)]
+fn to_os_owned(bytes: &[u8]) -> OsString {
+	use std::os::windows::ffi::OsStringExt;
+	OsString::from_wide(&wtf8_to_wide(bytes))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn to_os_owned(bytes: &[u8]) -> OsString {
+	OsString::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Decode WTF-8 to UTF-16, keeping the surrogates (`ED A0..=BF ..`), and
+/// replacing anything else that isn't UTF-8 with U+FFFD.
+#[cfg(windows)]
+fn wtf8_to_wide(bytes: &[u8]) -> Vec<u16> {
+	let mut wide = Vec::with_capacity(bytes.len());
+	let mut rest = bytes;
+	loop {
+		let (valid, e) = match from_utf8(rest) {
+			Ok(valid) => (valid, None),
+			// Everything up to `valid_up_to` is valid UTF-8.
+			Err(e) => (unsafe { ::std::str::from_utf8_unchecked(&rest[..e.valid_up_to()]) }, Some(e)),
+		};
+		wide.extend(valid.encode_utf16());
+		let e = match e {
+			Some(e) => e,
+			None => return wide,
+		};
+		rest = &rest[e.valid_up_to()..];
+		match *rest {
+			[0xED, second @ 0xA0..=0xBF, third @ 0x80..=0xBF, ..] => {
+				wide.push(0xD000 | ((u16::from(second) & 0x3F) << 6) | (u16::from(third) & 0x3F));
+				rest = &rest[3..];
+			}
+			_ => {
+				wide.push(0xFFFD);
+				rest = &rest[e.error_len().unwrap_or(rest.len())..];
+			}
+		}
+	}
+}
+
+/// Iterator over the arguments of the process.
+///
+/// See [`raw_args`](fn.raw_args.html).
+pub struct RawArgs {
+	inner: env::ArgsOs,
+}
+
+impl Iterator for RawArgs {
+	type Item = RawString;
+	#[inline]
+	fn next(&mut self) -> Option<RawString> {
+		self.inner.next().map(from_os)
+	}
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.inner.size_hint()
+	}
+}
+
+impl DoubleEndedIterator for RawArgs {
+	#[inline]
+	fn next_back(&mut self) -> Option<RawString> {
+		self.inner.next_back().map(from_os)
+	}
+}
+
+impl ExactSizeIterator for RawArgs {}
+
+impl FusedIterator for RawArgs {}
+
+/// Iterator over the environment variables of the process.
+///
+/// See [`raw_vars`](fn.raw_vars.html).
+pub struct RawVars {
+	inner: env::VarsOs,
+}
+
+impl Iterator for RawVars {
+	type Item = (RawString, RawString);
+	#[inline]
+	fn next(&mut self) -> Option<(RawString, RawString)> {
+		self.inner.next().map(|(k, v)| (from_os(k), from_os(v)))
+	}
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.inner.size_hint()
+	}
+}
+
+impl FusedIterator for RawVars {}
+
+/// The arguments of the process, starting with the program name.
+///
+/// Like `std::env::args_os`, but as `RawString`s. On Unix, these are the
+/// bytes exactly as they were passed to the process. On other platforms,
+/// they are the WTF-8 encoded bytes of the `OsString`s.
+///
+/// # Example
+///
+/// ```
+/// let files: Vec<_> = raw_string::raw_args().skip(1).collect();
+/// ```
+#[inline]
+pub fn raw_args() -> RawArgs {
+	RawArgs { inner: env::args_os() }
+}
+
+/// The environment variables of the process, as key-value pairs.
+///
+/// Like `std::env::vars_os`, but as `RawString`s. See
+/// [`raw_args`](fn.raw_args.html) for how they are converted.
+#[inline]
+pub fn raw_vars() -> RawVars {
+	RawVars { inner: env::vars_os() }
+}
+
+/// The value of an environment variable, or `None` if it isn't set.
+///
+/// See [`set_raw_var`](fn.set_raw_var.html) for how the key is converted.
+///
+/// # Example
+///
+/// ```
+/// # use raw_string::raw_var;
+/// if let Some(home) = raw_var("HOME") {
+///     println!("{}", home.display_safe());
+/// }
+/// ```
+pub fn raw_var<K: AsRef<RawStr>>(key: K) -> Option<RawString> {
+	env::var_os(to_os(key.as_ref())).map(from_os)
+}
+
+/// Set an environment variable.
+///
+/// On Unix, the key and value are used as they are. On Windows, they are
+/// converted lossily if they aren't valid WTF-8, which only happens if they
+/// didn't come from the OS. On other platforms, they are converted lossily if
+/// they aren't valid UTF-8.
+///
+/// # Safety
+///
+/// The same as for `std::env::set_var`: on most platforms other than
+/// Windows, this must not be called while any other thread might read or
+/// write the environment, including through functions like `getenv`.
+///
+/// # Panics
+///
+/// Like `std::env::set_var`, this panics if the key is empty, contains `=`
+/// or a NUL byte, or if the value contains a NUL byte.
+pub unsafe fn set_raw_var<K: AsRef<RawStr>, V: AsRef<RawStr>>(key: K, value: V) {
+	env::set_var(to_os(key.as_ref()), to_os(value.as_ref()))
+}
+
+// Tests {{{
+
+#[test]
+fn test_raw_args() {
+	let args: Vec<RawString> = raw_args().collect();
+	assert_eq!(args.len(), env::args_os().len());
+	assert!(!args.is_empty());
+	let mut backward: Vec<RawString> = raw_args().rev().collect();
+	backward.reverse();
+	assert_eq!(args, backward);
+}
+
+// }}}
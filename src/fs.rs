@@ -0,0 +1,103 @@
+use env::from_os;
+use std::fs;
+use std::io;
+use std::iter::FusedIterator;
+use std::path::Path;
+use str::RawStr;
+use string::RawString;
+
+/// Convert a path with [`RawStr::to_path`](struct.RawStr.html#method.to_path).
+fn path(path: &RawStr) -> io::Result<&Path> {
+	path.to_path().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Read a whole file.
+///
+/// Like `std::fs::read`, but with a `RawStr` path, and returning a
+/// `RawString`. On other platforms than Unix, the path must be valid UTF-8,
+/// otherwise this fails with `ErrorKind::InvalidInput`.
+///
+/// # Example
+///
+/// ```no_run
+/// # use raw_string::{read_raw, RawStr};
+/// let config = read_raw(RawStr::from_bytes(b"/etc/\xFF.conf"))?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn read_raw<P: AsRef<RawStr>>(path: P) -> io::Result<RawString> {
+	fs::read(self::path(path.as_ref())?).map(RawString::from_bytes)
+}
+
+/// Write a whole file, replacing its contents if it already exists.
+///
+/// Like `std::fs::write`, but with a `RawStr` path. See
+/// [`read_raw`](fn.read_raw.html) for how the path is converted.
+pub fn write_raw<P: AsRef<RawStr>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+	fs::write(self::path(path.as_ref())?, contents)
+}
+
+/// Iterator over the names of the entries of a directory.
+///
+/// See [`read_dir_raw`](fn.read_dir_raw.html).
+pub struct RawReadDir {
+	inner: fs::ReadDir,
+}
+
+impl Iterator for RawReadDir {
+	type Item = io::Result<RawString>;
+	#[inline]
+	fn next(&mut self) -> Option<io::Result<RawString>> {
+		self.inner.next().map(|entry| entry.map(|entry| from_os(entry.file_name())))
+	}
+}
+
+impl FusedIterator for RawReadDir {}
+
+/// List the names of the entries of a directory.
+///
+/// Like `std::fs::read_dir`, but with a `RawStr` path, and yielding just the
+/// file names as `RawString`s. The order is unspecified, and `.` and `..` are
+/// not included. See [`read_raw`](fn.read_raw.html) for how the path is
+/// converted, and [`raw_args`](fn.raw_args.html) for how the names are.
+///
+/// # Example
+///
+/// ```no_run
+/// # use raw_string::read_dir_raw;
+/// for name in read_dir_raw(".")? {
+///     println!("{}", name?.display_safe());
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn read_dir_raw<P: AsRef<RawStr>>(path: P) -> io::Result<RawReadDir> {
+	fs::read_dir(self::path(path.as_ref())?).map(|inner| RawReadDir { inner })
+}
+
+// Tests {{{
+
+#[cfg(unix)]
+#[test]
+fn test_fs() {
+	use std::env::temp_dir;
+	use unix::RawStrExt;
+
+	let mut dir = RawString::from_osstring(temp_dir().into_os_string());
+	dir.push_str(format!("/raw-string-test-{}", ::std::process::id()).as_str());
+	fs::create_dir(dir.as_path()).unwrap();
+
+	let mut file = dir.clone();
+	file.push_str(&b"/\xFF.txt"[..]);
+	write_raw(&file, &b"a\xFEb"[..]).unwrap();
+	assert_eq!(read_raw(&file).unwrap(), &b"a\xFEb"[..]);
+	write_raw(&file, RawStr::from_str("c")).unwrap();
+	assert_eq!(read_raw(&file).unwrap(), "c");
+
+	let names: Vec<RawString> = read_dir_raw(&dir).unwrap().map(Result::unwrap).collect();
+	assert_eq!(names, [&b"\xFF.txt"[..]]);
+
+	fs::remove_dir_all(dir.as_path()).unwrap();
+	assert_eq!(read_raw(&file).unwrap_err().kind(), io::ErrorKind::NotFound);
+	assert!(read_dir_raw(&dir).is_err());
+}
+
+// }}}
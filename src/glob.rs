@@ -0,0 +1,830 @@
+//! Shell-style glob patterns over `RawStr`.
+//!
+//! Globs are matched against whole strings (usually paths), byte by byte, so
+//! neither the glob nor the path needs to be valid UTF-8.
+//!
+//! # Syntax
+//!
+//! - `?` matches any single character, or a single byte that isn't part of
+//!   valid UTF-8.
+//! - `*` matches any sequence of characters and bytes.
+//! - `**` as a whole path component matches any number of components: `**/a`
+//!   matches `a` and `x/y/a`, `a/**` matches everything inside `a/`.
+//! - `[abc]`, `[a-z]` and `[[:alpha:]]` match a character from a set, and
+//!   `[!abc]` or `[^abc]` one that isn't. A `]` directly after the `[` (or
+//!   `[!`) is part of the set.
+//! - `{a,b,c}` matches any of the alternatives, which can be globs themselves.
+//!   Outside of braces, `,` and `}` match literally.
+//! - `\` makes the next character match literally.
+//!
+//! With [`literal_separator`](struct.RawGlobBuilder.html#method.literal_separator)
+//! (the default), `?`, `*` and `[!...]` never match a `/`.
+//!
+//! # Example
+//!
+//! ```
+//! # use raw_string::RawStr;
+//! # use raw_string::glob::{GlobSet, RawGlob};
+//! let glob = RawGlob::new("src/**/*.{rs,toml}").unwrap();
+//! assert!(glob.is_match(RawStr::from_bytes(b"src/a/\xFF.rs")));
+//! assert!(!glob.is_match(RawStr::from_bytes(b"src/a/b.c")));
+//!
+//! let set = GlobSet::new(&[
+//!     RawGlob::new("*.rs").unwrap(),
+//!     RawGlob::new("**/main.rs").unwrap(),
+//! ]).unwrap();
+//! assert_eq!(set.matches(RawStr::from_str("main.rs")), [0, 1]);
+//! assert_eq!(set.matches(RawStr::from_str("src/main.rs")), [1]);
+//! ```
+
+use regex::{self, is_posix_class, Regex};
+use std;
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{Display, Formatter, Write};
+use str::{RawStr, Utf8Chunk};
+use string::RawString;
+
+/// An error in the syntax of a glob.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+	message: &'static str,
+	position: usize,
+}
+
+impl Error {
+	/// A description of the error.
+	#[inline]
+	pub fn message(&self) -> &str {
+		self.message
+	}
+
+	/// The byte offset in the glob at which the error was found.
+	#[inline]
+	pub fn position(&self) -> usize {
+		self.position
+	}
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+		write!(f, "glob error at position {}: {}", self.position, self.message)
+	}
+}
+
+impl error::Error for Error {}
+
+// Parsing {{{
+
+/// A character of the glob, or a byte that isn't part of valid UTF-8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+	Char(char),
+	Byte(u8),
+}
+
+#[derive(Clone, Debug)]
+enum Part {
+	Literal(Token),
+	/// `?`
+	Any,
+	/// `*`
+	Star,
+	/// `**/`, including the `/`.
+	RecursivePrefix,
+	/// `**` at the end of the glob (or of an alternative).
+	RecursiveSuffix,
+	Class {
+		negated: bool,
+		items: Vec<ClassItem>,
+	},
+	Alternation(Vec<Vec<Part>>),
+}
+
+#[derive(Clone, Debug)]
+enum ClassItem {
+	Range(Token, Token),
+	Posix(String),
+}
+
+/// How deeply alternations may be nested.
+const MAX_NESTING: usize = 100;
+
+struct Parser {
+	tokens: Vec<(usize, Token)>,
+	len: usize,
+	index: usize,
+	/// The number of alternations around the current position.
+	depth: usize,
+}
+
+impl Parser {
+	fn new(glob: &RawStr) -> Parser {
+		let mut tokens = Vec::with_capacity(glob.len());
+		for chunk in glob.utf8_chunks() {
+			let offset = chunk.offset_in(glob);
+			let Utf8Chunk { valid, broken } = chunk;
+			tokens.extend(valid.char_indices().map(|(i, c)| (offset + i, Token::Char(c))));
+			let broken_offset = offset + valid.len();
+			tokens.extend(broken.iter().enumerate().map(|(i, &b)| (broken_offset + i, Token::Byte(b))));
+		}
+		Parser {
+			tokens,
+			len: glob.len(),
+			index: 0,
+			depth: 0,
+		}
+	}
+
+	fn peek(&self) -> Option<Token> {
+		self.tokens.get(self.index).map(|&(_, t)| t)
+	}
+
+	fn peek_char(&self, offset: usize) -> Option<char> {
+		match self.tokens.get(self.index + offset) {
+			Some(&(_, Token::Char(c))) => Some(c),
+			_ => None,
+		}
+	}
+
+	fn next(&mut self) -> Option<Token> {
+		let t = self.peek();
+		self.index += 1;
+		t
+	}
+
+	fn eat(&mut self, c: char) -> bool {
+		if self.peek_char(0) == Some(c) {
+			self.index += 1;
+			true
+		} else {
+			false
+		}
+	}
+
+	fn position(&self) -> usize {
+		self.tokens.get(self.index).map_or(self.len, |&(pos, _)| pos)
+	}
+
+	/// Parse a sequence, up to the end or (if `in_braces`) a `,` or `}`.
+	fn parse_sequence(&mut self, in_braces: bool) -> Result<Vec<Part>, Error> {
+		let mut parts = Vec::new();
+		loop {
+			let at_component_start = matches!(
+				parts.last(),
+				None | Some(&Part::RecursivePrefix) | Some(&Part::Literal(Token::Char('/')))
+			);
+			let c = match self.peek() {
+				None => break,
+				Some(Token::Byte(b)) => {
+					self.index += 1;
+					parts.push(Part::Literal(Token::Byte(b)));
+					continue;
+				}
+				Some(Token::Char(c)) => c,
+			};
+			if in_braces && (c == ',' || c == '}') {
+				break;
+			}
+			let start = self.position();
+			self.index += 1;
+			let part = match c {
+				'?' => Part::Any,
+				'*' if self.peek_char(0) == Some('*') && at_component_start => {
+					self.index += 1;
+					match self.peek_char(0) {
+						Some('/') => {
+							self.index += 1;
+							Part::RecursivePrefix
+						}
+						None => Part::RecursiveSuffix,
+						Some(',') | Some('}') if in_braces => Part::RecursiveSuffix,
+						// Not a whole component, so just two stars.
+						_ => Part::Star,
+					}
+				}
+				'*' => {
+					while self.eat('*') {}
+					Part::Star
+				}
+				'[' => self.parse_class(start)?,
+				'{' => {
+					if self.depth == MAX_NESTING {
+						return Err(Error {
+							message: "exceeds the nesting limit",
+							position: start,
+						});
+					}
+					self.depth += 1;
+					let mut alternatives = vec![self.parse_sequence(true)?];
+					while self.eat(',') {
+						alternatives.push(self.parse_sequence(true)?);
+					}
+					if !self.eat('}') {
+						return Err(Error {
+							message: "unclosed alternation",
+							position: start,
+						});
+					}
+					self.depth -= 1;
+					Part::Alternation(alternatives)
+				}
+				'\\' => match self.next() {
+					Some(t) => Part::Literal(t),
+					None => {
+						return Err(Error {
+							message: "incomplete escape sequence",
+							position: start,
+						})
+					}
+				},
+				c => Part::Literal(Token::Char(c)),
+			};
+			parts.push(part);
+		}
+		Ok(parts)
+	}
+
+	/// Parse a class, after the `[`.
+	fn parse_class(&mut self, start: usize) -> Result<Part, Error> {
+		let negated = self.eat('!') || self.eat('^');
+		let mut items = Vec::new();
+		let mut first = true;
+		loop {
+			let item_start = self.position();
+			let low = match self.next() {
+				None => {
+					return Err(Error {
+						message: "unclosed character class",
+						position: start,
+					})
+				}
+				Some(Token::Char(']')) if !first => break,
+				Some(Token::Char('[')) if self.peek_char(0) == Some(':') => {
+					let name_start = self.index + 1;
+					let mut end = name_start;
+					let mut name = String::new();
+					while let Some(c) = self.tokens.get(end).and_then(|&(_, t)| match t {
+						Token::Char(c) if c.is_ascii_lowercase() => Some(c),
+						_ => None,
+					}) {
+						name.push(c);
+						end += 1;
+					}
+					self.index = end;
+					if !(self.eat(':') && self.eat(']') && is_posix_class(&name)) {
+						return Err(Error {
+							message: "invalid POSIX character class",
+							position: item_start,
+						});
+					}
+					items.push(ClassItem::Posix(name));
+					first = false;
+					continue;
+				}
+				Some(Token::Char('\\')) => match self.next() {
+					Some(t) => t,
+					None => {
+						return Err(Error {
+							message: "unclosed character class",
+							position: start,
+						})
+					}
+				},
+				Some(t) => t,
+			};
+			first = false;
+			let high = if self.peek_char(0) == Some('-') && self.peek_char(1) != Some(']') && self.index + 1 < self.tokens.len() {
+				self.index += 1;
+				match self.next() {
+					Some(Token::Char('\\')) => self.next(),
+					t => t,
+				}
+			} else {
+				None
+			};
+			let high = high.unwrap_or(low);
+			let valid = match (low, high) {
+				(Token::Char(a), Token::Char(b)) => a <= b,
+				(Token::Byte(a), Token::Byte(b)) => a <= b,
+				(Token::Char(a), Token::Byte(_)) => a.is_ascii(),
+				(Token::Byte(_), Token::Char(_)) => false,
+			};
+			if !valid {
+				return Err(Error {
+					message: "invalid character class range",
+					position: item_start,
+				});
+			}
+			items.push(ClassItem::Range(low, high));
+		}
+		Ok(Part::Class { negated, items })
+	}
+}
+
+// }}}
+
+// Translation {{{
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Options {
+	case_insensitive: bool,
+	literal_separator: bool,
+}
+
+fn push_token(regex: &mut String, token: Token) {
+	match token {
+		Token::Char(c) if c.is_ascii_punctuation() => {
+			regex.push('\\');
+			regex.push(c);
+		}
+		Token::Char(c) if c.is_ascii_control() => write!(regex, "\\x{:02X}", c as u32).unwrap(),
+		Token::Char(c) => regex.push(c),
+		Token::Byte(b) => write!(regex, "\\x{:02X}", b).unwrap(),
+	}
+}
+
+fn push_parts(regex: &mut String, parts: &[Part], options: Options) {
+	let any = if options.literal_separator { "[^/]" } else { "." };
+	for part in parts {
+		match *part {
+			Part::Literal(t) => push_token(regex, t),
+			Part::Any => regex.push_str(any),
+			Part::Star => {
+				regex.push_str(any);
+				regex.push('*');
+			}
+			Part::RecursivePrefix => regex.push_str("(?:.*/)?"),
+			Part::RecursiveSuffix => regex.push_str(".*"),
+			Part::Class { negated, ref items } => {
+				regex.push('[');
+				if negated {
+					regex.push('^');
+					if options.literal_separator {
+						regex.push('/');
+					}
+				}
+				for item in items {
+					match *item {
+						ClassItem::Range(low, high) => {
+							push_token(regex, low);
+							if high != low {
+								regex.push('-');
+								push_token(regex, high);
+							}
+						}
+						ClassItem::Posix(ref name) => {
+							regex.push_str("[:");
+							regex.push_str(name);
+							regex.push_str(":]");
+						}
+					}
+				}
+				regex.push(']');
+			}
+			Part::Alternation(ref alternatives) => {
+				regex.push_str("(?:");
+				for (i, alternative) in alternatives.iter().enumerate() {
+					if i > 0 {
+						regex.push('|');
+					}
+					push_parts(regex, alternative, options);
+				}
+				regex.push(')');
+			}
+		}
+	}
+}
+
+/// Convert literal parts to a string, if all parts are literal.
+fn literal(parts: &[Part]) -> Option<RawString> {
+	let mut s = RawString::new();
+	for part in parts {
+		match *part {
+			Part::Literal(Token::Char(c)) => s.push_str(c.encode_utf8(&mut [0; 4])),
+			Part::Literal(Token::Byte(b)) => s.push(b),
+			_ => return None,
+		}
+	}
+	Some(s)
+}
+
+/// How a glob is matched.
+#[derive(Clone, Debug)]
+enum Strategy {
+	/// The glob has no wildcards.
+	Literal(RawString),
+	/// `*.ext`, or `**/*.ext`.
+	Extension {
+		/// The extension, without the dot.
+		extension: RawString,
+		/// Whether the match may contain a `/`.
+		any_directory: bool,
+	},
+	Regex,
+}
+
+impl Strategy {
+	fn new(parts: &[Part], options: Options) -> Strategy {
+		if options.case_insensitive {
+			return Strategy::Regex;
+		}
+		if let Some(literal) = literal(parts) {
+			return Strategy::Literal(literal);
+		}
+		let (any_directory, rest) = match *parts {
+			[Part::RecursivePrefix, Part::Star, Part::Literal(Token::Char('.')), ref rest @ ..] => (true, rest),
+			[Part::Star, Part::Literal(Token::Char('.')), ref rest @ ..] => (!options.literal_separator, rest),
+			_ => return Strategy::Regex,
+		};
+		match literal(rest) {
+			Some(extension) if !extension.is_empty() && !extension.contains_byte(b'/') && !extension.contains_byte(b'.') => {
+				Strategy::Extension {
+					extension,
+					any_directory,
+				}
+			}
+			_ => Strategy::Regex,
+		}
+	}
+}
+
+/// Convert the error of compiling a translated glob.
+///
+/// Globs are checked while parsing, so their translation can only be too
+/// large, never invalid.
+fn size_error(error: regex::Error, message: &'static str) -> Error {
+	assert!(error.is_size_limit(), "glob translated to an invalid regex: {}", error);
+	Error { message, position: 0 }
+}
+
+/// The extension of a path, without the dot.
+fn extension(path: &RawStr) -> Option<&RawStr> {
+	path.rfind_byte(b'.').map(|i| &path[i + 1..])
+}
+
+// }}}
+
+// RawGlob {{{
+
+/// Builder for a [`RawGlob`](struct.RawGlob.html).
+#[derive(Clone, Debug)]
+pub struct RawGlobBuilder {
+	options: Options,
+}
+
+impl Default for RawGlobBuilder {
+	fn default() -> Self {
+		RawGlobBuilder {
+			options: Options {
+				case_insensitive: false,
+				literal_separator: true,
+			},
+		}
+	}
+}
+
+impl RawGlobBuilder {
+	/// Create a builder with the default options: case sensitive, and with
+	/// `literal_separator` enabled.
+	#[inline]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Whether to ignore case when matching.
+	///
+	/// This uses the simple (single character) case mappings of `char`, and
+	/// doesn't affect bytes that aren't part of valid UTF-8.
+	#[inline]
+	pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+		self.options.case_insensitive = yes;
+		self
+	}
+
+	/// Whether `?`, `*` and `[!...]` should never match a `/`.
+	///
+	/// Enabled by default. When disabled, `*` also matches across directories,
+	/// so `*.rs` matches `src/main.rs`.
+	#[inline]
+	pub fn literal_separator(&mut self, yes: bool) -> &mut Self {
+		self.options.literal_separator = yes;
+		self
+	}
+
+	/// Compile the glob.
+	pub fn build<T: AsRef<RawStr>>(&self, glob: T) -> Result<RawGlob, Error> {
+		let glob = glob.as_ref();
+		let mut parser = Parser::new(glob);
+		let parts = parser.parse_sequence(false)?;
+		let mut regex = String::from("(?s");
+		if self.options.case_insensitive {
+			regex.push('i');
+		}
+		regex.push_str(")\\A");
+		push_parts(&mut regex, &parts, self.options);
+		regex.push_str("\\z");
+		let compiled = Regex::new(&regex).map_err(|e| size_error(e, "glob is too large"))?;
+		Ok(RawGlob {
+			glob: glob.to_owned(),
+			regex: compiled,
+			strategy: Strategy::new(&parts, self.options),
+		})
+	}
+}
+
+/// A compiled glob.
+///
+/// See the [module documentation](index.html) for the syntax.
+#[derive(Clone, Debug)]
+pub struct RawGlob {
+	glob: RawString,
+	regex: Regex,
+	strategy: Strategy,
+}
+
+impl RawGlob {
+	/// Compile a glob with the default options.
+	#[inline]
+	pub fn new<T: AsRef<RawStr>>(glob: T) -> Result<RawGlob, Error> {
+		RawGlobBuilder::new().build(glob)
+	}
+
+	/// Create a builder, to set options before compiling.
+	#[inline]
+	pub fn builder() -> RawGlobBuilder {
+		RawGlobBuilder::new()
+	}
+
+	/// The glob this was compiled from.
+	#[inline]
+	pub fn as_raw_str(&self) -> &RawStr {
+		&self.glob
+	}
+
+	/// The regular expression this glob was translated to.
+	#[inline]
+	pub fn regex(&self) -> &Regex {
+		&self.regex
+	}
+
+	/// Whether the glob matches the whole of `path`.
+	pub fn is_match<T: AsRef<RawStr>>(&self, path: T) -> bool {
+		let path = path.as_ref();
+		match self.strategy {
+			Strategy::Literal(ref literal) => path == &literal[..],
+			Strategy::Extension {
+				extension: ref ext,
+				any_directory,
+			} => {
+				extension(path).is_some_and(|e| e == &ext[..])
+					&& (any_directory || !path.contains_byte(b'/'))
+			}
+			Strategy::Regex => self.regex.is_match(path),
+		}
+	}
+}
+
+// }}}
+
+// GlobSet {{{
+
+/// A set of globs, to match against all of them at once.
+#[derive(Clone, Debug)]
+pub struct GlobSet {
+	len: usize,
+	literals: HashMap<RawString, Vec<usize>>,
+	extensions: HashMap<RawString, Vec<(usize, bool)>>,
+	regexes: Vec<(usize, Regex)>,
+	/// All of `regexes` in one, for `is_match`.
+	combined: Option<Regex>,
+}
+
+/// Builder for a [`GlobSet`](struct.GlobSet.html).
+#[derive(Clone, Debug, Default)]
+pub struct GlobSetBuilder {
+	globs: Vec<RawGlob>,
+}
+
+impl GlobSetBuilder {
+	/// Create an empty builder.
+	#[inline]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a glob. Its index in the set is the number of globs added before it.
+	#[inline]
+	pub fn add(&mut self, glob: RawGlob) -> &mut Self {
+		self.globs.push(glob);
+		self
+	}
+
+	/// Build the set.
+	///
+	/// This only fails if the globs together are too large.
+	pub fn build(&self) -> Result<GlobSet, Error> {
+		let mut set = GlobSet {
+			len: self.globs.len(),
+			literals: HashMap::new(),
+			extensions: HashMap::new(),
+			regexes: Vec::new(),
+			combined: None,
+		};
+		let mut combined = String::new();
+		for (i, glob) in self.globs.iter().enumerate() {
+			match glob.strategy {
+				Strategy::Literal(ref literal) => set.literals.entry(literal.clone()).or_default().push(i),
+				Strategy::Extension {
+					ref extension,
+					any_directory,
+				} => set.extensions.entry(extension.clone()).or_default().push((i, any_directory)),
+				Strategy::Regex => {
+					if !combined.is_empty() {
+						combined.push('|');
+					}
+					combined.push_str("(?:");
+					combined.push_str(glob.regex.as_str());
+					combined.push(')');
+					set.regexes.push((i, glob.regex.clone()));
+				}
+			}
+		}
+		if set.regexes.len() > 1 {
+			set.combined = Some(Regex::new(&combined).map_err(|e| size_error(e, "glob set is too large"))?);
+		}
+		Ok(set)
+	}
+}
+
+impl GlobSet {
+	/// Build a set of the given globs.
+	pub fn new<'a, I>(globs: I) -> Result<GlobSet, Error>
+	where
+		I: IntoIterator<Item = &'a RawGlob>,
+	{
+		let mut builder = GlobSetBuilder::new();
+		for glob in globs {
+			builder.add(glob.clone());
+		}
+		builder.build()
+	}
+
+	/// The number of globs in the set.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Whether the set contains no globs.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Whether any of the globs match.
+	pub fn is_match<T: AsRef<RawStr>>(&self, path: T) -> bool {
+		let path = path.as_ref();
+		if self.literals.contains_key(path) {
+			return true;
+		}
+		let has_slash = path.contains_byte(b'/');
+		if let Some(globs) = extension(path).and_then(|e| self.extensions.get(e)) {
+			if globs.iter().any(|&(_, any_directory)| any_directory || !has_slash) {
+				return true;
+			}
+		}
+		match self.combined {
+			Some(ref regex) => regex.is_match(path),
+			None => self.regexes.iter().any(|(_, regex)| regex.is_match(path)),
+		}
+	}
+
+	/// The indexes of all globs that match, in increasing order.
+	pub fn matches<T: AsRef<RawStr>>(&self, path: T) -> Vec<usize> {
+		let path = path.as_ref();
+		let mut matches = Vec::new();
+		if let Some(globs) = self.literals.get(path) {
+			matches.extend_from_slice(globs);
+		}
+		let has_slash = path.contains_byte(b'/');
+		if let Some(globs) = extension(path).and_then(|e| self.extensions.get(e)) {
+			matches.extend(globs.iter().filter(|&&(_, any_directory)| any_directory || !has_slash).map(|&(i, _)| i));
+		}
+		matches.extend(self.regexes.iter().filter(|(_, regex)| regex.is_match(path)).map(|&(i, _)| i));
+		matches.sort_unstable();
+		matches
+	}
+}
+
+// }}}
+
+// Tests {{{
+
+#[cfg(test)]
+fn glob_matches(glob: &str, path: &[u8]) -> bool {
+	let glob = RawGlob::new(glob).unwrap();
+	let path = RawStr::from_bytes(path);
+	let result = glob.is_match(path);
+	// All strategies must agree with the regex.
+	assert_eq!(result, glob.regex().is_match(path), "{}", glob.regex());
+	result
+}
+
+#[test]
+fn test_glob() {
+	assert!(glob_matches("a?c", b"abc"));
+	assert!(glob_matches("a?c", b"a\xFFc"));
+	assert!(glob_matches("a?c", "aéc".as_bytes()));
+	assert!(!glob_matches("a?c", b"a/c"));
+	assert!(!glob_matches("a?c", b"abbc"));
+	assert!(glob_matches("*.rs", b"main.rs"));
+	assert!(glob_matches("*.rs", b".rs"));
+	assert!(!glob_matches("*.rs", b"src/main.rs"));
+	assert!(!glob_matches("*.rs", b"main.rsx"));
+	assert!(glob_matches("**/*.rs", b"main.rs"));
+	assert!(glob_matches("**/*.rs", b"a/b/main.rs"));
+	assert!(!glob_matches("**/*.rs", b"a.rs/b"));
+	assert!(glob_matches("a/**", b"a/b/c"));
+	assert!(!glob_matches("a/**", b"ab"));
+	assert!(glob_matches("a/**/b", b"a/b"));
+	assert!(glob_matches("a/**/b", b"a/x/y/b"));
+	assert!(!glob_matches("a**b", b"a/b"));
+	assert!(glob_matches("a**b", b"axb"));
+	assert!(glob_matches("[a-c]x", b"bx"));
+	assert!(!glob_matches("[!a-c]x", b"bx"));
+	assert!(glob_matches("[!a-c]x", b"\xFFx"));
+	assert!(!glob_matches("[!a-c]x", b"/x"));
+	assert!(glob_matches("[]]", b"]"));
+	assert!(glob_matches("[!]]", b"a"));
+	assert!(glob_matches("[[:digit:]-]", b"-"));
+	assert!(glob_matches("{a,b{c,d}}.txt", b"bd.txt"));
+	assert!(!glob_matches("{a,b{c,d}}.txt", b"b.txt"));
+	assert!(glob_matches("{,x}y", b"y"));
+	assert!(glob_matches("\\*(a+b).$", b"*(a+b).$"));
+	assert!(glob_matches("fée.txt", "fée.txt".as_bytes()));
+	assert!(glob_matches("a,b}", b"a,b}"));
+	assert!(glob_matches("*", b""));
+	assert!(glob_matches("", b""));
+	assert!(!glob_matches("", b"a"));
+}
+
+#[test]
+fn test_glob_raw_and_options() {
+	let glob = RawGlob::new(RawStr::from_bytes(b"*\xFF[\xFE-\xFF]")).unwrap();
+	assert!(glob.is_match(RawStr::from_bytes(b"ab\xFF\xFE")));
+	assert!(!glob.is_match(RawStr::from_bytes(b"ab\xFFx")));
+
+	let glob = RawGlob::builder().case_insensitive(true).build("*.TXT").unwrap();
+	assert!(glob.is_match(RawStr::from_str("Ünïcode.txt")));
+	assert!(RawGlob::builder().case_insensitive(true).build("ÄB").unwrap().is_match(RawStr::from_str("äb")));
+
+	let glob = RawGlob::builder().literal_separator(false).build("*.rs").unwrap();
+	assert!(glob.is_match(RawStr::from_str("src/main.rs")));
+	assert!(glob.regex().is_match(RawStr::from_str("src/main.rs")));
+}
+
+#[test]
+fn test_glob_errors() {
+	for &(glob, position, message) in &[
+		("[a", 0, "unclosed character class"),
+		("x[!", 1, "unclosed character class"),
+		("[z-a]", 1, "invalid character class range"),
+		("[[:foo]", 1, "invalid POSIX character class"),
+		("a[[:foo:]]", 2, "invalid POSIX character class"),
+		("[a[:Alpha:]]", 2, "invalid POSIX character class"),
+		("{a,b", 0, "unclosed alternation"),
+		("a\\", 1, "incomplete escape sequence"),
+	] {
+		let error = RawGlob::new(glob).unwrap_err();
+		assert_eq!((error.position(), error.message()), (position, message), "{}", glob);
+	}
+
+	let nested = |depth: usize| format!("{}**/*a{}", "{".repeat(depth), "}".repeat(depth));
+	let globs = [RawGlob::new(nested(MAX_NESTING).as_str()).unwrap(), RawGlob::new("x*").unwrap()];
+	assert!(GlobSet::new(&globs).unwrap().is_match(RawStr::from_str("b/a")));
+	let error = RawGlob::new(format!("x{}", nested(30_000)).as_str()).unwrap_err();
+	assert_eq!((error.position(), error.message()), (MAX_NESTING + 1, "exceeds the nesting limit"));
+	let error = RawGlob::new("?".repeat(200_000).as_str()).err().map(|e| e.to_string());
+	assert_eq!(error.as_deref(), Some("glob error at position 0: glob is too large"));
+}
+
+#[test]
+fn test_glob_set() {
+	let globs: Vec<RawGlob> = ["*.rs", "src/lib.rs", "**/*.rs", "{src,test}/*", "*.RS", "src/lib.rs"]
+		.iter()
+		.map(|g| RawGlob::new(g).unwrap())
+		.collect();
+	let set = GlobSet::new(&globs).unwrap();
+	assert_eq!(set.len(), 6);
+	for path in &["main.rs", "src/lib.rs", "src/x", "test/a.rs", "x/y", "A.RS", ""] {
+		let path = RawStr::from_str(path);
+		let expected: Vec<usize> = (0..globs.len()).filter(|&i| globs[i].is_match(path)).collect();
+		assert_eq!(set.matches(path), expected, "{}", path);
+		assert_eq!(set.is_match(path), !expected.is_empty(), "{}", path);
+	}
+	assert_eq!(set.matches(RawStr::from_str("src/lib.rs")), [1, 2, 3, 5]);
+	assert!(GlobSet::new(&[]).unwrap().is_empty());
+}
+
+// }}}
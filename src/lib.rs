@@ -5,14 +5,31 @@
 //! They are useful in all places where you would otherwise use
 //! `Vec<u8>` and `[u8]` to represent your strings.
 
-// TODO: Remove this once docs.rs supports rust stable 1.28 or later.
-#![cfg_attr(feature="old-nightly",feature(slice_get_slice))]
-
+mod env;
+mod fs;
+mod process;
+mod sanitize;
+mod shell;
 mod str;
 mod string;
 
+#[cfg(feature = "unicode")]
+mod unicode;
+
+pub use env::*;
+pub use fs::*;
+pub use process::*;
+pub use sanitize::*;
+pub use shell::*;
 pub use str::*;
 pub use string::*;
+#[cfg(feature = "unicode")]
+pub use unicode::*;
+
+pub mod ansi;
+pub mod encoding;
+pub mod glob;
+pub mod regex;
 
 #[cfg(unix)]
 pub mod unix;
@@ -0,0 +1,132 @@
+use env::to_os;
+use std::process::{Command, Output};
+use str::RawStr;
+use string::RawString;
+
+/// `RawStr` arguments and environment variables for a `Command`.
+///
+/// On Unix, these are passed on exactly. On Windows, they are converted
+/// lossily if they aren't valid WTF-8, and on other platforms if they aren't
+/// valid UTF-8. See [`set_raw_var`](fn.set_raw_var.html).
+///
+/// # Example
+///
+/// ```no_run
+/// # use raw_string::{RawCommandExt, RawOutputExt, RawStr};
+/// # use std::process::Command;
+/// let output = Command::new("ls")
+///     .raw_arg(RawStr::from_bytes(b"\xFF"))
+///     .raw_env("LC_ALL", "C")
+///     .output()?;
+/// for line in output.raw_stdout().lines() {
+///     println!("{}", line.display_safe());
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub trait RawCommandExt {
+	/// Add an argument, like `Command::arg`.
+	fn raw_arg<S: AsRef<RawStr>>(&mut self, arg: S) -> &mut Self;
+
+	/// Add arguments, like `Command::args`.
+	fn raw_args<I>(&mut self, args: I) -> &mut Self
+	where
+		I: IntoIterator,
+		I::Item: AsRef<RawStr>;
+
+	/// Set an environment variable, like `Command::env`.
+	fn raw_env<K: AsRef<RawStr>, V: AsRef<RawStr>>(&mut self, key: K, value: V) -> &mut Self;
+}
+
+impl RawCommandExt for Command {
+	#[inline]
+	fn raw_arg<S: AsRef<RawStr>>(&mut self, arg: S) -> &mut Self {
+		self.arg(to_os(arg.as_ref()))
+	}
+
+	fn raw_args<I>(&mut self, args: I) -> &mut Self
+	where
+		I: IntoIterator,
+		I::Item: AsRef<RawStr>,
+	{
+		for arg in args {
+			self.raw_arg(arg);
+		}
+		self
+	}
+
+	#[inline]
+	fn raw_env<K: AsRef<RawStr>, V: AsRef<RawStr>>(&mut self, key: K, value: V) -> &mut Self {
+		self.env(to_os(key.as_ref()), to_os(value.as_ref()))
+	}
+}
+
+/// The captured output of a process as `RawStr` and `RawString`.
+///
+/// The `into_` methods reuse the buffers of the `Output`, without copying.
+pub trait RawOutputExt {
+	/// The captured standard output.
+	fn raw_stdout(&self) -> &RawStr;
+
+	/// The captured standard error.
+	fn raw_stderr(&self) -> &RawStr;
+
+	/// Take the captured standard output.
+	fn into_raw_stdout(self) -> RawString;
+
+	/// Take the captured standard error.
+	fn into_raw_stderr(self) -> RawString;
+
+	/// Take both the captured standard output and standard error.
+	fn into_raw_output(self) -> (RawString, RawString);
+}
+
+impl RawOutputExt for Output {
+	#[inline]
+	fn raw_stdout(&self) -> &RawStr {
+		RawStr::from_bytes(&self.stdout)
+	}
+
+	#[inline]
+	fn raw_stderr(&self) -> &RawStr {
+		RawStr::from_bytes(&self.stderr)
+	}
+
+	#[inline]
+	fn into_raw_stdout(self) -> RawString {
+		RawString::from_bytes(self.stdout)
+	}
+
+	#[inline]
+	fn into_raw_stderr(self) -> RawString {
+		RawString::from_bytes(self.stderr)
+	}
+
+	#[inline]
+	fn into_raw_output(self) -> (RawString, RawString) {
+		(RawString::from_bytes(self.stdout), RawString::from_bytes(self.stderr))
+	}
+}
+
+// Tests {{{
+
+#[cfg(unix)]
+#[test]
+fn test_raw_command() {
+	let output = Command::new("sh")
+		.raw_arg("-c")
+		.raw_arg(r#"printf '%s\n' "$@" "$X"; printf 'e\r\n' >&2"#)
+		.raw_args(&[RawStr::from_str("sh"), RawStr::from_bytes(b"a\xFF"), RawStr::from_str("b c")])
+		.raw_env("X", RawStr::from_bytes(b"\xFE"))
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+	let lines: Vec<&RawStr> = output.raw_stdout().lines().collect();
+	assert_eq!(lines, [&b"a\xFF"[..], b"b c", b"\xFE"]);
+	assert_eq!(output.raw_stderr(), "e\r\n");
+	let pointer = output.stdout.as_ptr();
+	let (stdout, stderr) = output.into_raw_output();
+	assert_eq!(stdout.as_bytes().as_ptr(), pointer);
+	assert_eq!(stderr.lines().next(), Some(RawStr::from_str("e")));
+}
+
+// }}}
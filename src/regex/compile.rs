@@ -0,0 +1,433 @@
+use std::str::from_utf8;
+use super::parse::Node;
+use super::Error;
+
+/// The largest number of instructions a program may have.
+const MAX_PROGRAM_SIZE: usize = 100_000;
+
+/// The message of the error for programs that are too large.
+pub(super) const SIZE_LIMIT_MESSAGE: &str = "compiled regex exceeds size limit";
+
+/// A compiled regular expression: a program for the virtual machine.
+#[derive(Clone, Debug)]
+pub(super) struct Program {
+	pub insts: Vec<Inst>,
+	pub classes: Vec<Class>,
+	/// The number of capture slots: two per group, including group 0.
+	pub slots: usize,
+	/// Whether every match must start at the start of the haystack.
+	pub anchored_start: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Inst {
+	Match,
+	/// Match one byte.
+	Byte(u8),
+	/// Match the class with this index in `Program::classes`.
+	Class(usize),
+	/// Continue at both targets, preferring the first.
+	Split(usize, usize),
+	Jump(usize),
+	/// Store the current position in a capture slot.
+	Save(usize),
+	Look(Look),
+}
+
+/// A zero-width assertion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Look {
+	/// `\A`, or `^` without the `m` flag.
+	Start,
+	/// `\z`, or `$` without the `m` flag.
+	End,
+	/// `^` with the `m` flag.
+	LineStart,
+	/// `$` with the `m` flag.
+	LineEnd,
+	/// `\b`
+	WordBoundary,
+	/// `\B`
+	NotWordBoundary,
+}
+
+impl Look {
+	pub fn holds(self, haystack: &[u8], pos: usize) -> bool {
+		match self {
+			Look::Start => pos == 0,
+			Look::End => pos == haystack.len(),
+			Look::LineStart => pos == 0 || haystack[pos - 1] == b'\n',
+			Look::LineEnd => pos == haystack.len() || haystack[pos] == b'\n',
+			Look::WordBoundary => is_word_before(haystack, pos) != is_word_after(haystack, pos),
+			Look::NotWordBoundary => is_word_before(haystack, pos) == is_word_after(haystack, pos),
+		}
+	}
+}
+
+/// A Unicode property, tested on characters in valid UTF-8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Predicate {
+	Any,
+	Ascii,
+	Alphabetic,
+	Lowercase,
+	Uppercase,
+	Numeric,
+	WhiteSpace,
+	Control,
+	/// Alphanumeric or `_`, as for `\w`.
+	Word,
+}
+
+impl Predicate {
+	/// Look up a property by name, as in `\p{name}`.
+	///
+	/// Case, spaces, `-` and `_` are ignored.
+	pub fn from_name(name: &str) -> Option<Predicate> {
+		let name: String = name
+			.chars()
+			.filter(|&c| c != ' ' && c != '-' && c != '_')
+			.map(|c| c.to_ascii_lowercase())
+			.collect();
+		Some(match &name[..] {
+			"any" => Predicate::Any,
+			"ascii" => Predicate::Ascii,
+			"alphabetic" | "alpha" | "l" | "letter" => Predicate::Alphabetic,
+			"lowercase" | "lower" => Predicate::Lowercase,
+			"uppercase" | "upper" => Predicate::Uppercase,
+			"numeric" | "n" | "number" => Predicate::Numeric,
+			"whitespace" | "space" => Predicate::WhiteSpace,
+			"control" | "cc" => Predicate::Control,
+			"word" => Predicate::Word,
+			_ => return None,
+		})
+	}
+
+	fn matches(self, c: char) -> bool {
+		match self {
+			Predicate::Any => true,
+			Predicate::Ascii => c.is_ascii(),
+			Predicate::Alphabetic => c.is_alphabetic(),
+			Predicate::Lowercase => c.is_lowercase(),
+			Predicate::Uppercase => c.is_uppercase(),
+			Predicate::Numeric => c.is_numeric(),
+			Predicate::WhiteSpace => c.is_whitespace(),
+			Predicate::Control => c.is_control(),
+			Predicate::Word => is_word_char(c),
+		}
+	}
+}
+
+/// A set of characters and bytes.
+///
+/// Characters only match valid UTF-8. Bytes match any single byte, even if
+/// it's part of a valid UTF-8 sequence.
+///
+/// A negated class matches any character that's not in the set, and any byte
+/// that's not in the set and not part of a valid UTF-8 sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct Class {
+	/// Sorted, non-overlapping, inclusive ranges.
+	ranges: Vec<(char, char)>,
+	pub predicates: Vec<(Predicate, bool)>,
+	/// A bitmap of the non-ASCII bytes.
+	bytes: [u64; 2],
+	pub negated: bool,
+	/// Whether a character also matches if its upper or lower case version is
+	/// in the set.
+	pub case_insensitive: bool,
+}
+
+impl Class {
+	pub fn new() -> Class {
+		Class {
+			ranges: Vec::new(),
+			predicates: Vec::new(),
+			bytes: [0; 2],
+			negated: false,
+			case_insensitive: false,
+		}
+	}
+
+	pub fn push_range(&mut self, a: char, b: char) {
+		let i = self.ranges.iter().position(|r| r.0 > a).unwrap_or(self.ranges.len());
+		self.ranges.insert(i, (a, b));
+		// Merge overlapping ranges.
+		let mut merged: Vec<(char, char)> = Vec::with_capacity(self.ranges.len());
+		for &(a, b) in &self.ranges {
+			match merged.last_mut() {
+				Some(last) if a <= last.1 || u32::from(a) == u32::from(last.1) + 1 => {
+					last.1 = last.1.max(b)
+				}
+				_ => merged.push((a, b)),
+			}
+		}
+		self.ranges = merged;
+	}
+
+	/// Add a range of bytes. Both must be 0x80 or higher.
+	pub fn push_byte_range(&mut self, a: u8, b: u8) {
+		for x in a..=b {
+			let x = x - 0x80;
+			self.bytes[usize::from(x / 64)] |= 1 << (x % 64);
+		}
+	}
+
+	fn contains_exactly(&self, c: char) -> bool {
+		let in_ranges = self
+			.ranges
+			.binary_search_by(|&(a, b)| {
+				if b < c {
+					::std::cmp::Ordering::Less
+				} else if a > c {
+					::std::cmp::Ordering::Greater
+				} else {
+					::std::cmp::Ordering::Equal
+				}
+			})
+			.is_ok();
+		in_ranges || self.predicates.iter().any(|&(p, negated)| p.matches(c) != negated)
+	}
+
+	fn contains_char(&self, c: char) -> bool {
+		self.contains_exactly(c)
+			|| self.case_insensitive
+				&& (simple_case(c.to_lowercase()).is_some_and(|l| self.contains_exactly(l))
+					|| simple_case(c.to_uppercase()).is_some_and(|u| self.contains_exactly(u)))
+	}
+
+	fn contains_byte(&self, b: u8) -> bool {
+		b >= 0x80 && self.bytes[usize::from((b - 0x80) / 64)] & 1 << ((b - 0x80) % 64) != 0
+	}
+
+	/// Match at `pos`.
+	///
+	/// Returns the length of the matched character, if any, and whether the
+	/// single byte at `pos` matched.
+	pub fn matches(&self, haystack: &[u8], pos: usize) -> (Option<usize>, bool) {
+		let unit = char_at(haystack, pos);
+		if self.negated {
+			match unit {
+				Some((c, len)) => (Some(len).filter(|_| !self.contains_char(c)), false),
+				None => (None, pos < haystack.len() && !self.contains_byte(haystack[pos])),
+			}
+		} else {
+			let c = unit.filter(|&(c, _)| self.contains_char(c)).map(|(_, len)| len);
+			(c, pos < haystack.len() && self.contains_byte(haystack[pos]))
+		}
+	}
+}
+
+/// The single character a case mapping maps to, if it's a single character.
+fn simple_case<I: Iterator<Item = char>>(mut mapping: I) -> Option<char> {
+	let c = mapping.next()?;
+	match mapping.next() {
+		None => Some(c),
+		Some(_) => None,
+	}
+}
+
+/// Decode the valid UTF-8 character at `pos`, and return it with its length.
+pub(super) fn char_at(haystack: &[u8], pos: usize) -> Option<(char, usize)> {
+	let len = match *haystack.get(pos)? {
+		0x00..=0x7F => 1,
+		0xC2..=0xDF => 2,
+		0xE0..=0xEF => 3,
+		0xF0..=0xF4 => 4,
+		_ => return None,
+	};
+	let s = from_utf8(haystack.get(pos..pos + len)?).ok()?;
+	s.chars().next().map(|c| (c, len))
+}
+
+/// Decode the valid UTF-8 character that ends at `pos`.
+fn char_before(haystack: &[u8], pos: usize) -> Option<char> {
+	(1..=4.min(pos)).find_map(|len| match char_at(haystack, pos - len) {
+		Some((c, l)) if l == len => Some(c),
+		_ => None,
+	})
+}
+
+/// Whether `pos` is not inside a valid UTF-8 sequence.
+pub(super) fn is_char_boundary(haystack: &[u8], pos: usize) -> bool {
+	(1..=3.min(pos)).all(|back| char_at(haystack, pos - back).map_or(true, |(_, len)| len <= back))
+}
+
+fn is_word_char(c: char) -> bool {
+	c.is_alphanumeric() || c == '_'
+}
+
+fn is_word_before(haystack: &[u8], pos: usize) -> bool {
+	char_before(haystack, pos).is_some_and(is_word_char)
+}
+
+fn is_word_after(haystack: &[u8], pos: usize) -> bool {
+	char_at(haystack, pos).is_some_and(|(c, _)| is_word_char(c))
+}
+
+// Compiler {{{
+
+struct Compiler {
+	insts: Vec<Inst>,
+	classes: Vec<Class>,
+}
+
+pub(super) fn compile(node: &Node, groups: usize) -> Result<Program, Error> {
+	let mut compiler = Compiler {
+		insts: Vec::new(),
+		classes: Vec::new(),
+	};
+	compiler.push(Inst::Save(0))?;
+	compiler.compile(node)?;
+	compiler.push(Inst::Save(1))?;
+	compiler.push(Inst::Match)?;
+	Ok(Program {
+		insts: compiler.insts,
+		classes: compiler.classes,
+		slots: groups * 2,
+		anchored_start: starts_anchored(node),
+	})
+}
+
+/// Whether all matches of `node` must start at the start of the haystack.
+fn starts_anchored(node: &Node) -> bool {
+	match *node {
+		Node::Look(Look::Start) => true,
+		Node::Capture(ref node, _) => starts_anchored(node),
+		Node::Concat(ref nodes) => nodes.first().is_some_and(starts_anchored),
+		Node::Alternation(ref nodes) => nodes.iter().all(starts_anchored),
+		_ => false,
+	}
+}
+
+impl Compiler {
+	fn push(&mut self, inst: Inst) -> Result<usize, Error> {
+		if self.insts.len() >= MAX_PROGRAM_SIZE {
+			return Err(Error::new(SIZE_LIMIT_MESSAGE.to_string(), 0));
+		}
+		self.insts.push(inst);
+		Ok(self.insts.len() - 1)
+	}
+
+	/// Point the jump or split at `pc` to the next instruction.
+	fn patch(&mut self, pc: usize) {
+		let target = self.insts.len();
+		match self.insts[pc] {
+			Inst::Jump(ref mut t) => *t = target,
+			Inst::Split(_, ref mut t) => *t = target,
+			_ => unreachable!(),
+		}
+	}
+
+	fn compile(&mut self, node: &Node) -> Result<(), Error> {
+		match *node {
+			Node::Empty => {}
+			Node::Char(c) => {
+				let mut buf = [0; 4];
+				for &b in c.encode_utf8(&mut buf).as_bytes() {
+					self.push(Inst::Byte(b))?;
+				}
+			}
+			Node::Byte(b) => {
+				self.push(Inst::Byte(b))?;
+			}
+			Node::Class(ref class) => {
+				let i = match self.classes.iter().position(|c| c == class) {
+					Some(i) => i,
+					None => {
+						self.classes.push(class.clone());
+						self.classes.len() - 1
+					}
+				};
+				self.push(Inst::Class(i))?;
+			}
+			Node::Look(look) => {
+				self.push(Inst::Look(look))?;
+			}
+			Node::Capture(ref node, index) => {
+				self.push(Inst::Save(index * 2))?;
+				self.compile(node)?;
+				self.push(Inst::Save(index * 2 + 1))?;
+			}
+			Node::Concat(ref nodes) => {
+				for node in nodes {
+					self.compile(node)?;
+				}
+			}
+			Node::Alternation(ref nodes) => {
+				let mut jumps = Vec::new();
+				for node in &nodes[..nodes.len() - 1] {
+					let split = self.push(Inst::Split(self.insts.len() + 1, 0))?;
+					self.compile(node)?;
+					jumps.push(self.push(Inst::Jump(0))?);
+					self.patch(split);
+				}
+				self.compile(&nodes[nodes.len() - 1])?;
+				for jump in jumps {
+					self.patch(jump);
+				}
+			}
+			Node::Repeat {
+				ref node,
+				min,
+				max,
+				greedy,
+			} => {
+				for _ in 0..min {
+					self.compile(node)?;
+				}
+				match max {
+					None => {
+						// L: split(body, end); body; jump L; end:
+						let split = self.push(Inst::Split(self.insts.len() + 1, 0))?;
+						self.compile(node)?;
+						self.push(Inst::Jump(split))?;
+						self.patch(split);
+						self.set_greedy(split, greedy);
+					}
+					Some(max) => {
+						// split(body, end); body; split(body, end); body; ... end:
+						let mut splits = Vec::new();
+						for _ in min..max {
+							splits.push(self.push(Inst::Split(self.insts.len() + 1, 0))?);
+							self.compile(node)?;
+						}
+						for split in splits {
+							self.patch(split);
+							self.set_greedy(split, greedy);
+						}
+					}
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Swap the preference of a split, for lazy repetitions.
+	fn set_greedy(&mut self, pc: usize, greedy: bool) {
+		if let Inst::Split(a, b) = self.insts[pc] {
+			if !greedy {
+				self.insts[pc] = Inst::Split(b, a);
+			}
+		}
+	}
+}
+
+// }}}
+
+// Tests {{{
+
+#[test]
+fn test_char_helpers() {
+	let s = b"a\xC3\xA9\xFF\xE2\x82";
+	assert_eq!(char_at(s, 0), Some(('a', 1)));
+	assert_eq!(char_at(s, 1), Some(('é', 2)));
+	assert_eq!(char_at(s, 2), None);
+	assert_eq!(char_at(s, 4), None);
+	assert_eq!(char_before(s, 3), Some('é'));
+	assert_eq!(char_before(s, 4), None);
+	let boundaries: Vec<bool> = (0..=s.len()).map(|i| is_char_boundary(s, i)).collect();
+	assert_eq!(boundaries, [true, true, false, true, true, true, true]);
+}
+
+// }}}
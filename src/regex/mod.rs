@@ -0,0 +1,648 @@
+//! Regular expressions over `RawStr`.
+//!
+//! The engine works on bytes, so it can search data that isn't (all) valid
+//! UTF-8. Valid UTF-8 is still treated as text: a match never starts inside a
+//! valid UTF-8 sequence, and `.`, classes and Unicode properties consume whole
+//! characters. Only `\xNN` can match a single byte of a valid UTF-8 sequence.
+//!
+//! It never backtracks, so searching takes time linear in the size of the
+//! haystack, for every pattern.
+//!
+//! # Syntax
+//!
+//! The syntax is a subset of that of the popular `regex` crate:
+//!
+//! - `.` matches any character except `\n`, or any byte that isn't part of
+//!   valid UTF-8.
+//! - `[abc]`, `[^a-z]`, `[[:alpha:]]` match a character from a set. A negated
+//!   set also matches any byte that isn't part of valid UTF-8.
+//! - `\d`, `\w`, `\s` (and `\D`, `\W`, `\S`) match numeric, word and white
+//!   space characters, according to the Unicode properties of `char`.
+//! - `\p{Alphabetic}`, `\p{Lowercase}`, `\p{Uppercase}`, `\p{Numeric}`,
+//!   `\p{White_Space}`, `\p{Control}`, `\p{ASCII}` and `\p{Any}`, and their
+//!   negations with `\P`.
+//! - `\xNN` matches the byte `NN`, even if it's part of valid UTF-8, if it is
+//!   `80` or higher. `\x{NNNN}` matches a character.
+//! - `\n`, `\t`, `\r`, `\f`, `\v`, `\a` and escaped punctuation.
+//! - `^`, `$`, `\A`, `\z`, `\b` and `\B`.
+//! - `*`, `+`, `?`, `{n}`, `{n,}`, `{n,m}`, and their lazy versions.
+//! - `(...)`, `(?:...)`, `(?P<name>...)` and `(?<name>...)`.
+//! - The flags `i` (case insensitive), `m` (multi-line), `s` (`.` matches `\n`)
+//!   and `x` (ignore white space and `#` comments), as `(?flags)` or
+//!   `(?flags:...)`.
+//!
+//! # Example
+//!
+//! ```
+//! # use raw_string::RawStr;
+//! # use raw_string::regex::Regex;
+//! let re = Regex::new(r"(?P<key>\w+)=(\d+)").unwrap();
+//! let s = RawStr::from_bytes(b"x=1 \xFF y=22");
+//! let caps = re.captures(s).unwrap();
+//! assert_eq!(&caps["key"], "x");
+//! assert_eq!(re.replace_all(s, "$2:$key"), &b"1:x \xFF 22:y"[..]);
+//! ```
+
+use std;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::iter::FusedIterator;
+use std::ops::{Index, Range};
+use std::sync::Arc;
+use str::{RawPattern, RawSearcher, RawStr, Split};
+use string::RawString;
+
+mod compile;
+mod parse;
+mod vm;
+
+use self::compile::{char_at, Program};
+use self::vm::Search;
+
+pub(crate) use self::parse::is_posix_class;
+
+/// A compiled regular expression.
+///
+/// See the [module documentation](index.html) for the syntax.
+#[derive(Clone, Debug)]
+pub struct Regex {
+	pattern: String,
+	program: Program,
+	names: Arc<Vec<Option<String>>>,
+}
+
+/// An error in the syntax of a regular expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+	message: String,
+	position: usize,
+}
+
+impl Error {
+	fn new(message: String, position: usize) -> Error {
+		Error { message, position }
+	}
+
+	/// A description of the error.
+	#[inline]
+	pub fn message(&self) -> &str {
+		&self.message
+	}
+
+	/// The byte offset in the pattern at which the error was found.
+	#[inline]
+	pub fn position(&self) -> usize {
+		self.position
+	}
+
+	/// Whether the pattern is valid, but compiles to a program that is too
+	/// large.
+	pub(crate) fn is_size_limit(&self) -> bool {
+		self.message == compile::SIZE_LIMIT_MESSAGE
+	}
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+		write!(f, "regex parse error at position {}: {}", self.position, self.message)
+	}
+}
+
+impl error::Error for Error {}
+
+impl Regex {
+	/// Compile a regular expression.
+	pub fn new(pattern: &str) -> Result<Regex, Error> {
+		let parsed = parse::parse(pattern)?;
+		let program = compile::compile(&parsed.node, parsed.names.len())?;
+		Ok(Regex {
+			pattern: pattern.to_string(),
+			program,
+			names: Arc::new(parsed.names),
+		})
+	}
+
+	/// The pattern this was compiled from.
+	#[inline]
+	pub fn as_str(&self) -> &str {
+		&self.pattern
+	}
+
+	/// The number of capture groups, including the implicit group 0 for the
+	/// whole match.
+	#[inline]
+	pub fn captures_len(&self) -> usize {
+		self.names.len()
+	}
+
+	/// The names of the capture groups, in order, including the implicit
+	/// group 0 for the whole match.
+	#[inline]
+	pub fn capture_names(&self) -> impl Iterator<Item = Option<&str>> {
+		self.names.iter().map(|name| name.as_ref().map(|name| &name[..]))
+	}
+
+	/// Whether the regex matches anywhere in the haystack.
+	pub fn is_match(&self, haystack: &RawStr) -> bool {
+		self.find(haystack).is_some()
+	}
+
+	/// Find the leftmost match.
+	#[inline]
+	pub fn find<'h>(&self, haystack: &'h RawStr) -> Option<Match<'h>> {
+		self.find_at(haystack, 0)
+	}
+
+	/// Find the leftmost match that starts at or after `start`.
+	///
+	/// Unlike searching `&haystack[start..]`, this lets `^`, `\b` and so on see
+	/// the text before `start`.
+	pub fn find_at<'h>(&self, haystack: &'h RawStr, start: usize) -> Option<Match<'h>> {
+		let mut slots = [None, None];
+		if !Search::new(&self.program, haystack.as_bytes(), haystack.len()).run(start, false, &mut slots) {
+			return None;
+		}
+		Some(Match::new(haystack, slots[0]?, slots[1]?))
+	}
+
+	/// Iterate over all non-overlapping matches.
+	#[inline]
+	pub fn find_iter<'r, 'h>(&'r self, haystack: &'h RawStr) -> FindIter<'r, 'h> {
+		FindIter {
+			regex: self,
+			haystack,
+			position: 0,
+			last_end: None,
+		}
+	}
+
+	/// Find the leftmost match, with the matches of all capture groups.
+	#[inline]
+	pub fn captures<'h>(&self, haystack: &'h RawStr) -> Option<Captures<'h>> {
+		self.captures_at(haystack, 0)
+	}
+
+	/// Find the leftmost match that starts at or after `start`, with the
+	/// matches of all capture groups.
+	pub fn captures_at<'h>(&self, haystack: &'h RawStr, start: usize) -> Option<Captures<'h>> {
+		let mut slots = vec![None; self.program.slots];
+		if !Search::new(&self.program, haystack.as_bytes(), haystack.len()).run(start, false, &mut slots) {
+			return None;
+		}
+		Some(Captures {
+			haystack,
+			slots,
+			names: self.names.clone(),
+		})
+	}
+
+	/// Iterate over all non-overlapping matches, with the matches of all
+	/// capture groups.
+	#[inline]
+	pub fn captures_iter<'r, 'h>(&'r self, haystack: &'h RawStr) -> CapturesIter<'r, 'h> {
+		CapturesIter {
+			regex: self,
+			haystack,
+			position: 0,
+			last_end: None,
+		}
+	}
+
+	/// Iterate over the parts of the haystack, separated by matches.
+	///
+	/// This is the same as `haystack.split(self)`.
+	#[inline]
+	pub fn split<'r, 'h>(&'r self, haystack: &'h RawStr) -> Split<'h, &'r Regex> {
+		haystack.split(self)
+	}
+
+	/// Replace the leftmost match.
+	///
+	/// See [`replace_all`](#method.replace_all).
+	#[inline]
+	pub fn replace<T: AsRef<RawStr>>(&self, haystack: &RawStr, replacement: T) -> RawString {
+		self.replacen(haystack, 1, replacement)
+	}
+
+	/// Replace all non-overlapping matches.
+	///
+	/// In the replacement, `$1` or `${1}` is replaced by the match of the
+	/// first capture group, `$name` or `${name}` by the match of the group
+	/// with that name, and `$$` by `$`. Groups that did not match are replaced
+	/// by nothing.
+	///
+	/// `$name` takes the longest sequence of letters, digits and underscores,
+	/// so use `${1}a` instead of `$1a` to put an `a` after the first group.
+	#[inline]
+	pub fn replace_all<T: AsRef<RawStr>>(&self, haystack: &RawStr, replacement: T) -> RawString {
+		self.replacen(haystack, usize::MAX, replacement)
+	}
+
+	/// Replace the first `limit` non-overlapping matches.
+	///
+	/// See [`replace_all`](#method.replace_all).
+	pub fn replacen<T: AsRef<RawStr>>(&self, haystack: &RawStr, limit: usize, replacement: T) -> RawString {
+		let replacement = replacement.as_ref();
+		let mut result = RawString::with_capacity(haystack.len());
+		let mut last_end = 0;
+		if replacement.contains_byte(b'$') {
+			for caps in self.captures_iter(haystack).take(limit) {
+				let m = caps.get(0).unwrap();
+				result.push_str(&haystack[last_end..m.start()]);
+				caps.expand(replacement, &mut result);
+				last_end = m.end();
+			}
+		} else {
+			for m in self.find_iter(haystack).take(limit) {
+				result.push_str(&haystack[last_end..m.start()]);
+				result.push_str(replacement);
+				last_end = m.end();
+			}
+		}
+		result.push_str(&haystack[last_end..]);
+		result
+	}
+
+	/// Find the leftmost match that starts exactly at `start`, and doesn't
+	/// extend beyond `end`.
+	fn find_anchored(&self, haystack: &RawStr, start: usize, end: usize) -> Option<Range<usize>> {
+		let mut slots = [None, None];
+		if !Search::new(&self.program, haystack.as_bytes(), end).run(start, true, &mut slots) {
+			return None;
+		}
+		Some(slots[0]?..slots[1]?)
+	}
+
+	/// Where to continue searching after a match, for the iterators.
+	///
+	/// After an empty match, this skips a character, so that it isn't found
+	/// again.
+	fn next_position(haystack: &RawStr, m: &Range<usize>) -> usize {
+		if m.start == m.end {
+			m.end + char_at(haystack.as_bytes(), m.end).map_or(1, |(_, len)| len)
+		} else {
+			m.end
+		}
+	}
+}
+
+impl Display for Regex {
+	fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+		f.write_str(&self.pattern)
+	}
+}
+
+impl RawSearcher for Regex {
+	#[inline]
+	fn find_at(&self, haystack: &RawStr, start: usize) -> Option<Range<usize>> {
+		Regex::find_at(self, haystack, start).map(|m| m.range())
+	}
+
+	fn rfind_at(&self, haystack: &RawStr, end: usize) -> Option<Range<usize>> {
+		if end > haystack.len() {
+			return None;
+		}
+		let start = Search::new(&self.program, haystack.as_bytes(), end).last_start(0)?;
+		self.find_anchored(haystack, start, end)
+	}
+}
+
+impl<'r> RawPattern for &'r Regex {
+	type Searcher = &'r Regex;
+	#[inline]
+	fn into_searcher(self) -> &'r Regex {
+		self
+	}
+}
+
+// Match / Captures {{{
+
+/// A match of a [`Regex`](struct.Regex.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match<'h> {
+	haystack: &'h RawStr,
+	start: usize,
+	end: usize,
+}
+
+impl<'h> Match<'h> {
+	#[inline]
+	fn new(haystack: &'h RawStr, start: usize, end: usize) -> Match<'h> {
+		Match { haystack, start, end }
+	}
+
+	/// The start of the match in the haystack.
+	#[inline]
+	pub fn start(&self) -> usize {
+		self.start
+	}
+
+	/// The end of the match in the haystack.
+	#[inline]
+	pub fn end(&self) -> usize {
+		self.end
+	}
+
+	/// The range of the match in the haystack.
+	#[inline]
+	pub fn range(&self) -> Range<usize> {
+		self.start..self.end
+	}
+
+	/// The matched part of the haystack.
+	#[inline]
+	pub fn as_raw_str(&self) -> &'h RawStr {
+		&self.haystack[self.start..self.end]
+	}
+
+	/// Whether the match is empty.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.start == self.end
+	}
+
+	/// The length of the match, in bytes.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.end - self.start
+	}
+}
+
+/// The matches of the capture groups of a [`Regex`](struct.Regex.html).
+///
+/// Group 0 is the whole match.
+///
+/// Can be indexed by group number or name, which panics if the group didn't
+/// match.
+#[derive(Clone, Debug)]
+pub struct Captures<'h> {
+	haystack: &'h RawStr,
+	slots: Vec<Option<usize>>,
+	names: Arc<Vec<Option<String>>>,
+}
+
+impl<'h> Captures<'h> {
+	/// The match of the group with the given index, if it matched.
+	#[inline]
+	pub fn get(&self, i: usize) -> Option<Match<'h>> {
+		let start = (*self.slots.get(i * 2)?)?;
+		let end = (*self.slots.get(i * 2 + 1)?)?;
+		Some(Match::new(self.haystack, start, end))
+	}
+
+	/// The match of the group with the given name, if it matched.
+	#[inline]
+	pub fn name(&self, name: &str) -> Option<Match<'h>> {
+		let i = self.names.iter().position(|n| n.as_ref().is_some_and(|n| n == name))?;
+		self.get(i)
+	}
+
+	/// The number of groups, including group 0.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.names.len()
+	}
+
+	/// Always false, as there is always group 0.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		false
+	}
+
+	/// Iterate over the matches of all groups, in order.
+	#[inline]
+	pub fn iter<'c>(&'c self) -> impl Iterator<Item = Option<Match<'h>>> + 'c {
+		(0..self.len()).map(move |i| self.get(i))
+	}
+
+	/// Append `replacement` to `dst`, with the `$` references replaced by the
+	/// matches of the groups.
+	///
+	/// See [`Regex::replace_all`](struct.Regex.html#method.replace_all) for the
+	/// syntax.
+	pub fn expand(&self, replacement: &RawStr, dst: &mut RawString) {
+		let mut rest = replacement.as_bytes();
+		while let Some(i) = rest.iter().position(|&b| b == b'$') {
+			dst.push_str(RawStr::from_bytes(&rest[..i]));
+			rest = &rest[i + 1..];
+			if rest.first() == Some(&b'$') {
+				dst.push(b'$');
+				rest = &rest[1..];
+				continue;
+			}
+			let (name, len) = if rest.first() == Some(&b'{') {
+				match rest.iter().position(|&b| b == b'}') {
+					Some(end) => (&rest[1..end], end + 1),
+					None => (&rest[..0], 0),
+				}
+			} else {
+				let len = rest
+					.iter()
+					.position(|&b| !(b.is_ascii_alphanumeric() || b == b'_'))
+					.unwrap_or(rest.len());
+				(&rest[..len], len)
+			};
+			if len == 0 {
+				// Not a valid reference: keep the `$`.
+				dst.push(b'$');
+				continue;
+			}
+			rest = &rest[len..];
+			let name = RawStr::from_bytes(name);
+			let m = match name.to_str().ok().and_then(|n| n.parse().ok()) {
+				Some(i) => self.get(i),
+				None => name.to_str().ok().and_then(|n| self.name(n)),
+			};
+			if let Some(m) = m {
+				dst.push_str(m.as_raw_str());
+			}
+		}
+		dst.push_str(RawStr::from_bytes(rest));
+	}
+}
+
+impl<'h> Index<usize> for Captures<'h> {
+	type Output = RawStr;
+	fn index(&self, i: usize) -> &RawStr {
+		match self.get(i) {
+			Some(m) => m.as_raw_str(),
+			None => panic!("no match for group {}", i),
+		}
+	}
+}
+
+impl<'h, 'n> Index<&'n str> for Captures<'h> {
+	type Output = RawStr;
+	fn index(&self, name: &'n str) -> &RawStr {
+		match self.name(name) {
+			Some(m) => m.as_raw_str(),
+			None => panic!("no match for group '{}'", name),
+		}
+	}
+}
+
+// }}}
+
+// Iterators {{{
+
+/// Iterator over the matches of a [`Regex`](struct.Regex.html).
+///
+/// See [`Regex::find_iter`](struct.Regex.html#method.find_iter).
+#[derive(Clone, Debug)]
+pub struct FindIter<'r, 'h> {
+	regex: &'r Regex,
+	haystack: &'h RawStr,
+	position: usize,
+	last_end: Option<usize>,
+}
+
+impl<'r, 'h> Iterator for FindIter<'r, 'h> {
+	type Item = Match<'h>;
+	fn next(&mut self) -> Option<Match<'h>> {
+		loop {
+			let m = self.regex.find_at(self.haystack, self.position)?;
+			self.position = Regex::next_position(self.haystack, &m.range());
+			// An empty match right after the previous match doesn't count.
+			if m.is_empty() && self.last_end == Some(m.end) {
+				continue;
+			}
+			self.last_end = Some(m.end);
+			return Some(m);
+		}
+	}
+}
+
+impl<'r, 'h> FusedIterator for FindIter<'r, 'h> {}
+
+/// Iterator over the captures of the matches of a [`Regex`](struct.Regex.html).
+///
+/// See [`Regex::captures_iter`](struct.Regex.html#method.captures_iter).
+#[derive(Clone, Debug)]
+pub struct CapturesIter<'r, 'h> {
+	regex: &'r Regex,
+	haystack: &'h RawStr,
+	position: usize,
+	last_end: Option<usize>,
+}
+
+impl<'r, 'h> Iterator for CapturesIter<'r, 'h> {
+	type Item = Captures<'h>;
+	fn next(&mut self) -> Option<Captures<'h>> {
+		loop {
+			let caps = self.regex.captures_at(self.haystack, self.position)?;
+			let m = caps.get(0).unwrap();
+			self.position = Regex::next_position(self.haystack, &m.range());
+			if m.is_empty() && self.last_end == Some(m.end) {
+				continue;
+			}
+			self.last_end = Some(m.end);
+			return Some(caps);
+		}
+	}
+}
+
+impl<'r, 'h> FusedIterator for CapturesIter<'r, 'h> {}
+
+// }}}
+
+// Tests {{{
+
+#[cfg(test)]
+fn matches(pattern: &str, haystack: &[u8]) -> Vec<(usize, usize)> {
+	let re = Regex::new(pattern).unwrap();
+	re.find_iter(RawStr::from_bytes(haystack)).map(|m| (m.start(), m.end())).collect()
+}
+
+#[test]
+fn test_regex_basics() {
+	assert_eq!(matches("a+", b"caaat aa"), [(1, 4), (6, 8)]);
+	assert_eq!(matches("a|ab", b"ab"), [(0, 1)]);
+	assert_eq!(matches("ab|a", b"ab"), [(0, 2)]);
+	assert_eq!(matches("a*?b", b"aab"), [(0, 3)]);
+	assert_eq!(matches("a{2,3}", b"aaaaaaa"), [(0, 3), (3, 6)]);
+	assert_eq!(matches("a{2}?", b"aaa"), [(0, 2)]);
+	assert_eq!(matches("x*", b"ab"), [(0, 0), (1, 1), (2, 2)]);
+	assert_eq!(matches("a*", b"ab"), [(0, 1), (2, 2)]);
+	assert_eq!(matches("^a|b$", b"aab"), [(0, 1), (2, 3)]);
+	assert_eq!(matches("(?m)^\\w", b"ab\ncd"), [(0, 1), (3, 4)]);
+	assert_eq!(matches("\\bfoo\\b", b"foo xfoo foo_ foo"), [(0, 3), (14, 17)]);
+	assert_eq!(matches("(?i)héllo", "HÉLLO héllo".as_bytes()), [(0, 6), (7, 13)]);
+	assert_eq!(matches("(?x) a b # comment\n c", b"abc"), [(0, 3)]);
+	assert_eq!(matches("[^a-c]+", b"abxyzc"), [(2, 5)]);
+	assert_eq!(matches("[[:digit:]x]+", b"a1x2b"), [(1, 4)]);
+}
+
+#[test]
+fn test_regex_bytes() {
+	// `.` matches whole characters, or single broken bytes.
+	assert_eq!(matches(".", b"a\xC3\xA9\xFF"), [(0, 1), (1, 3), (3, 4)]);
+	assert_eq!(matches("a.b", b"a\xE2\x82\xACb a\x80b"), [(0, 5), (6, 9)]);
+	assert_eq!(matches(".", b"\n"), []);
+	assert_eq!(matches("(?s).", b"\n"), [(0, 1)]);
+	// Bytes can match parts of characters, but matches don't start within one.
+	assert_eq!(matches("\\xA9", b"\xC3\xA9\xA9"), [(2, 3)]);
+	assert_eq!(matches("\\xC3\\xA9|\\xC3", b"\xC3\xA9\xC3"), [(0, 2), (2, 3)]);
+	assert_eq!(matches("[\\x80-\\xFF]+", b"a\xFF\xFEb"), [(1, 3)]);
+	// Unicode classes only match valid UTF-8.
+	assert_eq!(matches("\\w+", b"h\xC3\xA9\xFFllo"), [(0, 3), (4, 7)]);
+	assert_eq!(matches("\\W", b"a\xFF b"), [(1, 2), (2, 3)]);
+	// Empty matches are never inside characters.
+	assert_eq!(matches("", "é".as_bytes()), [(0, 0), (2, 2)]);
+}
+
+#[test]
+fn test_regex_captures() {
+	let re = Regex::new("(?P<y>\\d{4})-(\\d\\d)(-(\\d\\d))?").unwrap();
+	let caps = re.captures(RawStr::from_str("on 2021-03, 2022-04-05")).unwrap();
+	assert_eq!(&caps[0], "2021-03");
+	assert_eq!(&caps["y"], "2021");
+	assert_eq!(caps.get(2).map(|m| m.range()), Some(8..10));
+	assert!(caps.get(4).is_none());
+	assert_eq!(caps.len(), 5);
+	let all: Vec<Vec<Option<&RawStr>>> = re
+		.captures_iter(RawStr::from_str("2021-03, 2022-04-05"))
+		.map(|caps| caps.iter().map(|m| m.map(|m| m.as_raw_str())).collect())
+		.collect();
+	let s = RawStr::from_str;
+	assert_eq!(all, [
+		[Some(s("2021-03")), Some(s("2021")), Some(s("03")), None, None],
+		[Some(s("2022-04-05")), Some(s("2022")), Some(s("04")), Some(s("-05")), Some(s("05"))],
+	]);
+	assert_eq!(
+		re.capture_names().collect::<Vec<_>>(),
+		[None, Some("y"), None, None, None]
+	);
+}
+
+#[test]
+fn test_regex_replace_and_split() {
+	let re = Regex::new("(\\w+)@(?<host>\\w+)").unwrap();
+	let s = RawStr::from_bytes(b"a@b, \xFF c@d");
+	assert_eq!(re.replace_all(s, "$host/$1"), &b"b/a, \xFF d/c"[..]);
+	assert_eq!(re.replace(s, "${1}x$$ $9 $"), &b"ax$  $, \xFF c@d"[..]);
+	assert_eq!(re.replace_all(s, "-"), &b"-, \xFF -"[..]);
+	let re = Regex::new(",\\s*").unwrap();
+	let parts: Vec<&RawStr> = re.split(RawStr::from_str("a, b,c")).collect();
+	assert_eq!(parts, ["a", "b", "c"]);
+	assert_eq!(RawStr::from_str("a, b,c").rsplitn(2, &re).collect::<Vec<_>>(), ["c", "a, b"]);
+	assert_eq!(RawStr::from_str("a1b22").rfind(&Regex::new("\\d+").unwrap()), Some(4));
+}
+
+#[test]
+fn test_regex_rfind() {
+	let patterns = ["a", "a+", "a*", "ab|b", "b|ab", "(a|ab)(c|bcd)", "\\b\\w", "^a", "a$", ".", "\\xFF+", "é?b"];
+	let haystacks: [&[u8]; 5] = [b"", b"abcd", b"aab ab\xFFa", "xéb aé\u{2603}ba".as_bytes(), b"a\xFF\xFFb\xC3"];
+	for pattern in &patterns {
+		let re = Regex::new(pattern).unwrap();
+		for &haystack in &haystacks {
+			let haystack = RawStr::from_bytes(haystack);
+			for end in 0..=haystack.len() {
+				// The match that starts last, by trying every start.
+				let expected = (0..=end).rev().find_map(|start| re.find_anchored(haystack, start, end));
+				assert_eq!(re.rfind_at(haystack, end), expected, "{} in {:?}[..{}]", pattern, haystack, end);
+			}
+		}
+	}
+}
+
+// }}}
@@ -0,0 +1,645 @@
+use super::compile::{Class, Look, Predicate};
+use super::Error;
+
+/// A parsed regular expression.
+#[derive(Clone, Debug)]
+pub(super) enum Node {
+	Empty,
+	/// A character, matched as its UTF-8 encoding.
+	Char(char),
+	/// A single byte, from a `\xNN` escape with `NN` of 80 or higher.
+	Byte(u8),
+	Class(Class),
+	Look(Look),
+	/// A capture group, with its index.
+	Capture(Box<Node>, usize),
+	Concat(Vec<Node>),
+	Alternation(Vec<Node>),
+	Repeat {
+		node: Box<Node>,
+		min: u32,
+		max: Option<u32>,
+		greedy: bool,
+	},
+}
+
+/// The result of parsing.
+pub(super) struct Parsed {
+	pub node: Node,
+	/// The name of each capture group, where group 0 is the whole match.
+	pub names: Vec<Option<String>>,
+}
+
+/// The largest number allowed in a `{n,m}` repetition.
+const MAX_REPEAT: u32 = 1000;
+
+/// How deeply groups and repetitions may be nested.
+///
+/// Parsing, compiling and dropping the parsed expression are all recursive,
+/// so this keeps them from overflowing the stack.
+const MAX_NESTING: usize = 250;
+
+#[derive(Clone, Copy, Default)]
+struct Flags {
+	case_insensitive: bool,
+	multi_line: bool,
+	dot_matches_new_line: bool,
+	ignore_whitespace: bool,
+}
+
+struct Parser<'a> {
+	pattern: &'a str,
+	pos: usize,
+	flags: Flags,
+	names: Vec<Option<String>>,
+	/// The number of groups we are in.
+	group_depth: usize,
+}
+
+pub(super) fn parse(pattern: &str) -> Result<Parsed, Error> {
+	let mut parser = Parser {
+		pattern,
+		pos: 0,
+		flags: Flags::default(),
+		names: vec![None],
+		group_depth: 0,
+	};
+	let (node, _) = parser.parse_alternation()?;
+	if parser.pos < pattern.len() {
+		// The only thing that stops parse_alternation early is a `)`.
+		return Err(parser.error("unopened group"));
+	}
+	Ok(Parsed {
+		node,
+		names: parser.names,
+	})
+}
+
+impl<'a> Parser<'a> {
+	fn error(&self, message: &str) -> Error {
+		Error::new(message.to_string(), self.pos)
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.pattern[self.pos..].chars().next()
+	}
+
+	fn next(&mut self) -> Option<char> {
+		let c = self.peek()?;
+		self.pos += c.len_utf8();
+		Some(c)
+	}
+
+	fn eat(&mut self, c: char) -> bool {
+		if self.peek() == Some(c) {
+			self.pos += c.len_utf8();
+			true
+		} else {
+			false
+		}
+	}
+
+	fn eat_str(&mut self, s: &str) -> bool {
+		if self.pattern[self.pos..].starts_with(s) {
+			self.pos += s.len();
+			true
+		} else {
+			false
+		}
+	}
+
+	fn expect(&mut self, c: char, message: &str) -> Result<(), Error> {
+		if self.eat(c) {
+			Ok(())
+		} else {
+			Err(self.error(message))
+		}
+	}
+
+	/// Skip whitespace and comments, in `x` mode.
+	fn skip_whitespace(&mut self) {
+		if !self.flags.ignore_whitespace {
+			return;
+		}
+		while let Some(c) = self.peek() {
+			if c == '#' {
+				while let Some(c) = self.next() {
+					if c == '\n' {
+						break;
+					}
+				}
+			} else if c.is_whitespace() {
+				self.next();
+			} else {
+				break;
+			}
+		}
+	}
+
+	/// Check that a node nested `depth` levels deep is allowed.
+	fn check_nesting(&self, depth: usize) -> Result<usize, Error> {
+		if depth > MAX_NESTING {
+			Err(self.error("exceeds the nesting limit"))
+		} else {
+			Ok(depth)
+		}
+	}
+
+	// The parse functions return the parsed node together with its depth: the
+	// number of nested groups and repetitions in it.
+
+	fn parse_alternation(&mut self) -> Result<(Node, usize), Error> {
+		let (node, mut depth) = self.parse_concat()?;
+		let mut branches = vec![node];
+		while self.eat('|') {
+			let (node, d) = self.parse_concat()?;
+			branches.push(node);
+			depth = depth.max(d);
+		}
+		Ok(if branches.len() == 1 {
+			(branches.pop().unwrap(), depth)
+		} else {
+			(Node::Alternation(branches), depth)
+		})
+	}
+
+	fn parse_concat(&mut self) -> Result<(Node, usize), Error> {
+		let mut items = Vec::new();
+		let mut depth = 0;
+		loop {
+			self.skip_whitespace();
+			match self.peek() {
+				None | Some('|') | Some(')') => break,
+				Some(_) => {}
+			}
+			let (atom, d) = self.parse_atom()?;
+			let (atom, d) = self.parse_repetitions(atom, d)?;
+			items.push(atom);
+			depth = depth.max(d);
+		}
+		Ok(match items.len() {
+			0 => (Node::Empty, 0),
+			1 => (items.pop().unwrap(), depth),
+			_ => (Node::Concat(items), depth),
+		})
+	}
+
+	fn parse_repetitions(&mut self, mut atom: Node, mut depth: usize) -> Result<(Node, usize), Error> {
+		loop {
+			self.skip_whitespace();
+			let start = self.pos;
+			let (min, max) = match self.peek() {
+				Some('*') => {
+					self.next();
+					(0, None)
+				}
+				Some('+') => {
+					self.next();
+					(1, None)
+				}
+				Some('?') => {
+					self.next();
+					(0, Some(1))
+				}
+				Some('{') => {
+					self.next();
+					self.parse_counted_repetition()?
+				}
+				_ => return Ok((atom, depth)),
+			};
+			if let Node::Look(_) | Node::Empty = atom {
+				self.pos = start;
+				return Err(self.error("repetition operator missing expression"));
+			}
+			let greedy = !self.eat('?');
+			depth = self.check_nesting(depth + 1)?;
+			atom = Node::Repeat {
+				node: Box::new(atom),
+				min,
+				max,
+				greedy,
+			};
+		}
+	}
+
+	/// Parse the inside of `{n}`, `{n,}` or `{n,m}`, after the `{`.
+	fn parse_counted_repetition(&mut self) -> Result<(u32, Option<u32>), Error> {
+		let min = self.parse_number()?;
+		let max = if self.eat(',') {
+			if self.peek() == Some('}') {
+				None
+			} else {
+				Some(self.parse_number()?)
+			}
+		} else {
+			Some(min)
+		};
+		self.expect('}', "unclosed counted repetition")?;
+		if max.is_some_and(|max| max < min) {
+			return Err(self.error("invalid counted repetition: minimum exceeds maximum"));
+		}
+		Ok((min, max))
+	}
+
+	fn parse_number(&mut self) -> Result<u32, Error> {
+		let start = self.pos;
+		while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+			self.next();
+		}
+		match self.pattern[start..self.pos].parse() {
+			Ok(n) if n <= MAX_REPEAT => Ok(n),
+			Ok(_) => Err(self.error("counted repetition is too large")),
+			Err(_) => Err(self.error("expected a number in counted repetition")),
+		}
+	}
+
+	fn parse_atom(&mut self) -> Result<(Node, usize), Error> {
+		let start = self.pos;
+		let c = self.next().unwrap();
+		let node = match c {
+			'(' => return self.parse_group(),
+			'[' => Node::Class(self.parse_class()?),
+			'.' => {
+				let mut class = Class::new();
+				if !self.flags.dot_matches_new_line {
+					class.push_range('\n', '\n');
+				}
+				class.negated = true;
+				Node::Class(class)
+			}
+			'^' => Node::Look(if self.flags.multi_line {
+				Look::LineStart
+			} else {
+				Look::Start
+			}),
+			'$' => Node::Look(if self.flags.multi_line {
+				Look::LineEnd
+			} else {
+				Look::End
+			}),
+			'\\' => self.parse_escape()?,
+			'*' | '+' | '?' | '{' => {
+				self.pos = start;
+				return Err(self.error("repetition operator missing expression"));
+			}
+			c => self.literal(c),
+		};
+		Ok((node, 0))
+	}
+
+	fn literal(&self, c: char) -> Node {
+		if self.flags.case_insensitive && has_other_case(c) {
+			let mut class = Class::new();
+			class.push_range(c, c);
+			class.case_insensitive = true;
+			Node::Class(class)
+		} else {
+			Node::Char(c)
+		}
+	}
+
+	/// Parse a group, after the `(`.
+	fn parse_group(&mut self) -> Result<(Node, usize), Error> {
+		self.group_depth = self.check_nesting(self.group_depth + 1)?;
+		let outer_flags = self.flags;
+		let mut capture = None;
+		if self.eat('?') {
+			if self.eat_str("P<") || self.eat('<') {
+				capture = Some(self.parse_group_name()?);
+			} else {
+				// Flags, like `(?i)`, `(?-s:...)` or just `(?:...)`.
+				let mut enable = true;
+				loop {
+					match self.next() {
+						Some('i') => self.flags.case_insensitive = enable,
+						Some('m') => self.flags.multi_line = enable,
+						Some('s') => self.flags.dot_matches_new_line = enable,
+						Some('x') => self.flags.ignore_whitespace = enable,
+						Some('-') if enable => enable = false,
+						Some(':') => break,
+						Some(')') => {
+							self.group_depth -= 1;
+							return Ok((Node::Empty, 0));
+						}
+						Some(c) => {
+							self.pos -= c.len_utf8();
+							return Err(self.error("unrecognized flag"));
+						}
+						None => return Err(self.error("unclosed group")),
+					}
+				}
+			}
+		} else {
+			capture = Some(None);
+		}
+		let index = capture.map(|name| {
+			self.names.push(name);
+			self.names.len() - 1
+		});
+		let (node, depth) = self.parse_alternation()?;
+		self.expect(')', "unclosed group")?;
+		self.flags = outer_flags;
+		self.group_depth -= 1;
+		Ok(match index {
+			Some(index) => (Node::Capture(Box::new(node), index), self.check_nesting(depth + 1)?),
+			None => (node, depth),
+		})
+	}
+
+	/// Parse a group name and the `>` after it.
+	fn parse_group_name(&mut self) -> Result<Option<String>, Error> {
+		let start = self.pos;
+		while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+			self.next();
+		}
+		let name = &self.pattern[start..self.pos];
+		if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+			return Err(self.error("invalid capture group name"));
+		}
+		if self.names.iter().any(|n| n.as_ref().is_some_and(|n| n == name)) {
+			return Err(self.error("duplicate capture group name"));
+		}
+		self.expect('>', "invalid capture group name")?;
+		Ok(Some(name.to_string()))
+	}
+
+	/// Parse an escape sequence outside a class, after the `\`.
+	fn parse_escape(&mut self) -> Result<Node, Error> {
+		let look = match self.peek() {
+			Some('b') => Some(Look::WordBoundary),
+			Some('B') => Some(Look::NotWordBoundary),
+			Some('A') => Some(Look::Start),
+			Some('z') => Some(Look::End),
+			_ => None,
+		};
+		if let Some(look) = look {
+			self.next();
+			return Ok(Node::Look(look));
+		}
+		Ok(match self.parse_class_escape()? {
+			Escape::Char(c) => self.literal(c),
+			Escape::Byte(b) => Node::Byte(b),
+			Escape::Predicate(predicate, negated) => {
+				let mut class = Class::new();
+				class.predicates.push((predicate, false));
+				class.negated = negated;
+				class.case_insensitive = self.flags.case_insensitive;
+				Node::Class(class)
+			}
+		})
+	}
+
+	/// Parse an escape sequence that can also appear in a class, after the `\`.
+	fn parse_class_escape(&mut self) -> Result<Escape, Error> {
+		let start = self.pos - 1;
+		let c = match self.next() {
+			Some(c) => c,
+			None => return Err(self.error("incomplete escape sequence")),
+		};
+		Ok(match c {
+			'n' => Escape::Char('\n'),
+			't' => Escape::Char('\t'),
+			'r' => Escape::Char('\r'),
+			'f' => Escape::Char('\x0C'),
+			'v' => Escape::Char('\x0B'),
+			'a' => Escape::Char('\x07'),
+			'd' => Escape::Predicate(Predicate::Numeric, false),
+			'D' => Escape::Predicate(Predicate::Numeric, true),
+			'w' => Escape::Predicate(Predicate::Word, false),
+			'W' => Escape::Predicate(Predicate::Word, true),
+			's' => Escape::Predicate(Predicate::WhiteSpace, false),
+			'S' => Escape::Predicate(Predicate::WhiteSpace, true),
+			'p' | 'P' => Escape::Predicate(self.parse_property()?, c == 'P'),
+			'x' => self.parse_hex()?,
+			c if c.is_ascii() && !c.is_ascii_alphanumeric() => Escape::Char(c),
+			_ => {
+				self.pos = start;
+				return Err(self.error("unrecognized escape sequence"));
+			}
+		})
+	}
+
+	/// Parse a Unicode property name, after `\p` or `\P`.
+	fn parse_property(&mut self) -> Result<Predicate, Error> {
+		let start = self.pos;
+		let name = if self.eat('{') {
+			let name_start = self.pos;
+			while self.peek().is_some_and(|c| c != '}') {
+				self.next();
+			}
+			let name = &self.pattern[name_start..self.pos];
+			self.expect('}', "unclosed Unicode class")?;
+			name
+		} else {
+			match self.next() {
+				Some(_) => &self.pattern[start..self.pos],
+				None => return Err(self.error("incomplete escape sequence")),
+			}
+		};
+		Predicate::from_name(name).ok_or_else(|| {
+			Error::new(format!("unrecognized Unicode class: {}", name), start)
+		})
+	}
+
+	/// Parse `NN` or `{N...}`, after `\x`.
+	fn parse_hex(&mut self) -> Result<Escape, Error> {
+		let start = self.pos;
+		let braced = self.eat('{');
+		let digits_start = self.pos;
+		while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) && (braced || self.pos < start + 2) {
+			self.next();
+		}
+		let digits = &self.pattern[digits_start..self.pos];
+		if braced {
+			self.expect('}', "unclosed hexadecimal escape")?;
+		}
+		let value = match u32::from_str_radix(digits, 16) {
+			Ok(value) if braced || digits.len() == 2 => value,
+			_ => {
+				self.pos = start;
+				return Err(self.error("invalid hexadecimal escape"));
+			}
+		};
+		if !braced && value >= 0x80 {
+			return Ok(Escape::Byte(value as u8));
+		}
+		match ::std::char::from_u32(value) {
+			Some(c) => Ok(Escape::Char(c)),
+			None => {
+				self.pos = start;
+				Err(self.error("hexadecimal escape is not a Unicode scalar value"))
+			}
+		}
+	}
+
+	/// Parse a bracketed class, after the `[`.
+	fn parse_class(&mut self) -> Result<Class, Error> {
+		let start = self.pos - 1;
+		let mut class = Class::new();
+		class.case_insensitive = self.flags.case_insensitive;
+		class.negated = self.eat('^');
+		let mut first = true;
+		loop {
+			let item_start = self.pos;
+			let item = match self.peek() {
+				None => {
+					self.pos = start;
+					return Err(self.error("unclosed character class"));
+				}
+				Some(']') if !first => {
+					self.next();
+					return Ok(class);
+				}
+				Some('[') if self.eat_str("[:") => {
+					let name_start = self.pos;
+					while self.peek().is_some_and(|c| c.is_ascii_lowercase()) {
+						self.next();
+					}
+					let name = &self.pattern[name_start..self.pos];
+					if !self.eat_str(":]") || !push_posix_class(&mut class, name) {
+						self.pos = item_start;
+						return Err(self.error("invalid POSIX character class"));
+					}
+					first = false;
+					continue;
+				}
+				Some('\\') => {
+					self.next();
+					self.parse_class_escape()?
+				}
+				Some(c) => {
+					self.next();
+					Escape::Char(c)
+				}
+			};
+			first = false;
+			let is_range = self.peek() == Some('-') && !self.pattern[self.pos + 1..].starts_with(']');
+			let end = if is_range {
+				self.next();
+				match self.peek() {
+					Some('\\') => {
+						self.next();
+						Some(self.parse_class_escape()?)
+					}
+					Some(c) => {
+						self.next();
+						Some(Escape::Char(c))
+					}
+					None => None,
+				}
+			} else {
+				None
+			};
+			match (item, end) {
+				(Escape::Char(c), None) => class.push_range(c, c),
+				(Escape::Byte(b), None) => class.push_byte_range(b, b),
+				(Escape::Predicate(p, negated), None) => class.predicates.push((p, negated)),
+				(Escape::Char(a), Some(Escape::Char(b))) if a <= b => class.push_range(a, b),
+				(Escape::Byte(a), Some(Escape::Byte(b))) if a <= b => class.push_byte_range(a, b),
+				(Escape::Char(a), Some(Escape::Byte(b))) if a < '\u{80}' => {
+					class.push_range(a, '\x7F');
+					class.push_byte_range(0x80, b);
+				}
+				_ => {
+					self.pos = item_start;
+					return Err(self.error("invalid character class range"));
+				}
+			}
+		}
+	}
+}
+
+/// An escape sequence.
+enum Escape {
+	Char(char),
+	Byte(u8),
+	Predicate(Predicate, bool),
+}
+
+/// Add a POSIX class like `[:alpha:]`. Returns false if the name is unknown.
+fn push_posix_class(class: &mut Class, name: &str) -> bool {
+	let ranges = match posix_class(name) {
+		Some(ranges) => ranges,
+		None => return false,
+	};
+	for &(a, b) in ranges {
+		class.push_range(a, b);
+	}
+	true
+}
+
+/// Whether `name` is a POSIX class that can be used as `[:name:]`.
+pub(crate) fn is_posix_class(name: &str) -> bool {
+	posix_class(name).is_some()
+}
+
+/// The ranges of a POSIX class.
+fn posix_class(name: &str) -> Option<&'static [(char, char)]> {
+	let ranges: &[(char, char)] = match name {
+		"alnum" => &[('0', '9'), ('A', 'Z'), ('a', 'z')],
+		"alpha" => &[('A', 'Z'), ('a', 'z')],
+		"ascii" => &[('\0', '\x7F')],
+		"blank" => &[('\t', '\t'), (' ', ' ')],
+		"cntrl" => &[('\0', '\x1F'), ('\x7F', '\x7F')],
+		"digit" => &[('0', '9')],
+		"graph" => &[('!', '~')],
+		"lower" => &[('a', 'z')],
+		"print" => &[(' ', '~')],
+		"punct" => &[('!', '/'), (':', '@'), ('[', '`'), ('{', '~')],
+		"space" => &[('\t', '\r'), (' ', ' ')],
+		"upper" => &[('A', 'Z')],
+		"word" => &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')],
+		"xdigit" => &[('0', '9'), ('A', 'F'), ('a', 'f')],
+		_ => return None,
+	};
+	Some(ranges)
+}
+
+/// Whether `c` has a different upper or lower case form.
+fn has_other_case(c: char) -> bool {
+	c.to_lowercase().ne(Some(c)) || c.to_uppercase().ne(Some(c))
+}
+
+// Tests {{{
+
+#[test]
+fn test_parse_errors() {
+	let cases = [
+		("a)", 1, "unopened group"),
+		("(a", 2, "unclosed group"),
+		("*a", 0, "repetition operator missing expression"),
+		("a{2,1}", 6, "invalid counted repetition: minimum exceeds maximum"),
+		("a{1001}", 6, "counted repetition is too large"),
+		("[a", 0, "unclosed character class"),
+		("[z-a]", 1, "invalid character class range"),
+		("\\q", 0, "unrecognized escape sequence"),
+		("\\x{D800}", 2, "hexadecimal escape is not a Unicode scalar value"),
+		("\\p{Foo}", 2, "unrecognized Unicode class: Foo"),
+		("(?<a>x)(?<a>y)", 11, "duplicate capture group name"),
+		("(?q)", 2, "unrecognized flag"),
+	];
+	for &(pattern, position, message) in &cases {
+		let error = parse(pattern).err().unwrap();
+		assert_eq!((error.position(), error.message()), (position, message), "{}", pattern);
+	}
+}
+
+#[test]
+fn test_nesting_limit() {
+	let nested = |open: &str, close: &str, n: usize| format!("{}a{}", open.repeat(n), close.repeat(n));
+	assert!(parse(&nested("(", ")", MAX_NESTING)).is_ok());
+	assert!(parse(&nested("(?:", ")*", MAX_NESTING)).is_ok());
+	for pattern in &[
+		nested("(", ")", MAX_NESTING + 1),
+		nested("(?:", ")*", MAX_NESTING + 1),
+		nested("(", ")+", MAX_NESTING / 2 + 1),
+		nested("", "+", MAX_NESTING + 1),
+		nested("(", "", 50_000),
+		nested("", "*", 50_000),
+	] {
+		let error = parse(pattern).err().unwrap();
+		assert_eq!(error.message(), "exceeds the nesting limit");
+	}
+}
+
+// }}}
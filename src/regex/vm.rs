@@ -0,0 +1,258 @@
+//! A Pike VM: simulates all threads of the program at once, in lockstep over
+//! the haystack, so it never backtracks and runs in `O(n * m)` time.
+//!
+//! A thread that matched a multi-byte character skips the next bytes, while
+//! keeping its place in the priority order of the threads.
+
+use super::compile::{char_at, is_char_boundary, Inst, Program};
+use std::cmp::max;
+use std::mem::swap;
+
+type Slots = Vec<Option<usize>>;
+
+struct Thread {
+	pc: usize,
+	/// The number of bytes still to skip before this thread continues at
+	/// `pc`. Zero for threads that are ready to run.
+	skip: usize,
+	slots: Slots,
+}
+
+/// The threads at one position, in order of priority.
+struct Threads {
+	threads: Vec<Thread>,
+	/// For each `(pc, skip)` pair, the generation in which it was last added.
+	seen: Vec<u32>,
+	generation: u32,
+}
+
+impl Threads {
+	fn new(len: usize) -> Threads {
+		Threads {
+			threads: Vec::new(),
+			seen: vec![0; len * 4],
+			generation: 1,
+		}
+	}
+
+	fn clear(&mut self) {
+		self.threads.clear();
+		self.generation += 1;
+	}
+
+	/// Mark a `(pc, skip)` as seen. Returns false if it already was.
+	fn insert(&mut self, pc: usize, skip: usize) -> bool {
+		let key = pc * 4 + skip;
+		if self.seen[key] == self.generation {
+			false
+		} else {
+			self.seen[key] = self.generation;
+			true
+		}
+	}
+}
+
+enum Frame {
+	Explore(usize),
+	Restore(usize, Option<usize>),
+}
+
+pub(super) struct Search<'a> {
+	program: &'a Program,
+	haystack: &'a [u8],
+	/// Matches may not extend beyond this position.
+	end: usize,
+	stack: Vec<Frame>,
+}
+
+impl<'a> Search<'a> {
+	pub fn new(program: &'a Program, haystack: &'a [u8], end: usize) -> Search<'a> {
+		Search {
+			program,
+			haystack,
+			end,
+			stack: Vec::new(),
+		}
+	}
+
+	/// Find the leftmost-first match starting at or after `start`, filling in
+	/// `slots`. Only the first `slots.len()` slots are tracked.
+	///
+	/// If `anchored`, the match must start at `start`.
+	pub fn run(&mut self, start: usize, anchored: bool, slots: &mut [Option<usize>]) -> bool {
+		if start > self.end {
+			return false;
+		}
+		let anchored = anchored || self.program.anchored_start;
+		let n = self.program.insts.len();
+		let mut current = Threads::new(n);
+		let mut next = Threads::new(n);
+		let mut matched = false;
+		let mut scratch: Slots = vec![None; slots.len()];
+		let mut next_boundary = if is_char_boundary(self.haystack, start) {
+			start
+		} else {
+			// Find the end of the character we're in.
+			(start + 1..self.end).find(|&i| is_char_boundary(self.haystack, i)).unwrap_or(self.end)
+		};
+		for pos in start..=self.end {
+			if pos == next_boundary {
+				if !matched && (!anchored || pos == start) {
+					for s in scratch.iter_mut() {
+						*s = None;
+					}
+					self.add(&mut current, 0, pos, &mut scratch);
+				}
+				next_boundary = pos + char_at(self.haystack, pos).map_or(1, |(_, len)| len);
+			}
+			if current.threads.is_empty() {
+				if matched || anchored {
+					break;
+				}
+				current.clear();
+				continue;
+			}
+			self.step(&mut current, &mut next, pos, |thread_slots| {
+				slots.copy_from_slice(thread_slots);
+				matched = true;
+				// All other threads have a lower priority.
+				false
+			});
+			swap(&mut current, &mut next);
+			next.clear();
+		}
+		matched
+	}
+
+	/// Find the start of the match that starts last, at or after `start`.
+	///
+	/// This is a single pass over the haystack. The threads are kept in order
+	/// of where they started, latest first, so when two threads meet, the one
+	/// that started later survives: whatever the other could still match, it
+	/// can match as well.
+	pub fn last_start(&mut self, start: usize) -> Option<usize> {
+		if start > self.end {
+			return None;
+		}
+		let n = self.program.insts.len();
+		let mut current = Threads::new(n);
+		let mut next = Threads::new(n);
+		let mut merged = Threads::new(n);
+		let mut last = None;
+		let mut slot: Slots = vec![None];
+		for pos in start..=self.end {
+			// Threads that start here come first. Like in `run`, a character
+			// cut off by `end` doesn't stop a match from starting at `end`.
+			let may_start = !self.program.anchored_start || pos == start;
+			if may_start && (is_char_boundary(self.haystack, pos) || pos == self.end) {
+				slot[0] = None;
+				self.add(&mut merged, 0, pos, &mut slot);
+			}
+			for thread in current.threads.drain(..) {
+				if merged.insert(thread.pc, thread.skip) {
+					merged.threads.push(thread);
+				}
+			}
+			swap(&mut current, &mut merged);
+			merged.clear();
+			self.step(&mut current, &mut next, pos, |thread_slots| {
+				last = max(last, thread_slots[0]);
+				true
+			});
+			swap(&mut current, &mut next);
+			next.clear();
+		}
+		last
+	}
+
+	/// Advance the threads at `pos` to `next`, in order of priority.
+	///
+	/// `on_match` is called with the slots of every thread that matches. If
+	/// it returns false, the remaining threads are dropped.
+	fn step<F>(&mut self, current: &mut Threads, next: &mut Threads, pos: usize, mut on_match: F)
+	where
+		F: FnMut(&[Option<usize>]) -> bool,
+	{
+		for mut thread in current.threads.drain(..) {
+			if thread.skip > 0 {
+				if thread.skip == 1 {
+					self.add(next, thread.pc, pos + 1, &mut thread.slots);
+				} else if next.insert(thread.pc, thread.skip - 1) {
+					next.threads.push(Thread {
+						skip: thread.skip - 1,
+						..thread
+					});
+				}
+				continue;
+			}
+			let (char_len, byte) = match self.program.insts[thread.pc] {
+				Inst::Match => {
+					if on_match(&thread.slots) {
+						continue;
+					}
+					break;
+				}
+				Inst::Byte(b) => (None, pos < self.end && self.haystack[pos] == b),
+				Inst::Class(i) => self.program.classes[i].matches(self.haystack, pos),
+				_ => unreachable!(),
+			};
+			let pc = thread.pc + 1;
+			if let Some(len) = char_len.filter(|&len| pos + len <= self.end) {
+				if len == 1 {
+					self.add(next, pc, pos + 1, &mut thread.slots.clone());
+				} else if next.insert(pc, len - 1) {
+					next.threads.push(Thread {
+						pc,
+						skip: len - 1,
+						slots: thread.slots.clone(),
+					});
+				}
+			}
+			if byte && char_len != Some(1) && pos < self.end {
+				self.add(next, pc, pos + 1, &mut thread.slots);
+			}
+		}
+	}
+
+	/// Add the thread at `pc` to `threads`, following all jumps, splits,
+	/// saves and assertions at `pos`.
+	fn add(&mut self, threads: &mut Threads, pc: usize, pos: usize, slots: &mut Slots) {
+		self.stack.push(Frame::Explore(pc));
+		while let Some(frame) = self.stack.pop() {
+			let pc = match frame {
+				Frame::Explore(pc) => pc,
+				Frame::Restore(slot, value) => {
+					slots[slot] = value;
+					continue;
+				}
+			};
+			if !threads.insert(pc, 0) {
+				continue;
+			}
+			match self.program.insts[pc] {
+				Inst::Jump(target) => self.stack.push(Frame::Explore(target)),
+				Inst::Split(a, b) => {
+					self.stack.push(Frame::Explore(b));
+					self.stack.push(Frame::Explore(a));
+				}
+				Inst::Save(slot) => {
+					if slot < slots.len() {
+						self.stack.push(Frame::Restore(slot, slots[slot]));
+						slots[slot] = Some(pos);
+					}
+					self.stack.push(Frame::Explore(pc + 1));
+				}
+				Inst::Look(look) => {
+					if look.holds(self.haystack, pos) {
+						self.stack.push(Frame::Explore(pc + 1));
+					}
+				}
+				Inst::Match | Inst::Byte(_) | Inst::Class(_) => threads.threads.push(Thread {
+					pc,
+					skip: 0,
+					slots: slots.clone(),
+				}),
+			}
+		}
+	}
+}
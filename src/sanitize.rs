@@ -0,0 +1,295 @@
+use std::char::REPLACEMENT_CHARACTER;
+use std::fmt::{Display, Formatter, Write};
+use std::str::from_utf8;
+use str::{RawStr, Utf8Chunk};
+use string::RawString;
+
+/// What to do with broken UTF-8 in
+/// [`RawString::sanitize_utf8`](struct.RawString.html#method.sanitize_utf8).
+///
+/// A broken sequence is the `broken` part of a
+/// [`Utf8Chunk`](struct.Utf8Chunk.html): one to three bytes that would be
+/// displayed as a single replacement character.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Utf8Policy<'a> {
+	/// Replace each broken sequence by U+FFFD, the replacement character.
+	Replace,
+	/// Remove each broken sequence.
+	Drop,
+	/// Replace each broken sequence by the given string.
+	ReplaceWith(&'a RawStr),
+	/// Replace each byte of a broken sequence by `\xNN`.
+	Escape,
+}
+
+/// Statistics returned by
+/// [`RawString::sanitize_utf8`](struct.RawString.html#method.sanitize_utf8)
+/// and [`RawString::sanitize_cesu8`](struct.RawString.html#method.sanitize_cesu8).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SanitizeStats {
+	/// The number of broken sequences that were handled by the policy.
+	pub broken_sequences: usize,
+	/// The total number of bytes in those broken sequences.
+	pub broken_bytes: usize,
+	/// The number of CESU-8 surrogate pairs and Java `C0 80` NUL bytes that
+	/// were decoded into proper UTF-8.
+	///
+	/// Always zero for `sanitize_utf8`.
+	pub decoded_sequences: usize,
+}
+
+impl SanitizeStats {
+	/// Whether anything was changed.
+	#[inline]
+	pub fn is_unchanged(&self) -> bool {
+		self.broken_sequences == 0 && self.decoded_sequences == 0
+	}
+}
+
+impl RawString {
+	/// Make the contents valid UTF-8, by handling all broken sequences
+	/// according to the given policy.
+	///
+	/// Afterwards, `self.to_str()` is guaranteed to succeed.
+	/// Nothing is allocated if the contents were already valid UTF-8.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::{RawString, Utf8Policy};
+	/// let mut s = RawString::from(&b"a\xFFb\xE2\x82"[..]);
+	/// let stats = s.sanitize_utf8(Utf8Policy::Escape);
+	/// assert_eq!(s, "a\\xffb\\xe2\\x82");
+	/// assert_eq!(stats.broken_sequences, 2);
+	/// assert_eq!(stats.broken_bytes, 3);
+	/// ```
+	pub fn sanitize_utf8(&mut self, policy: Utf8Policy) -> SanitizeStats {
+		self.sanitize(policy, false)
+	}
+
+	/// Like [`sanitize_utf8`](#method.sanitize_utf8), but first decodes
+	/// CESU-8 and Java's Modified UTF-8.
+	///
+	/// Surrogate pairs that are encoded as two three-byte sequences are
+	/// converted to a single four-byte UTF-8 sequence, and `C0 80` is
+	/// converted to a NUL byte. Unpaired surrogates are broken sequences,
+	/// and are handled by the policy.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::{RawString, Utf8Policy};
+	/// let mut s = RawString::from(&b"\xED\xA0\xBD\xED\xB8\x80\xC0\x80"[..]);
+	/// let stats = s.sanitize_cesu8(Utf8Policy::Replace);
+	/// assert_eq!(s, "😀\0");
+	/// assert_eq!(stats.decoded_sequences, 2);
+	/// ```
+	pub fn sanitize_cesu8(&mut self, policy: Utf8Policy) -> SanitizeStats {
+		self.sanitize(policy, true)
+	}
+
+	fn sanitize(&mut self, policy: Utf8Policy, cesu8: bool) -> SanitizeStats {
+		let mut stats = SanitizeStats::default();
+		if from_utf8(self.as_bytes()).is_ok() {
+			return stats;
+		}
+		let mut out = RawString::with_capacity(self.len());
+		let mut pos = 0;
+		while let Some(Utf8Chunk { valid, broken }) = self[pos..].utf8_chunks().next() {
+			out.push_str(valid);
+			pos += valid.len();
+			if broken.is_empty() {
+				break;
+			}
+			if cesu8 {
+				if let Some((c, len)) = decode_cesu8(&self.as_bytes()[pos..]) {
+					let mut buf = [0; 4];
+					out.push_str(c.encode_utf8(&mut buf));
+					stats.decoded_sequences += 1;
+					pos += len;
+					continue;
+				}
+			}
+			match policy {
+				Utf8Policy::Replace => {
+					let mut buf = [0; 4];
+					out.push_str(REPLACEMENT_CHARACTER.encode_utf8(&mut buf));
+				}
+				Utf8Policy::Drop => {}
+				Utf8Policy::ReplaceWith(s) => out.push_str(s),
+				Utf8Policy::Escape => {
+					for &b in broken {
+						out.push_str("\\x");
+						out.push(HEX_DIGITS[usize::from(b >> 4)]);
+						out.push(HEX_DIGITS[usize::from(b & 0xF)]);
+					}
+				}
+			}
+			stats.broken_sequences += 1;
+			stats.broken_bytes += broken.len();
+			pos += broken.len();
+		}
+		*self = out;
+		stats
+	}
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Decode a CESU-8 surrogate pair or a Modified UTF-8 NUL at the start of `bytes`.
+///
+/// Returns the character and the number of bytes it took.
+fn decode_cesu8(bytes: &[u8]) -> Option<(char, usize)> {
+	match *bytes {
+		[0xC0, 0x80, ..] => Some(('\0', 2)),
+		[0xED, a @ 0xA0..=0xAF, b @ 0x80..=0xBF, 0xED, c @ 0xB0..=0xBF, d @ 0x80..=0xBF, ..] => {
+			let high = 0xD000 | u32::from(a & 0x3F) << 6 | u32::from(b & 0x3F);
+			let low = 0xD000 | u32::from(c & 0x3F) << 6 | u32::from(d & 0x3F);
+			let c = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+			::std::char::from_u32(c).map(|c| (c, 6))
+		}
+		_ => None,
+	}
+}
+
+// Display safe {{{
+
+/// Safe display of untrusted text in a terminal.
+///
+/// See [`RawStr::display_safe`](struct.RawStr.html#method.display_safe).
+#[derive(Clone, Copy, Debug)]
+pub struct DisplaySafe<'a> {
+	s: &'a RawStr,
+}
+
+/// Whether the character could change the behaviour of a terminal, or the
+/// order in which text is shown: C0 and C1 controls, and bidi embeddings,
+/// overrides and isolates.
+fn is_unsafe(c: char) -> bool {
+	matches!(c, '\0'..='\x1F' | '\x7F'..='\u{9F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+fn write_safe_char(f: &mut Formatter, c: char) -> ::std::fmt::Result {
+	match c {
+		'\0'..='\x1F' => {
+			f.write_char('^')?;
+			f.write_char((c as u8 + 0x40) as char)
+		}
+		'\x7F' => f.write_str("^?"),
+		_ => write!(f, "\\u{{{:x}}}", c as u32),
+	}
+}
+
+impl<'a> Display for DisplaySafe<'a> {
+	fn fmt(&self, f: &mut Formatter) -> ::std::fmt::Result {
+		for Utf8Chunk { valid, broken } in self.s.utf8_chunks() {
+			let mut written = 0;
+			for (i, c) in valid.char_indices() {
+				if is_unsafe(c) {
+					f.write_str(&valid[written..i])?;
+					write_safe_char(f, c)?;
+					written = i + c.len_utf8();
+				}
+			}
+			f.write_str(&valid[written..])?;
+			for &b in broken {
+				write!(f, "\\x{:02x}", b)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl RawStr {
+	/// Display the string without anything that could affect the terminal.
+	///
+	/// Unlike `Display`, this doesn't pass control characters through, so it
+	/// is safe to use on untrusted input:
+	///
+	/// - C0 controls (including newlines and tabs) are shown in caret
+	///   notation, like `^[` for escape, and `^?` for delete.
+	/// - C1 controls and the bidi embedding, override and isolate characters
+	///   are shown as `\u{..}`.
+	/// - Each byte of broken UTF-8 is shown as `\xNN`.
+	///
+	/// Everything else is shown as-is, without the quotes and escapes of
+	/// `Debug`.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"\x1b[2Jna\xC3\xAFve\r\xFF\xC2\x9B\xE2\x80\xAEtxt.exe");
+	/// assert_eq!(s.display_safe().to_string(), "^[[2Jnaïve^M\\xff\\u{9b}\\u{202e}txt.exe");
+	/// ```
+	#[inline]
+	pub fn display_safe(&self) -> DisplaySafe<'_> {
+		DisplaySafe { s: self }
+	}
+}
+
+// }}}
+
+// Tests {{{
+
+#[test]
+fn test_sanitize_utf8() {
+	let input = RawStr::from_bytes(b"a\xFFb\xE2\x82c\xC0\x80");
+
+	let mut s = input.to_owned();
+	let stats = s.sanitize_utf8(Utf8Policy::Replace);
+	assert_eq!(s, "a\u{FFFD}b\u{FFFD}c\u{FFFD}\u{FFFD}");
+	assert_eq!(
+		stats,
+		SanitizeStats {
+			broken_sequences: 4,
+			broken_bytes: 5,
+			decoded_sequences: 0,
+		}
+	);
+
+	let mut s = input.to_owned();
+	s.sanitize_utf8(Utf8Policy::Drop);
+	assert_eq!(s, "abc");
+
+	let mut s = input.to_owned();
+	s.sanitize_utf8(Utf8Policy::ReplaceWith(RawStr::from_str("?")));
+	assert_eq!(s, "a?b?c??");
+
+	let mut s = input.to_owned();
+	s.sanitize_utf8(Utf8Policy::Escape);
+	assert_eq!(s, "a\\xffb\\xe2\\x82c\\xc0\\x80");
+
+	let mut s = RawString::from("μs");
+	assert!(s.sanitize_utf8(Utf8Policy::Drop).is_unchanged());
+	assert_eq!(s, "μs");
+}
+
+#[test]
+fn test_sanitize_cesu8() {
+	// U+1F600 as a surrogate pair, an unpaired high surrogate, and a NUL.
+	let mut s = RawString::from(&b"<\xED\xA0\xBD\xED\xB8\x80|\xED\xA0\xBD|\xC0\x80>"[..]);
+	let stats = s.sanitize_cesu8(Utf8Policy::Replace);
+	assert_eq!(s, "<😀|\u{FFFD}\u{FFFD}\u{FFFD}|\0>");
+	assert_eq!(
+		stats,
+		SanitizeStats {
+			broken_sequences: 3,
+			broken_bytes: 3,
+			decoded_sequences: 2,
+		}
+	);
+}
+
+#[test]
+fn test_display_safe() {
+	let safe = |s: &[u8]| RawStr::from_bytes(s).display_safe().to_string();
+	assert_eq!(safe(b""), "");
+	assert_eq!(safe("plain μs \\ 日本".as_bytes()), "plain μs \\ 日本");
+	assert_eq!(safe(b"\0\x07\t\n\r\x1b\x1f\x7f"), "^@^G^I^J^M^[^_^?");
+	assert_eq!(safe(b"\xC2\x80\xC2\x9F\xC2\xA0"), "\\u{80}\\u{9f}\u{a0}");
+	assert_eq!(safe("\u{202A}\u{202E}\u{2066}\u{2069}\u{200F}".as_bytes()), "\\u{202a}\\u{202e}\\u{2066}\\u{2069}\u{200f}");
+	assert_eq!(safe(b"a\xFFb\xE2\x82"), "a\\xffb\\xe2\\x82");
+}
+
+// }}}
@@ -0,0 +1,228 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use str::RawStr;
+use string::RawString;
+
+/// The error returned by
+/// [`RawStr::shell_split`](struct.RawStr.html#method.shell_split).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShellSplitError {
+	/// A `'` without a matching `'`.
+	UnterminatedSingleQuote,
+	/// A `"` without a matching `"`.
+	UnterminatedDoubleQuote,
+	/// A `\` at the very end.
+	TrailingBackslash,
+}
+
+impl Display for ShellSplitError {
+	fn fmt(&self, f: &mut Formatter) -> ::std::fmt::Result {
+		f.write_str(match *self {
+			ShellSplitError::UnterminatedSingleQuote => "unterminated single quote",
+			ShellSplitError::UnterminatedDoubleQuote => "unterminated double quote",
+			ShellSplitError::TrailingBackslash => "trailing backslash",
+		})
+	}
+}
+
+impl Error for ShellSplitError {}
+
+fn is_blank(b: u8) -> bool {
+	b == b' ' || b == b'\t' || b == b'\n'
+}
+
+/// Whether the byte never needs quoting.
+fn is_safe(b: u8) -> bool {
+	b.is_ascii_alphanumeric() || b"@%+=:,./-_".contains(&b)
+}
+
+impl RawStr {
+	/// Split into words, following the quoting rules of the POSIX shell.
+	///
+	/// - Words are separated by spaces, tabs and newlines.
+	/// - Everything between single quotes is taken literally.
+	/// - Between double quotes, a backslash only escapes `$`, `` ` ``, `"`,
+	///   `\` and a newline.
+	/// - Elsewhere, a backslash escapes any byte.
+	/// - A backslash followed by a newline is removed outside single quotes.
+	/// - A `#` at the start of a word starts a comment, up to the next newline.
+	///
+	/// Nothing is expanded: `$`, `` ` ``, `*`, `~` and the like are kept as
+	/// they are. Bytes that aren't valid UTF-8 are kept as well.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"cp -- 'a b' \"\\$HOME\"/c\\ d \xFF # copy");
+	/// let words = s.shell_split().unwrap();
+	/// assert_eq!(words, [&b"cp"[..], b"--", b"a b", b"$HOME/c d", b"\xFF"]);
+	/// ```
+	pub fn shell_split(&self) -> Result<Vec<RawString>, ShellSplitError> {
+		let bytes = self.as_bytes();
+		let mut words = Vec::new();
+		let mut word: Option<RawString> = None;
+		let mut i = 0;
+		while i < bytes.len() {
+			let b = bytes[i];
+			i += 1;
+			match b {
+				_ if is_blank(b) => {
+					words.extend(word.take());
+				}
+				b'#' if word.is_none() => {
+					i += bytes[i..].iter().position(|&b| b == b'\n').unwrap_or(bytes.len() - i);
+				}
+				b'\\' => match bytes.get(i) {
+					None => return Err(ShellSplitError::TrailingBackslash),
+					Some(b'\n') => i += 1,
+					Some(&next) => {
+						word.get_or_insert_with(RawString::new).push(next);
+						i += 1;
+					}
+				},
+				b'\'' => {
+					let word = word.get_or_insert_with(RawString::new);
+					let end = bytes[i..]
+						.iter()
+						.position(|&b| b == b'\'')
+						.ok_or(ShellSplitError::UnterminatedSingleQuote)?;
+					word.push_str(RawStr::from_bytes(&bytes[i..i + end]));
+					i += end + 1;
+				}
+				b'"' => {
+					let word = word.get_or_insert_with(RawString::new);
+					loop {
+						match bytes.get(i) {
+							None => return Err(ShellSplitError::UnterminatedDoubleQuote),
+							Some(b'"') => break,
+							Some(b'\\') => match bytes.get(i + 1) {
+								Some(b'\n') => i += 1,
+								Some(&next @ (b'$' | b'`' | b'"' | b'\\')) => {
+									word.push(next);
+									i += 1;
+								}
+								_ => word.push(b'\\'),
+							},
+							Some(&b) => word.push(b),
+						}
+						i += 1;
+					}
+					i += 1;
+				}
+				_ => word.get_or_insert_with(RawString::new).push(b),
+			}
+		}
+		words.extend(word);
+		Ok(words)
+	}
+}
+
+/// Quote a word for the POSIX shell, so that
+/// [`RawStr::shell_split`](struct.RawStr.html#method.shell_split) (or `sh`)
+/// turns it back into the same word.
+///
+/// Words that only consist of safe characters (ASCII letters and digits, and
+/// `@%+=:,./-_`) are returned as they are. Other words are put in single
+/// quotes.
+///
+/// # Example
+///
+/// ```
+/// # use raw_string::{shell_quote, RawStr};
+/// assert_eq!(&*shell_quote(RawStr::from_str("file.txt")), "file.txt");
+/// assert_eq!(&*shell_quote(RawStr::from_str("it's $x")), "'it'\\''s $x'");
+/// assert_eq!(&*shell_quote(RawStr::from_bytes(b"\xFF")), &b"'\xFF'"[..]);
+/// assert_eq!(&*shell_quote(RawStr::from_str("")), "''");
+/// ```
+pub fn shell_quote(word: &RawStr) -> Cow<'_, RawStr> {
+	if !word.is_empty() && word.bytes().all(is_safe) {
+		return Cow::Borrowed(word);
+	}
+	let mut result = RawString::with_capacity(word.len() + 2);
+	result.push(b'\'');
+	for b in word.bytes() {
+		if b == b'\'' {
+			result.push_str("'\\''");
+		} else {
+			result.push(b);
+		}
+	}
+	result.push(b'\'');
+	Cow::Owned(result)
+}
+
+/// Quote every word with [`shell_quote`](fn.shell_quote.html), and join them
+/// with spaces.
+///
+/// # Example
+///
+/// ```
+/// # use raw_string::shell_join;
+/// assert_eq!(shell_join(&["echo", "a b", "c"]), "echo 'a b' c");
+/// ```
+pub fn shell_join<I>(words: I) -> RawString
+where
+	I: IntoIterator,
+	I::Item: AsRef<RawStr>,
+{
+	let mut result = RawString::new();
+	for (i, word) in words.into_iter().enumerate() {
+		if i > 0 {
+			result.push(b' ');
+		}
+		result.push_str(&*shell_quote(word.as_ref()));
+	}
+	result
+}
+
+// Tests {{{
+
+#[cfg(test)]
+fn split(s: &[u8]) -> Result<Vec<Vec<u8>>, ShellSplitError> {
+	RawStr::from_bytes(s)
+		.shell_split()
+		.map(|words| words.into_iter().map(RawString::into_bytes).collect())
+}
+
+#[test]
+fn test_shell_split() {
+	let ok = |words: &[&[u8]]| Ok(words.iter().map(|w| w.to_vec()).collect::<Vec<_>>());
+	assert_eq!(split(b""), ok(&[]));
+	assert_eq!(split(b" \t\n "), ok(&[]));
+	assert_eq!(split(b"a  b\tc\nd"), ok(&[b"a", b"b", b"c", b"d"]));
+	assert_eq!(split(b"'' \"\" x''"), ok(&[b"", b"", b"x"]));
+	assert_eq!(split(b"'a\\b \"c' d"), ok(&[b"a\\b \"c", b"d"]));
+	assert_eq!(split(b"\"a\\$b\\`c\\\"d\\\\e\\f\""), ok(&[b"a$b`c\"d\\e\\f"]));
+	assert_eq!(split(b"\"a\\\nb\" c\\\nd"), ok(&[b"ab", b"cd"]));
+	assert_eq!(split(b"a\\ b\\'c \\\\"), ok(&[b"a b'c", b"\\"]));
+	assert_eq!(split(b"a'b'\"c\"d"), ok(&[b"abcd"]));
+	assert_eq!(split(b"a#b # c d\ne #"), ok(&[b"a#b", b"e"]));
+	assert_eq!(split(b"\\#a '#b'"), ok(&[b"#a", b"#b"]));
+	assert_eq!(split(b"$HOME *.rs ~"), ok(&[b"$HOME", b"*.rs", b"~"]));
+	assert_eq!(split(b"\xFF'\xFE x'\"\xE2\x82\""), ok(&[b"\xFF\xFE x\xE2\x82"]));
+
+	assert_eq!(split(b"a 'b"), Err(ShellSplitError::UnterminatedSingleQuote));
+	assert_eq!(split(b"a \"b\\\""), Err(ShellSplitError::UnterminatedDoubleQuote));
+	assert_eq!(split(b"a\\"), Err(ShellSplitError::TrailingBackslash));
+	assert_eq!(ShellSplitError::TrailingBackslash.to_string(), "trailing backslash");
+}
+
+#[test]
+fn test_shell_quote_and_join() {
+	for word in &[&b""[..], b"a", b"a b", b"'", b"''a'", b"\\\"$`", b"\n", b"#", b"~", b"\xFF\x00\xC3", b"-_=.@"] {
+		let word = RawStr::from_bytes(word);
+		let quoted = shell_quote(word);
+		assert_eq!(quoted.shell_split().unwrap(), [word]);
+	}
+	assert!(matches!(shell_quote(RawStr::from_str("a/b.c")), Cow::Borrowed(_)));
+
+	let words = [RawStr::from_str("x y"), RawStr::from_bytes(b"\xFF"), RawStr::from_str("z")];
+	let joined = shell_join(&words);
+	assert_eq!(joined, &b"'x y' '\xFF' z"[..]);
+	assert_eq!(joined.shell_split().unwrap(), words);
+	assert_eq!(shell_join(Vec::<RawString>::new()), "");
+}
+
+// }}}
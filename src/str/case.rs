@@ -0,0 +1,333 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use str::case_tables::SIMPLE_CASE_FOLDING;
+use str::{find_ignore_ascii_case, RawStr, Utf8Chunk};
+use string::RawString;
+
+/// The simple case folding of a character: the single character it maps to
+/// for caseless comparison, which is usually its lowercase form.
+pub(crate) fn simple_fold(c: char) -> char {
+	if c.is_ascii() {
+		return c.to_ascii_lowercase();
+	}
+	match SIMPLE_CASE_FOLDING.binary_search_by_key(&c, |&(from, _)| from) {
+		Ok(i) => SIMPLE_CASE_FOLDING[i].1,
+		Err(_) => c,
+	}
+}
+
+/// A character of valid UTF-8, or a byte that isn't part of valid UTF-8.
+#[derive(PartialEq, Eq)]
+enum Unit {
+	Char(char),
+	Byte(u8),
+}
+
+/// The case folded characters and the broken bytes of a string.
+fn folded_units(s: &RawStr) -> impl Iterator<Item = Unit> + '_ {
+	s.utf8_chunks().flat_map(|Utf8Chunk { valid, broken }| {
+		valid
+			.chars()
+			.map(|c| Unit::Char(simple_fold(c)))
+			.chain(broken.iter().map(|&b| Unit::Byte(b)))
+	})
+}
+
+impl RawStr {
+	/// Find the first occurrence of `needle`, ignoring ASCII case.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"Content-Type: text/plain\r\n\xFF");
+	/// assert_eq!(s.find_ignore_ascii_case("content-TYPE"), Some(0));
+	/// assert_eq!(s.find_ignore_ascii_case("TEXT/"), Some(14));
+	/// ```
+	pub fn find_ignore_ascii_case<T: AsRef<RawStr>>(&self, needle: T) -> Option<usize> {
+		find_ignore_ascii_case(needle.as_ref().as_bytes(), self.as_bytes())
+	}
+
+	/// Whether `needle` occurs anywhere, ignoring ASCII case.
+	#[inline]
+	pub fn contains_ignore_ascii_case<T: AsRef<RawStr>>(&self, needle: T) -> bool {
+		self.find_ignore_ascii_case(needle).is_some()
+	}
+
+	/// Whether this starts with `prefix`, ignoring ASCII case.
+	#[inline]
+	pub fn starts_with_ignore_ascii_case<T: AsRef<RawStr>>(&self, prefix: T) -> bool {
+		let prefix = prefix.as_ref();
+		self.len() >= prefix.len() && self[..prefix.len()].eq_ignore_ascii_case(prefix)
+	}
+
+	/// Whether this ends with `suffix`, ignoring ASCII case.
+	#[inline]
+	pub fn ends_with_ignore_ascii_case<T: AsRef<RawStr>>(&self, suffix: T) -> bool {
+		let suffix = suffix.as_ref();
+		self.len() >= suffix.len() && self[self.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+	}
+
+	/// Compare, as if both were converted to ASCII lowercase.
+	pub fn cmp_ignore_ascii_case(&self, other: &RawStr) -> Ordering {
+		let lower = |b: u8| b.to_ascii_lowercase();
+		self.bytes().map(lower).cmp(other.bytes().map(lower))
+	}
+
+	/// Convert to uppercase, using the full Unicode case mapping.
+	///
+	/// Only valid UTF-8 is converted. Bytes that aren't part of valid UTF-8
+	/// are copied unchanged. Like `str::to_uppercase`, a character can become
+	/// multiple characters: `ß` becomes `SS`.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"stra\xC3\x9Fe \xFF\xC3\xA9");
+	/// assert_eq!(s.to_uppercase(), RawStr::from_bytes(b"STRASSE \xFF\xC3\x89"));
+	/// ```
+	pub fn to_uppercase(&self) -> RawString {
+		let mut result = RawString::with_capacity(self.len());
+		for Utf8Chunk { valid, broken } in self.utf8_chunks() {
+			result.push_str(valid.to_uppercase().as_str());
+			result.push_str(RawStr::from_bytes(broken));
+		}
+		result
+	}
+
+	/// Convert to lowercase, using the full Unicode case mapping.
+	///
+	/// Only valid UTF-8 is converted. Bytes that aren't part of valid UTF-8
+	/// are copied unchanged.
+	///
+	/// Like `str::to_lowercase`, a `Σ` at the end of a word becomes `ς`. Words
+	/// are not considered to continue across bytes that aren't valid UTF-8.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"\xC3\x89T\xC3\x89 \xFF");
+	/// assert_eq!(s.to_lowercase(), RawStr::from_bytes(b"\xC3\xA9t\xC3\xA9 \xFF"));
+	/// ```
+	pub fn to_lowercase(&self) -> RawString {
+		let mut result = RawString::with_capacity(self.len());
+		for Utf8Chunk { valid, broken } in self.utf8_chunks() {
+			result.push_str(valid.to_lowercase().as_str());
+			result.push_str(RawStr::from_bytes(broken));
+		}
+		result
+	}
+
+	/// Apply Unicode simple case folding.
+	///
+	/// Every character of valid UTF-8 is replaced by its simple case folding
+	/// (usually its lowercase form), and all other bytes are kept as they are.
+	/// Two strings that only differ in case fold to the same string, unless
+	/// that difference involves a character that folds to multiple characters
+	/// (like `ß` and `SS`).
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"\xCE\xA3\xCE\xB9\xCF\x83\xCF\x8D\xCF\x86\xCE\xBF\xCF\x82 \xFF");
+	/// assert_eq!(s.case_fold(), RawStr::from_bytes(b"\xCF\x83\xCE\xB9\xCF\x83\xCF\x8D\xCF\x86\xCE\xBF\xCF\x83 \xFF"));
+	/// ```
+	pub fn case_fold(&self) -> RawString {
+		let mut result = RawString::with_capacity(self.len());
+		for Utf8Chunk { valid, broken } in self.utf8_chunks() {
+			let mut buf = [0; 4];
+			for c in valid.chars() {
+				result.push_str(simple_fold(c).encode_utf8(&mut buf));
+			}
+			result.push_str(RawStr::from_bytes(broken));
+		}
+		result
+	}
+
+	/// Whether both strings are the same after Unicode simple case folding.
+	///
+	/// This is the same as `a.case_fold() == b.case_fold()`, but without
+	/// allocating.
+	pub fn eq_ignore_case(&self, other: &RawStr) -> bool {
+		folded_units(self).eq(folded_units(other))
+	}
+}
+
+// CaseInsensitive {{{
+
+/// A wrapper that compares and hashes a string ignoring ASCII case.
+///
+/// Useful as a key in a `HashMap` or `BTreeMap`, for example for HTTP
+/// headers.
+///
+/// # Example
+///
+/// ```
+/// # use raw_string::{CaseInsensitive, RawStr};
+/// # use std::collections::HashSet;
+/// let mut headers = HashSet::new();
+/// headers.insert(CaseInsensitive(RawStr::from_str("Content-Type")));
+/// assert!(headers.contains(&CaseInsensitive(RawStr::from_str("content-type"))));
+/// ```
+#[derive(Clone, Copy, Default)]
+pub struct CaseInsensitive<T>(pub T);
+
+impl<T> CaseInsensitive<T> {
+	/// Unwrap the string.
+	#[inline]
+	pub fn into_inner(self) -> T {
+		self.0
+	}
+}
+
+impl<T: AsRef<RawStr>> AsRef<RawStr> for CaseInsensitive<T> {
+	#[inline]
+	fn as_ref(&self) -> &RawStr {
+		self.0.as_ref()
+	}
+}
+
+impl<T: AsRef<RawStr>> PartialEq for CaseInsensitive<T> {
+	#[inline]
+	fn eq(&self, other: &Self) -> bool {
+		self.as_ref().eq_ignore_ascii_case(other.as_ref())
+	}
+}
+
+impl<T: AsRef<RawStr>> Eq for CaseInsensitive<T> {}
+
+impl<T: AsRef<RawStr>> PartialOrd for CaseInsensitive<T> {
+	#[inline]
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<T: AsRef<RawStr>> Ord for CaseInsensitive<T> {
+	#[inline]
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.as_ref().cmp_ignore_ascii_case(other.as_ref())
+	}
+}
+
+impl<T: AsRef<RawStr>> Hash for CaseInsensitive<T> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		let s = self.as_ref().as_bytes();
+		state.write_usize(s.len());
+		let mut buf = [0u8; 64];
+		for chunk in s.chunks(buf.len()) {
+			let buf = &mut buf[..chunk.len()];
+			buf.copy_from_slice(chunk);
+			buf.make_ascii_lowercase();
+			state.write(buf);
+		}
+	}
+}
+
+impl<T: AsRef<RawStr>> Debug for CaseInsensitive<T> {
+	#[inline]
+	fn fmt(&self, f: &mut Formatter) -> ::std::fmt::Result {
+		Debug::fmt(self.as_ref(), f)
+	}
+}
+
+impl<T: AsRef<RawStr>> Display for CaseInsensitive<T> {
+	#[inline]
+	fn fmt(&self, f: &mut Formatter) -> ::std::fmt::Result {
+		Display::fmt(self.as_ref(), f)
+	}
+}
+
+impl<T> From<T> for CaseInsensitive<T> {
+	#[inline]
+	fn from(s: T) -> Self {
+		CaseInsensitive(s)
+	}
+}
+
+// }}}
+
+// Tests {{{
+
+#[test]
+fn test_ignore_ascii_case() {
+	let s = RawStr::from_bytes(b"xX\xFFaBcAbC\xC3\xA9");
+	assert_eq!(s.find_ignore_ascii_case("abc"), Some(3));
+	assert_eq!(s.find_ignore_ascii_case("cabc"), Some(5));
+	assert_eq!(s.find_ignore_ascii_case(RawStr::from_bytes(b"\xFFA")), Some(2));
+	assert_eq!(s.find_ignore_ascii_case("\u{C9}"), None);
+	assert_eq!(s.find_ignore_ascii_case(""), Some(0));
+	assert_eq!(s.find_ignore_ascii_case("abcabcabcabcabc"), None);
+	let mut long = RawString::from(vec![b'A'; 200_000]);
+	let mut needle = RawString::from(vec![b'a'; 20_000]);
+	needle.push(b'b');
+	assert_eq!(long.find_ignore_ascii_case(&needle), None);
+	long.push(b'B');
+	assert_eq!(long.find_ignore_ascii_case(&needle), Some(180_000));
+	assert!(s.contains_ignore_ascii_case("XX"));
+	assert!(s.starts_with_ignore_ascii_case("XX"));
+	assert!(!s.starts_with_ignore_ascii_case("xxx"));
+	assert!(s.ends_with_ignore_ascii_case("ABC\u{E9}"));
+	assert!(!RawStr::from_str("a").ends_with_ignore_ascii_case("ba"));
+	assert_eq!(RawStr::from_str("abc").cmp_ignore_ascii_case(RawStr::from_str("ABD")), Ordering::Less);
+	assert_eq!(RawStr::from_str("B").cmp_ignore_ascii_case(RawStr::from_str("a")), Ordering::Greater);
+	assert_eq!(RawStr::from_str("[").cmp_ignore_ascii_case(RawStr::from_str("a")), Ordering::Less);
+	assert_eq!(RawStr::from_str("aB").cmp_ignore_ascii_case(RawStr::from_str("Ab")), Ordering::Equal);
+}
+
+#[test]
+fn test_case_insensitive() {
+	use std::collections::hash_map::DefaultHasher;
+	use std::collections::{BTreeSet, HashSet};
+	let hash = |s: &str| {
+		let mut hasher = DefaultHasher::new();
+		CaseInsensitive(RawStr::from_str(s)).hash(&mut hasher);
+		hasher.finish()
+	};
+	let long = "x".repeat(100);
+	assert_eq!(hash("Content-Length"), hash("content-length"));
+	assert_eq!(hash(&long), hash(&long.to_uppercase()));
+	assert_ne!(hash("ab"), hash("abc"));
+
+	let set: HashSet<_> = ["Host", "HOST", "accept", "Accept", "\u{C9}", "\u{E9}"]
+		.iter()
+		.map(|s| CaseInsensitive(RawString::from(*s)))
+		.collect();
+	assert_eq!(set.len(), 4);
+	let set: BTreeSet<_> = ["b", "A", "C"].iter().map(|&s| CaseInsensitive(s)).collect();
+	assert_eq!(set.into_iter().map(CaseInsensitive::into_inner).collect::<Vec<_>>(), ["A", "b", "C"]);
+}
+
+#[test]
+fn test_to_upper_and_lowercase() {
+	let s = RawStr::from_bytes(b"ma\xC3\x9F \xC3\xA9\xE9t\xC3\xA9 \xFF\xFE \xC7\x85");
+	assert_eq!(s.to_uppercase(), RawStr::from_bytes(b"MASS \xC3\x89\xE9T\xC3\x89 \xFF\xFE \xC7\x84"));
+	assert_eq!(s.to_lowercase(), RawStr::from_bytes(b"ma\xC3\x9F \xC3\xA9\xE9t\xC3\xA9 \xFF\xFE \xC7\x86"));
+	assert_eq!(RawStr::from_str("\u{130}").to_lowercase(), "i\u{307}");
+	assert_eq!(RawStr::from_str("\u{3A3}\u{391}\u{3A3}").to_lowercase(), "\u{3C3}\u{3B1}\u{3C2}");
+	assert_eq!(RawStr::from_bytes(b"").to_uppercase(), "");
+}
+
+#[test]
+fn test_case_fold() {
+	assert_eq!(simple_fold('A'), 'a');
+	assert_eq!(simple_fold('\u{C9}'), '\u{E9}');
+	assert_eq!(simple_fold('\u{1E9E}'), '\u{DF}');
+	assert_eq!(simple_fold('\u{3A3}'), '\u{3C3}');
+	assert_eq!(simple_fold('\u{3C2}'), '\u{3C3}');
+	assert_eq!(simple_fold('\u{10400}'), '\u{10428}');
+	assert_eq!(simple_fold('\u{DF}'), '\u{DF}');
+	assert_eq!(simple_fold('1'), '1');
+	let s = RawStr::from_bytes(b"\xC3\x89T\xC3\xA9\xC3 \xFF\xE2\x84\xAA");
+	assert_eq!(s.case_fold(), RawStr::from_bytes(b"\xC3\xA9t\xC3\xA9\xC3 \xFFk"));
+	assert!(s.eq_ignore_case(RawStr::from_bytes(b"\xC3\xA9t\xC3\x89\xC3 \xFFK")));
+	assert!(!s.eq_ignore_case(RawStr::from_bytes(b"\xC3\xA9t\xC3\x89\xC4 \xFFK")));
+	assert!(!RawStr::from_str("stra\u{DF}e").eq_ignore_case(RawStr::from_str("STRASSE")));
+	assert!(RawStr::from_str("stra\u{DF}e").eq_ignore_case(RawStr::from_str("STRA\u{1E9E}E")));
+}
+
+// }}}
@@ -34,8 +34,16 @@ pub trait RawStrIndex {
 	/// Get the (mutable) range or byte from the given `&mut RawStr`.
 	fn get_mut(self, s: &mut RawStr) -> Option<&mut Self::Output>;
 	/// Like `get`, but unsafe and unchecked.
+	///
+	/// # Safety
+	///
+	/// The index must be in bounds.
 	unsafe fn get_unchecked(self, s: &RawStr) -> &Self::Output;
 	/// Like `get_mut`, but unsafe and unchecked.
+	///
+	/// # Safety
+	///
+	/// The index must be in bounds.
 	unsafe fn get_unchecked_mut(self, s: &mut RawStr) -> &mut Self::Output;
 	/// Like `get`, but panics on failure.
 	fn index(self, s: &RawStr) -> &Self::Output;
@@ -44,6 +52,7 @@ pub trait RawStrIndex {
 }
 
 #[doc(hidden)]
+#[allow(clippy::wrong_self_convention)]
 pub trait RawStrIndexOutput {
 	type Output: ?Sized;
 	fn into(&self) -> &Self::Output;
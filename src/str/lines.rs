@@ -0,0 +1,116 @@
+use std::iter::FusedIterator;
+use str::{memchr, memrchr, RawStr};
+
+/// Iterator over the lines of a string.
+///
+/// See [`RawStr::lines`](struct.RawStr.html#method.lines).
+#[derive(Clone)]
+pub struct Lines<'a> {
+	rest: &'a [u8],
+}
+
+/// Remove the `\r` of a `\r\n` line ending.
+fn strip_cr(line: &[u8]) -> &RawStr {
+	RawStr::from_bytes(line.strip_suffix(b"\r").unwrap_or(line))
+}
+
+impl<'a> Iterator for Lines<'a> {
+	type Item = &'a RawStr;
+	fn next(&mut self) -> Option<&'a RawStr> {
+		if self.rest.is_empty() {
+			return None;
+		}
+		let line = match memchr(b'\n', self.rest) {
+			Some(end) => {
+				let line = strip_cr(&self.rest[..end]);
+				self.rest = &self.rest[end + 1..];
+				line
+			}
+			None => RawStr::from_bytes(::std::mem::take(&mut self.rest)),
+		};
+		Some(line)
+	}
+}
+
+impl<'a> DoubleEndedIterator for Lines<'a> {
+	fn next_back(&mut self) -> Option<&'a RawStr> {
+		if self.rest.is_empty() {
+			return None;
+		}
+		let (body, terminated) = match self.rest.strip_suffix(b"\n") {
+			Some(body) => (body, true),
+			None => (self.rest, false),
+		};
+		let start = memrchr(b'\n', body).map_or(0, |i| i + 1);
+		self.rest = &self.rest[..start];
+		if terminated {
+			Some(strip_cr(&body[start..]))
+		} else {
+			Some(RawStr::from_bytes(&body[start..]))
+		}
+	}
+}
+
+impl<'a> FusedIterator for Lines<'a> {}
+
+impl RawStr {
+	/// Iterate over the lines of the string.
+	///
+	/// Lines end with `\n` or `\r\n`, which is not included. The last line
+	/// doesn't need a line ending. Like `str::lines`, this doesn't produce an
+	/// empty line after a final line ending.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"a\r\n\xFF\n\nb");
+	/// let lines: Vec<&RawStr> = s.lines().collect();
+	/// assert_eq!(lines, [&b"a"[..], b"\xFF", b"", b"b"]);
+	/// ```
+	#[inline]
+	pub fn lines(&self) -> Lines<'_> {
+		Lines { rest: self.as_bytes() }
+	}
+}
+
+// Tests {{{
+
+#[cfg(test)]
+fn lines(s: &[u8]) -> (Vec<&[u8]>, Vec<&[u8]>) {
+	let s = RawStr::from_bytes(s);
+	let forward = s.lines().map(RawStr::as_bytes).collect();
+	let mut backward: Vec<&[u8]> = s.lines().rev().map(RawStr::as_bytes).collect();
+	backward.reverse();
+	(forward, backward)
+}
+
+#[test]
+fn test_lines() {
+	for &(s, expected) in &[
+		(&b""[..], &[][..]),
+		(b"\n", &[&b""[..]]),
+		(b"a", &[b"a"]),
+		(b"a\n", &[b"a"]),
+		(b"a\n\n", &[b"a", b""]),
+		(b"a\r\nb\r\n", &[b"a", b"b"]),
+		(b"a\rb\r", &[b"a\rb\r"]),
+		(b"\r\n\r\r\n", &[b"", b"\r"]),
+		(b"\xFF\n\xFE\r\n\xE2\x82", &[b"\xFF", b"\xFE", b"\xE2\x82"]),
+	] {
+		let (forward, backward) = lines(s);
+		assert_eq!(forward, expected);
+		assert_eq!(backward, expected);
+	}
+
+	let s = RawStr::from_str("a\nb\nc\nd");
+	let mut iter = s.lines();
+	assert_eq!(iter.next_back(), Some(RawStr::from_str("d")));
+	assert_eq!(iter.next(), Some(RawStr::from_str("a")));
+	assert_eq!(iter.next_back(), Some(RawStr::from_str("c")));
+	assert_eq!(iter.next(), Some(RawStr::from_str("b")));
+	assert_eq!(iter.next(), None);
+	assert_eq!(iter.next_back(), None);
+}
+
+// }}}
@@ -2,18 +2,34 @@ use std;
 use std::char::REPLACEMENT_CHARACTER;
 use std::cmp::Ordering;
 use std::convert::AsRef;
-use std::ffi::OsStr;
+use std::ffi::{CStr, CString, NulError, OsStr};
 use std::fmt::{Debug, Display, Formatter, Write};
 use std::mem::transmute;
 use std::ops::{Index, IndexMut};
+use std::os::raw::c_char;
 use std::path::Path;
 use std::str::{from_utf8, Utf8Error};
+use string::RawString;
 
+mod case;
+mod case_tables;
 mod index;
+mod lines;
+mod multi_pattern;
+mod pattern;
+mod search;
 mod utf8chunks;
+mod whitespace;
 
+pub use self::case::CaseInsensitive;
 pub use self::index::{RawStrIndex, RawStrIndexOutput};
-pub use self::utf8chunks::{Utf8Chunk, Utf8ChunksIter};
+pub use self::lines::Lines;
+pub use self::multi_pattern::{MatchKind, MultiMatch, MultiMatches, RawMultiPattern, RawMultiPatternBuilder};
+pub use self::pattern::{MatchIndices, Matches, RSplit, RawPattern, RawSearcher, Split};
+pub use self::search::{CharSearcher, RawFinder, RawFinderIter, StrSearcher};
+pub(crate) use self::search::{find_ignore_ascii_case, memchr, memchr2, memchr3, memrchr, memrchr2, memrchr3};
+pub use self::utf8chunks::{Utf8Chunk, Utf8ChunksIter, Utf8ErrorKind};
+pub use self::whitespace::{SplitAsciiWhitespace, SplitWhitespace};
 
 /// A `str` with unchecked contents.
 ///
@@ -37,6 +53,7 @@ impl RawStr {
 		unsafe { transmute::<&[u8], &Self>(bytes) }
 	}
 
+	#[allow(clippy::should_implement_trait)]
 	#[inline]
 	pub fn from_str(bytes: &str) -> &Self {
 		Self::from_bytes(bytes.as_bytes())
@@ -74,7 +91,7 @@ impl RawStr {
 
 	#[inline]
 	pub fn first(&self) -> Option<u8> {
-		self.inner.first().map(|&x| x)
+		self.inner.first().cloned()
 	}
 
 	#[inline]
@@ -84,7 +101,7 @@ impl RawStr {
 
 	#[inline]
 	pub fn last(&self) -> Option<u8> {
-		self.inner.last().map(|&x| x)
+		self.inner.last().cloned()
 	}
 
 	#[inline]
@@ -134,7 +151,133 @@ impl RawStr {
 
 	#[inline]
 	pub fn contains_byte(&self, x: u8) -> bool {
-		self.inner.contains(&x)
+		memchr(x, &self.inner).is_some()
+	}
+
+	/// Find the index of the first occurrence of the byte `x`.
+	///
+	/// This compares a whole machine word at a time, which is much faster
+	/// than comparing byte by byte on long strings.
+	#[inline]
+	pub fn find_byte(&self, x: u8) -> Option<usize> {
+		memchr(x, &self.inner)
+	}
+
+	/// Find the index of the last occurrence of the byte `x`.
+	#[inline]
+	pub fn rfind_byte(&self, x: u8) -> Option<usize> {
+		memrchr(x, &self.inner)
+	}
+
+	/// Find the index of the first occurrence of the byte `x` or `y`.
+	#[inline]
+	pub fn find_byte2(&self, x: u8, y: u8) -> Option<usize> {
+		memchr2(x, y, &self.inner)
+	}
+
+	/// Find the index of the last occurrence of the byte `x` or `y`.
+	#[inline]
+	pub fn rfind_byte2(&self, x: u8, y: u8) -> Option<usize> {
+		memrchr2(x, y, &self.inner)
+	}
+
+	/// Find the index of the first occurrence of the byte `x`, `y` or `z`.
+	#[inline]
+	pub fn find_byte3(&self, x: u8, y: u8, z: u8) -> Option<usize> {
+		memchr3(x, y, z, &self.inner)
+	}
+
+	/// Find the index of the last occurrence of the byte `x`, `y` or `z`.
+	#[inline]
+	pub fn rfind_byte3(&self, x: u8, y: u8, z: u8) -> Option<usize> {
+		memrchr3(x, y, z, &self.inner)
+	}
+
+	/// Whether the pattern matches anywhere in the string.
+	#[inline]
+	pub fn contains<P: RawPattern>(&self, pat: P) -> bool {
+		self.find(pat).is_some()
+	}
+
+	/// Find the index of the first match of the pattern.
+	///
+	/// Use a [`RawFinder`](struct.RawFinder.html) to search for the same
+	/// string many times.
+	#[inline]
+	pub fn find<P: RawPattern>(&self, pat: P) -> Option<usize> {
+		pat.into_searcher().find_at(self, 0).map(|m| m.start)
+	}
+
+	/// Find the index of the last match of the pattern.
+	///
+	/// Use a [`RawFinder`](struct.RawFinder.html) to search for the same
+	/// string many times.
+	#[inline]
+	pub fn rfind<P: RawPattern>(&self, pat: P) -> Option<usize> {
+		pat.into_searcher().rfind_at(self, self.len()).map(|m| m.start)
+	}
+
+	/// Iterate over the parts of the string, separated by the pattern.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"a,\xFF,,b");
+	/// let parts: Vec<&RawStr> = s.split(b',').collect();
+	/// assert_eq!(parts, [&b"a"[..], &b"\xFF"[..], &b""[..], &b"b"[..]]);
+	/// ```
+	#[inline]
+	pub fn split<P: RawPattern>(&self, pat: P) -> Split<'_, P> {
+		Split::new(self, pat, usize::MAX)
+	}
+
+	/// Iterate over the parts of the string, separated by the pattern,
+	/// starting from the end.
+	#[inline]
+	pub fn rsplit<P: RawPattern>(&self, pat: P) -> RSplit<'_, P> {
+		RSplit::new(self, pat, usize::MAX)
+	}
+
+	/// Like [`split`](#method.split), but stops after `n` parts.
+	///
+	/// The last part contains the remainder of the string.
+	#[inline]
+	pub fn splitn<P: RawPattern>(&self, n: usize, pat: P) -> Split<'_, P> {
+		Split::new(self, pat, n)
+	}
+
+	/// Like [`rsplit`](#method.rsplit), but stops after `n` parts.
+	///
+	/// The last part contains the remainder of the string.
+	#[inline]
+	pub fn rsplitn<P: RawPattern>(&self, n: usize, pat: P) -> RSplit<'_, P> {
+		RSplit::new(self, pat, n)
+	}
+
+	/// Iterate over the non-overlapping matches of the pattern.
+	#[inline]
+	pub fn matches<P: RawPattern>(&self, pat: P) -> Matches<'_, P> {
+		Matches::new(self, pat)
+	}
+
+	/// Iterate over the non-overlapping matches of the pattern, and their
+	/// indices.
+	#[inline]
+	pub fn match_indices<P: RawPattern>(&self, pat: P) -> MatchIndices<'_, P> {
+		MatchIndices::new(self, pat)
+	}
+
+	/// Replace all matches of the pattern.
+	#[inline]
+	pub fn replace<P: RawPattern, T: AsRef<RawStr>>(&self, from: P, to: T) -> RawString {
+		pattern::replace(self, from, to.as_ref(), usize::MAX)
+	}
+
+	/// Replace the first `count` matches of the pattern.
+	#[inline]
+	pub fn replacen<P: RawPattern, T: AsRef<RawStr>>(&self, from: P, to: T, count: usize) -> RawString {
+		pattern::replace(self, from, to.as_ref(), count)
 	}
 
 	#[inline]
@@ -157,33 +300,45 @@ impl RawStr {
 		index.get_mut(self)
 	}
 
+	/// # Safety
+	///
+	/// The index must be in bounds.
 	#[inline]
 	pub unsafe fn get_unchecked<I: RawStrIndex>(&self, index: I) -> &I::Output {
 		index.get_unchecked(self)
 	}
 
+	/// # Safety
+	///
+	/// The index must be in bounds.
 	#[inline]
 	pub unsafe fn get_unchecked_mut<I: RawStrIndex>(&mut self, index: I) -> &mut I::Output {
 		index.get_unchecked_mut(self)
 	}
 
+	/// # Safety
+	///
+	/// The index must be in bounds.
 	#[inline]
 	pub unsafe fn slice_unchecked(&self, begin: usize, end: usize) -> &RawStr {
 		self.get_unchecked(begin..end)
 	}
 
+	/// # Safety
+	///
+	/// The index must be in bounds.
 	#[inline]
 	pub unsafe fn slice_mut_unchecked(&mut self, begin: usize, end: usize) -> &mut RawStr {
 		self.get_unchecked_mut(begin..end)
 	}
 
 	#[inline]
-	pub fn bytes(&self) -> std::iter::Cloned<std::slice::Iter<u8>> {
+	pub fn bytes(&self) -> std::iter::Cloned<std::slice::Iter<'_, u8>> {
 		self.inner.iter().cloned()
 	}
 
 	#[inline]
-	pub fn bytes_mut(&mut self) -> std::slice::IterMut<u8> {
+	pub fn bytes_mut(&mut self) -> std::slice::IterMut<'_, u8> {
 		self.inner.iter_mut()
 	}
 
@@ -193,38 +348,25 @@ impl RawStr {
 	/// broken characters, which could be replaced by the unicode replacement
 	/// character.
 	#[inline]
-	pub fn utf8_chunks(&self) -> Utf8ChunksIter {
-		Utf8ChunksIter { bytes: &self.inner }
+	pub fn utf8_chunks(&self) -> Utf8ChunksIter<'_> {
+		Utf8ChunksIter::new(&self.inner)
 	}
 
 	// Things that could be added:
-	//   pub fn lines(&self) -> Lines
-	//   pub fn split_whitespace(&self) -> SplitWhitespace
 	//   pub fn trim
 	//   pub fn trim_left
 	//   pub fn trim_right
 	//
-	//  RawPattern and:
-	//   pub fn contains<'a, P: RawPattern<'a>>(&'a self, pat: P) -> bool
+	//  With RawPattern:
 	//   pub fn starts_with<'a, P: RawPattern<'a>>(&'a self, pat: P) -> bool
 	//   pub fn ends_with<'a, P: RawPattern<'a>>(&'a self, pat: P) -> bool
-	//   pub fn find<'a, P: RawPattern<'a>>(&'a self, pat: P) -> Option<usize>
-	//   pub fn rfind<'a, P: RawPattern<'a>>(&'a self, pat: P) -> Option<usize>
-	//   pub fn split<'a, P: RawPattern<'a>>(&'a self, pat: P) -> Split<'a, P>
-	//   pub fn rsplit<'a, P: RawPattern<'a>>(&'a self, pat: P) -> RSplit<'a, P>
 	//   pub fn split_terminator<'a, P: RawPattern<'a>>(&'a self, pat: P) -> SplitTerminator<'a, P>
 	//   pub fn rsplit_terminator<'a, P: RawPattern<'a>>(&'a self, pat: P) -> RSplitTerminator<'a, P>
-	//   pub fn splitn<'a, P: RawPattern<'a>>(&'a self, n: usize, pat: P) -> Split<'a, P>
-	//   pub fn rsplitn<'a, P: RawPattern<'a>>(&'a self, n: usize, pat: P) -> RSplit<'a, P>
-	//   pub fn matches<'a, P: RawPattern<'a>>(&'a self, pat: P) -> Matches<'a, P>
 	//   pub fn rmatches<'a, P: RawPattern<'a>>(&'a self, pat: P) -> Matches<'a, P>
-	//   pub fn match_indices<'a, P: RawPattern<'a>>(&'a self, pat: P) -> Matches<'a, P>
 	//   pub fn rmatch_indices<'a, P: RawPattern<'a>>(&'a self, pat: P) -> Matches<'a, P>
 	//   pub fn trim_matches <RawPattern>
 	//   pub fn trim_left_matches <RawPattern>
 	//   pub fn trim_right_matches <RawPattern>
-	//   pub fn replace (RawPattern -> AsRef<RawStr>) -> RawString
-	//   pub fn replace_n (RawPattern -> AsRef<RawStr>, n) -> RawString
 	//
 	//   pub fn is_utf8_char_boundary(&self, index: usize) -> bool
 	//   pub fn utf8_chars() -> Utf8Chars
@@ -273,6 +415,59 @@ impl RawStr {
 		Ok(OsStr::new(self.to_str()?))
 	}
 
+	/// Convert to a C string, by appending a NUL byte.
+	///
+	/// Fails if the string contains a NUL byte itself.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let c = RawStr::from_bytes(b"a\xFF").to_cstring().unwrap();
+	/// assert_eq!(c.as_bytes_with_nul(), b"a\xFF\0");
+	/// assert_eq!(RawStr::from_str("a\0b").to_cstring().unwrap_err().nul_position(), 1);
+	/// ```
+	#[inline]
+	pub fn to_cstring(&self) -> Result<CString, NulError> {
+		CString::new(self.as_bytes())
+	}
+
+	/// The contents of a C string, without the terminating NUL byte.
+	#[inline]
+	pub fn from_cstr(s: &CStr) -> &Self {
+		Self::from_bytes(s.to_bytes())
+	}
+
+	/// The bytes up to the first NUL byte, or all of them if there is none.
+	///
+	/// This is useful for fixed size buffers that are filled in by C
+	/// functions.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// assert_eq!(RawStr::from_bytes_until_nul(b"name\0\0\0"), "name");
+	/// assert_eq!(RawStr::from_bytes_until_nul(b"name"), "name");
+	/// ```
+	#[inline]
+	pub fn from_bytes_until_nul(bytes: &[u8]) -> &Self {
+		let end = memchr(0, bytes).unwrap_or(bytes.len());
+		Self::from_bytes(&bytes[..end])
+	}
+
+	/// The contents of a NUL-terminated C string, without the NUL byte.
+	///
+	/// # Safety
+	///
+	/// The same as for [`CStr::from_ptr`]: `ptr` must point to a
+	/// NUL-terminated string that stays valid and unmodified for the lifetime
+	/// `'a`.
+	#[inline]
+	pub unsafe fn from_ptr<'a>(ptr: *const c_char) -> &'a Self {
+		Self::from_cstr(CStr::from_ptr(ptr))
+	}
+
 	#[inline]
 	pub fn is_ascii(&self) -> bool {
 		self.inner.is_ascii()
@@ -328,14 +523,14 @@ impl AsRef<[u8]> for RawStr {
 
 // Default {{{
 
-impl<'a> Default for &'a RawStr {
+impl Default for &RawStr {
 	#[inline]
 	fn default() -> Self {
 		RawStr::from_bytes(&[])
 	}
 }
 
-impl<'a> Default for &'a mut RawStr {
+impl Default for &mut RawStr {
 	#[inline]
 	fn default() -> Self {
 		RawStr::from_bytes_mut(&mut [])
@@ -491,6 +686,24 @@ impl_ord!(&[u8]);
 
 // Tests {{{
 
+#[test]
+fn test_cstr() {
+	let c = RawStr::from_bytes(b"\xFFa").to_cstring().unwrap();
+	assert_eq!(RawStr::from_cstr(&c), &b"\xFFa"[..]);
+	assert_eq!(unsafe { RawStr::from_ptr(c.as_ptr()) }, &b"\xFFa"[..]);
+	assert_eq!(RawStr::from_str("").to_cstring().unwrap().as_bytes_with_nul(), b"\0");
+	assert!(RawStr::from_str("a\0").to_cstring().is_err());
+	assert_eq!(RawStr::from_bytes_until_nul(b""), "");
+	assert_eq!(RawStr::from_bytes_until_nul(b"\0a\0"), "");
+	assert_eq!(RawStr::from_bytes_until_nul(b"a\xFF\0b"), &b"a\xFF"[..]);
+
+	let s = RawString::from(c);
+	assert_eq!(s, &b"\xFFa"[..]);
+	assert_eq!(s.into_cstring().unwrap().as_bytes(), b"\xFFa");
+	let e = RawString::from("a\0b").into_cstring().unwrap_err();
+	assert_eq!(RawString::from_bytes(e.into_vec()), "a\0b");
+}
+
 #[test]
 fn test_display() {
 	let a = RawStr::from("1\" μs / °C");
@@ -0,0 +1,581 @@
+use std::collections::VecDeque;
+use std::iter::FusedIterator;
+use std::ops::Range;
+use str::{RawPattern, RawSearcher, RawStr};
+use string::RawString;
+
+/// Which match to report when several patterns of a
+/// [`RawMultiPattern`](struct.RawMultiPattern.html) match.
+///
+/// In both cases, the match that starts first wins. This only decides between
+/// matches that start at the same position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchKind {
+	/// Prefer the pattern that was given first, like alternation in regular
+	/// expressions.
+	LeftmostFirst,
+	/// Prefer the longest match.
+	LeftmostLongest,
+}
+
+impl Default for MatchKind {
+	#[inline]
+	fn default() -> Self {
+		MatchKind::LeftmostFirst
+	}
+}
+
+/// A match of a [`RawMultiPattern`](struct.RawMultiPattern.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MultiMatch {
+	/// The index of the pattern that matched.
+	pub pattern: usize,
+	/// The start of the match in the haystack.
+	pub start: usize,
+	/// The end of the match in the haystack.
+	pub end: usize,
+}
+
+impl MultiMatch {
+	/// The range of the match in the haystack.
+	#[inline]
+	pub fn range(&self) -> Range<usize> {
+		self.start..self.end
+	}
+}
+
+/// Builder for a [`RawMultiPattern`](struct.RawMultiPattern.html).
+#[derive(Clone, Debug, Default)]
+pub struct RawMultiPatternBuilder {
+	match_kind: MatchKind,
+	ascii_case_insensitive: bool,
+}
+
+impl RawMultiPatternBuilder {
+	/// Create a builder with the default options: leftmost-first matching,
+	/// and case sensitive.
+	#[inline]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set which match is reported when several patterns match.
+	#[inline]
+	pub fn match_kind(&mut self, match_kind: MatchKind) -> &mut Self {
+		self.match_kind = match_kind;
+		self
+	}
+
+	/// Whether to treat ASCII upper and lower case letters as equal.
+	///
+	/// Other bytes, including those of non-ASCII characters, must always match
+	/// exactly.
+	#[inline]
+	pub fn ascii_case_insensitive(&mut self, yes: bool) -> &mut Self {
+		self.ascii_case_insensitive = yes;
+		self
+	}
+
+	/// Compile the patterns.
+	pub fn build<I, T>(&self, patterns: I) -> RawMultiPattern
+	where
+		I: IntoIterator<Item = T>,
+		T: AsRef<RawStr>,
+	{
+		let patterns: Vec<T> = patterns.into_iter().collect();
+		let fold = |b: u8| {
+			if self.ascii_case_insensitive {
+				b.to_ascii_lowercase()
+			} else {
+				b
+			}
+		};
+
+		// All bytes that don't appear in any pattern behave the same, so they
+		// share class 0. All others get their own class.
+		let mut classes = [0u16; 256];
+		for p in &patterns {
+			for b in p.as_ref().bytes() {
+				classes[usize::from(fold(b))] = 1;
+			}
+		}
+		let mut stride = 1;
+		for class in classes.iter_mut() {
+			if *class != 0 {
+				*class = stride;
+				stride += 1;
+			}
+		}
+		if self.ascii_case_insensitive {
+			for b in b'A'..=b'Z' {
+				classes[usize::from(b)] = classes[usize::from(b.to_ascii_lowercase())];
+			}
+		}
+
+		let forward = Automaton::new(classes, usize::from(stride), patterns.iter().map(|p| p.as_ref().bytes()));
+		let reverse = Automaton::new(classes, usize::from(stride), patterns.iter().map(|p| p.as_ref().bytes().rev()));
+		RawMultiPattern {
+			match_kind: self.match_kind,
+			forward,
+			reverse,
+			pattern_count: patterns.len(),
+		}
+	}
+}
+
+const NONE: u32 = u32::MAX;
+
+/// A set of patterns, compiled into an Aho-Corasick automaton to search for
+/// all of them at once.
+///
+/// The time it takes to search does not depend on the number of patterns.
+/// Matches never overlap.
+///
+/// Can be used as a [`RawPattern`](trait.RawPattern.html) (by reference), so
+/// it can be used with [`RawStr::split`](struct.RawStr.html#method.split),
+/// [`RawStr::replace`](struct.RawStr.html#method.replace), and so on.
+///
+/// # Example
+///
+/// ```
+/// # use raw_string::{MatchKind, RawMultiPattern, RawStr};
+/// let patterns = RawMultiPattern::builder()
+///     .match_kind(MatchKind::LeftmostLongest)
+///     .ascii_case_insensitive(true)
+///     .build(&["error", "warn", "warning"]);
+/// let log = RawStr::from_bytes(b"WARNING: \xFF; Error: x");
+/// let found: Vec<usize> = patterns.find_iter(log).map(|m| m.pattern).collect();
+/// assert_eq!(found, [2, 0]);
+/// assert_eq!(patterns.replace_all(log, &["E", "W", "W"]), &b"W: \xFF; E: x"[..]);
+/// assert_eq!(log.split(&patterns).count(), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RawMultiPattern {
+	match_kind: MatchKind,
+	/// The automaton for the patterns.
+	forward: Automaton,
+	/// The automaton for the reversed patterns, to search backwards.
+	reverse: Automaton,
+	pattern_count: usize,
+}
+
+/// An Aho-Corasick automaton, with the transitions of a DFA.
+#[derive(Clone, Debug)]
+struct Automaton {
+	/// The equivalence class of each byte.
+	classes: [u16; 256],
+	/// The number of equivalence classes.
+	stride: usize,
+	/// The transitions, `stride` per state. State 0 is the start state.
+	trans: Vec<u32>,
+	/// The depth of each state in the trie, which is the length of the
+	/// pattern it matches, if any.
+	depth: Vec<u32>,
+	/// The pattern that ends in each state, or `NONE`.
+	output: Vec<u32>,
+	/// For each state, the next state on its chain of failure transitions
+	/// that has an output, or `NONE`.
+	dict: Vec<u32>,
+}
+
+impl Automaton {
+	fn new<P, B>(classes: [u16; 256], stride: usize, patterns: P) -> Automaton
+	where
+		P: Iterator<Item = B>,
+		B: Iterator<Item = u8>,
+	{
+		let mut automaton = Automaton {
+			classes,
+			stride,
+			trans: Vec::new(),
+			depth: Vec::new(),
+			output: Vec::new(),
+			dict: Vec::new(),
+		};
+		automaton.add_state(0);
+
+		// Build the trie.
+		for (id, p) in patterns.enumerate() {
+			let mut state = 0;
+			for b in p {
+				let i = automaton.index(state, b);
+				state = match automaton.trans[i] {
+					NONE => {
+						let next = automaton.add_state(automaton.depth[state as usize] + 1);
+						automaton.trans[i] = next;
+						next
+					}
+					next => next,
+				};
+			}
+			// For duplicates (or patterns equal apart from case), the first
+			// one wins.
+			if automaton.output[state as usize] == NONE {
+				automaton.output[state as usize] = id as u32;
+			}
+		}
+
+		// Fill in the failure transitions, breadth first.
+		let mut fail = vec![0u32; automaton.depth.len()];
+		let mut queue = VecDeque::new();
+		for t in &mut automaton.trans[..stride] {
+			if *t == NONE {
+				*t = 0;
+			} else {
+				queue.push_back(*t);
+			}
+		}
+		while let Some(state) = queue.pop_front() {
+			let s = state as usize;
+			let f = fail[s] as usize;
+			automaton.dict[s] = if automaton.output[f] != NONE {
+				f as u32
+			} else {
+				automaton.dict[f]
+			};
+			for c in 0..stride {
+				let t = automaton.trans[s * stride + c];
+				let ft = automaton.trans[f * stride + c];
+				if t == NONE {
+					automaton.trans[s * stride + c] = ft;
+				} else {
+					fail[t as usize] = ft;
+					queue.push_back(t);
+				}
+			}
+		}
+
+		automaton
+	}
+
+	fn add_state(&mut self, depth: u32) -> u32 {
+		let id = self.depth.len() as u32;
+		self.trans.extend((0..self.stride).map(|_| NONE));
+		self.depth.push(depth);
+		self.output.push(NONE);
+		self.dict.push(NONE);
+		id
+	}
+
+	#[inline]
+	fn index(&self, state: u32, b: u8) -> usize {
+		state as usize * self.stride + usize::from(self.classes[usize::from(b)])
+	}
+
+	#[inline]
+	fn next_state(&self, state: u32, b: u8) -> u32 {
+		self.trans[self.index(state, b)]
+	}
+
+	/// The patterns that end in the given state, longest first.
+	fn outputs(&self, state: u32) -> Outputs<'_> {
+		let first = if self.output[state as usize] != NONE {
+			state
+		} else {
+			self.dict[state as usize]
+		};
+		Outputs {
+			automaton: self,
+			state: first,
+		}
+	}
+}
+
+impl RawMultiPattern {
+	/// Compile the patterns with the default options.
+	///
+	/// Use [`builder`](#method.builder) for other options.
+	#[inline]
+	pub fn new<I, T>(patterns: I) -> Self
+	where
+		I: IntoIterator<Item = T>,
+		T: AsRef<RawStr>,
+	{
+		RawMultiPatternBuilder::new().build(patterns)
+	}
+
+	/// Create a builder, to set options before compiling.
+	#[inline]
+	pub fn builder() -> RawMultiPatternBuilder {
+		RawMultiPatternBuilder::new()
+	}
+
+	/// The number of patterns.
+	#[inline]
+	pub fn pattern_count(&self) -> usize {
+		self.pattern_count
+	}
+
+	/// The match kind this was built with.
+	#[inline]
+	pub fn match_kind(&self) -> MatchKind {
+		self.match_kind
+	}
+
+	/// Find the first match.
+	#[inline]
+	pub fn find(&self, haystack: &RawStr) -> Option<MultiMatch> {
+		self.find_from(haystack.as_bytes(), 0)
+	}
+
+	/// Iterate over all non-overlapping matches.
+	#[inline]
+	pub fn find_iter<'p, 'h>(&'p self, haystack: &'h RawStr) -> MultiMatches<'p, 'h> {
+		MultiMatches {
+			patterns: self,
+			haystack: haystack.as_bytes(),
+			position: 0,
+		}
+	}
+
+	/// Replace all matches by their replacement.
+	///
+	/// A match of the pattern with index `i` is replaced by `replacements[i]`.
+	///
+	/// # Panics
+	///
+	/// Panics if the number of replacements is not equal to the number of
+	/// patterns.
+	pub fn replace_all<T: AsRef<RawStr>>(&self, haystack: &RawStr, replacements: &[T]) -> RawString {
+		assert_eq!(
+			replacements.len(),
+			self.pattern_count,
+			"number of replacements does not match number of patterns"
+		);
+		let mut result = RawString::with_capacity(haystack.len());
+		let mut last_end = 0;
+		for m in self.find_iter(haystack) {
+			result.push_str(&haystack[last_end..m.start]);
+			result.push_str(&replacements[m.pattern]);
+			last_end = m.end;
+		}
+		result.push_str(&haystack[last_end..]);
+		result
+	}
+
+	/// Whether `a` is preferred over `b`, if they start at the same position.
+	fn prefer(&self, a: &MultiMatch, b: &MultiMatch) -> bool {
+		match self.match_kind {
+			MatchKind::LeftmostFirst => a.pattern < b.pattern,
+			MatchKind::LeftmostLongest => (a.end, b.pattern) > (b.end, a.pattern),
+		}
+	}
+
+	/// The matches that end at `end` in the given state of the forward
+	/// automaton, longest first.
+	fn matches_ending_at(&self, state: u32, end: usize) -> impl Iterator<Item = MultiMatch> + '_ {
+		self.forward.outputs(state).map(move |(pattern, len)| MultiMatch {
+			pattern,
+			start: end - len,
+			end,
+		})
+	}
+
+	/// Find the leftmost match that starts at or after `start`.
+	fn find_from(&self, haystack: &[u8], start: usize) -> Option<MultiMatch> {
+		let mut best: Option<MultiMatch> = None;
+		let consider = |m: MultiMatch, best: &mut Option<MultiMatch>| match *best {
+			// The rest of the outputs start even later.
+			Some(ref b) if m.start > b.start => false,
+			Some(ref b) if m.start == b.start && !self.prefer(&m, b) => true,
+			_ => {
+				*best = Some(m);
+				true
+			}
+		};
+		let mut state = 0;
+		for m in self.matches_ending_at(state, start) {
+			consider(m, &mut best);
+		}
+		for (end, &b) in (start + 1..).zip(haystack.get(start..)?) {
+			state = self.forward.next_state(state, b);
+			if let Some(ref b) = best {
+				// Nothing that is still in progress can start early enough.
+				if end - self.forward.depth[state as usize] as usize > b.start {
+					break;
+				}
+			}
+			for m in self.matches_ending_at(state, end) {
+				if !consider(m, &mut best) {
+					break;
+				}
+			}
+		}
+		best
+	}
+
+	/// Find the match that starts last, within `haystack[..end]`.
+	///
+	/// This runs the automaton of the reversed patterns backwards from `end`,
+	/// so the first matches it finds are the ones that start last.
+	fn rfind_until(&self, haystack: &[u8], end: usize) -> Option<MultiMatch> {
+		let haystack = haystack.get(..end)?;
+		let mut state = 0;
+		let mut start = end;
+		loop {
+			let mut best: Option<MultiMatch> = None;
+			for (pattern, len) in self.reverse.outputs(state) {
+				let m = MultiMatch {
+					pattern,
+					start,
+					end: start + len,
+				};
+				match best {
+					Some(ref b) if !self.prefer(&m, b) => {}
+					_ => best = Some(m),
+				}
+			}
+			if best.is_some() || start == 0 {
+				return best;
+			}
+			start -= 1;
+			state = self.reverse.next_state(state, haystack[start]);
+		}
+	}
+}
+
+/// The patterns and their lengths that end in a state, by following the
+/// `dict` links.
+struct Outputs<'a> {
+	automaton: &'a Automaton,
+	state: u32,
+}
+
+impl<'a> Iterator for Outputs<'a> {
+	type Item = (usize, usize);
+	#[inline]
+	fn next(&mut self) -> Option<(usize, usize)> {
+		if self.state == NONE {
+			return None;
+		}
+		let s = self.state as usize;
+		self.state = self.automaton.dict[s];
+		Some((self.automaton.output[s] as usize, self.automaton.depth[s] as usize))
+	}
+}
+
+/// Iterator over the matches of a [`RawMultiPattern`](struct.RawMultiPattern.html).
+///
+/// See [`RawMultiPattern::find_iter`](struct.RawMultiPattern.html#method.find_iter).
+#[derive(Clone, Debug)]
+pub struct MultiMatches<'p, 'h> {
+	patterns: &'p RawMultiPattern,
+	haystack: &'h [u8],
+	position: usize,
+}
+
+impl<'p, 'h> Iterator for MultiMatches<'p, 'h> {
+	type Item = MultiMatch;
+	fn next(&mut self) -> Option<MultiMatch> {
+		let found = self.patterns.find_from(self.haystack, self.position);
+		match found {
+			// Step over empty matches, so we don't find them again.
+			Some(m) if m.start == m.end => self.position = m.end + 1,
+			Some(m) => self.position = m.end,
+			None => self.position = self.haystack.len() + 1,
+		}
+		found
+	}
+}
+
+impl<'p, 'h> FusedIterator for MultiMatches<'p, 'h> {}
+
+impl RawSearcher for RawMultiPattern {
+	#[inline]
+	fn find_at(&self, haystack: &RawStr, start: usize) -> Option<Range<usize>> {
+		self.find_from(haystack.as_bytes(), start).map(|m| m.range())
+	}
+
+	#[inline]
+	fn rfind_at(&self, haystack: &RawStr, end: usize) -> Option<Range<usize>> {
+		self.rfind_until(haystack.as_bytes(), end).map(|m| m.range())
+	}
+}
+
+impl<'a> RawPattern for &'a RawMultiPattern {
+	type Searcher = &'a RawMultiPattern;
+	#[inline]
+	fn into_searcher(self) -> &'a RawMultiPattern {
+		self
+	}
+}
+
+// Tests {{{
+
+#[cfg(test)]
+fn naive_find(patterns: &[&str], kind: MatchKind, haystack: &str, start: usize) -> Option<MultiMatch> {
+	for s in start..=haystack.len() {
+		let mut best: Option<MultiMatch> = None;
+		for (i, p) in patterns.iter().enumerate() {
+			if haystack.as_bytes()[s..].starts_with(p.as_bytes()) {
+				let better = match best {
+					None => true,
+					Some(b) => kind == MatchKind::LeftmostLongest && p.len() > b.end - b.start,
+				};
+				if better {
+					best = Some(MultiMatch {
+						pattern: i,
+						start: s,
+						end: s + p.len(),
+					});
+				}
+			}
+		}
+		if best.is_some() {
+			return best;
+		}
+	}
+	None
+}
+
+#[test]
+fn test_multi_pattern() {
+	let sets: &[&[&str]] = &[
+		&["a"],
+		&["ab", "a", "b"],
+		&["a", "ab", "abc", "bc", "c"],
+		&["abcd", "bc", "b"],
+		&["samwise", "sam", "wise", "mwi"],
+		&["", "a"],
+		&["a", ""],
+		&["aa", "aa", "aaa"],
+		&["bcd", "abcdx", "cd"],
+	];
+	let haystacks = ["", "a", "abc", "abcd", "abcabcd", "samwise wise sam", "aaaaaaa", "xabcdxbcd"];
+	for &kind in &[MatchKind::LeftmostFirst, MatchKind::LeftmostLongest] {
+		for &patterns in sets {
+			let automaton = RawMultiPattern::builder().match_kind(kind).build(patterns);
+			for &haystack in &haystacks {
+				let raw = RawStr::from_str(haystack);
+				let mut expected = Vec::new();
+				let mut pos = 0;
+				while let Some(m) = naive_find(patterns, kind, haystack, pos) {
+					pos = if m.start == m.end { m.end + 1 } else { m.end };
+					expected.push(m);
+				}
+				let found: Vec<MultiMatch> = automaton.find_iter(raw).collect();
+				assert_eq!(found, expected, "{:?} {:?} in {:?}", kind, patterns, haystack);
+
+				for end in 0..=haystack.len() {
+					let last = (0..=end)
+						.rev()
+						.find_map(|s| naive_find(patterns, kind, &haystack[..end], s).filter(|m| m.start == s));
+					assert_eq!(automaton.rfind_until(raw.as_bytes(), end), last);
+				}
+			}
+		}
+	}
+}
+
+#[test]
+fn test_multi_pattern_case_insensitive() {
+	let patterns = RawMultiPattern::builder()
+		.ascii_case_insensitive(true)
+		.build(["Foo", "bär"]);
+	let haystack = RawStr::from_str("FOO foo bär BÄR BäR");
+	let found: Vec<(usize, usize)> = patterns.find_iter(haystack).map(|m| (m.pattern, m.start)).collect();
+	assert_eq!(found, [(0, 0), (0, 4), (1, 8), (1, 18)]);
+	assert_eq!(patterns.replace_all(haystack, &["x", "y"]), "x x y BÄR y");
+}
+
+// }}}
@@ -0,0 +1,462 @@
+use std::iter::FusedIterator;
+use std::ops::Range;
+use str::{memchr, memrchr, CharSearcher, RawFinder, RawStr, StrSearcher};
+use string::RawString;
+
+/// Something that can be searched for in a `RawStr`.
+///
+/// Used by [`RawStr::find`](struct.RawStr.html#method.find),
+/// [`RawStr::split`](struct.RawStr.html#method.split),
+/// [`RawStr::replace`](struct.RawStr.html#method.replace), and so on.
+///
+/// Implemented for single bytes (`u8`), `char`s (as their UTF-8 encoding),
+/// strings (`&str`, `&RawStr`, `&[u8]`, ...),
+/// [`RawFinder`](struct.RawFinder.html)s and
+/// [`RawMultiPattern`](struct.RawMultiPattern.html)s.
+pub trait RawPattern {
+	/// The searcher for this pattern.
+	type Searcher: RawSearcher;
+
+	/// Prepare to search for this pattern.
+	fn into_searcher(self) -> Self::Searcher;
+}
+
+/// A prepared [`RawPattern`](trait.RawPattern.html).
+pub trait RawSearcher {
+	/// Find the first match that starts at or after `start`.
+	///
+	/// Returns `None` if `start` is past the end of the haystack.
+	fn find_at(&self, haystack: &RawStr, start: usize) -> Option<Range<usize>>;
+
+	/// Find the match that starts last, within the first `end` bytes of the
+	/// haystack.
+	///
+	/// Returns `None` if `end` is past the end of the haystack.
+	fn rfind_at(&self, haystack: &RawStr, end: usize) -> Option<Range<usize>>;
+}
+
+// Implementations {{{
+
+impl RawSearcher for u8 {
+	#[inline]
+	fn find_at(&self, haystack: &RawStr, start: usize) -> Option<Range<usize>> {
+		let i = start + memchr(*self, haystack.as_bytes().get(start..)?)?;
+		Some(i..i + 1)
+	}
+
+	#[inline]
+	fn rfind_at(&self, haystack: &RawStr, end: usize) -> Option<Range<usize>> {
+		let i = memrchr(*self, haystack.as_bytes().get(..end)?)?;
+		Some(i..i + 1)
+	}
+}
+
+impl RawSearcher for RawFinder {
+	#[inline]
+	fn find_at(&self, haystack: &RawStr, start: usize) -> Option<Range<usize>> {
+		let i = self.find_from(haystack.as_bytes(), start)?;
+		Some(i..i + self.needle().len())
+	}
+
+	#[inline]
+	fn rfind_at(&self, haystack: &RawStr, end: usize) -> Option<Range<usize>> {
+		let i = self.rfind_until(haystack.as_bytes(), end)?;
+		Some(i..i + self.needle().len())
+	}
+}
+
+impl<'a> RawSearcher for StrSearcher<'a> {
+	#[inline]
+	fn find_at(&self, haystack: &RawStr, start: usize) -> Option<Range<usize>> {
+		let i = self.find_from(haystack.as_bytes(), start)?;
+		Some(i..i + self.needle_len())
+	}
+
+	#[inline]
+	fn rfind_at(&self, haystack: &RawStr, end: usize) -> Option<Range<usize>> {
+		let i = self.rfind_until(haystack.as_bytes(), end)?;
+		Some(i..i + self.needle_len())
+	}
+}
+
+impl RawSearcher for CharSearcher {
+	#[inline]
+	fn find_at(&self, haystack: &RawStr, start: usize) -> Option<Range<usize>> {
+		let i = self.find_from(haystack.as_bytes(), start)?;
+		Some(i..i + self.needle_len())
+	}
+
+	#[inline]
+	fn rfind_at(&self, haystack: &RawStr, end: usize) -> Option<Range<usize>> {
+		let i = self.rfind_until(haystack.as_bytes(), end)?;
+		Some(i..i + self.needle_len())
+	}
+}
+
+impl<S: RawSearcher + ?Sized> RawSearcher for &S {
+	#[inline]
+	fn find_at(&self, haystack: &RawStr, start: usize) -> Option<Range<usize>> {
+		(**self).find_at(haystack, start)
+	}
+
+	#[inline]
+	fn rfind_at(&self, haystack: &RawStr, end: usize) -> Option<Range<usize>> {
+		(**self).rfind_at(haystack, end)
+	}
+}
+
+impl RawPattern for u8 {
+	type Searcher = u8;
+	#[inline]
+	fn into_searcher(self) -> u8 {
+		self
+	}
+}
+
+impl RawPattern for char {
+	type Searcher = CharSearcher;
+	#[inline]
+	fn into_searcher(self) -> CharSearcher {
+		CharSearcher::new(self)
+	}
+}
+
+impl RawPattern for RawFinder {
+	type Searcher = RawFinder;
+	#[inline]
+	fn into_searcher(self) -> RawFinder {
+		self
+	}
+}
+
+impl<'a> RawPattern for &'a RawFinder {
+	type Searcher = &'a RawFinder;
+	#[inline]
+	fn into_searcher(self) -> &'a RawFinder {
+		self
+	}
+}
+
+macro_rules! impl_pattern_for_borrowed_string {
+	($t:ty) => {
+		impl<'a> RawPattern for $t {
+			type Searcher = StrSearcher<'a>;
+			#[inline]
+			fn into_searcher(self) -> StrSearcher<'a> {
+				StrSearcher::new(self.as_ref())
+			}
+		}
+	};
+}
+
+impl_pattern_for_borrowed_string!(&'a RawStr);
+impl_pattern_for_borrowed_string!(&'a str);
+impl_pattern_for_borrowed_string!(&'a [u8]);
+impl_pattern_for_borrowed_string!(&'a RawString);
+impl_pattern_for_borrowed_string!(&'a String);
+
+impl RawPattern for RawString {
+	type Searcher = RawFinder;
+	#[inline]
+	fn into_searcher(self) -> RawFinder {
+		RawFinder::from_owned(self)
+	}
+}
+
+impl RawPattern for String {
+	type Searcher = RawFinder;
+	#[inline]
+	fn into_searcher(self) -> RawFinder {
+		RawFinder::from_owned(RawString::from_string(self))
+	}
+}
+
+// }}}
+
+// Iterators {{{
+
+/// The non-overlapping matches of a searcher, from the start.
+#[derive(Clone, Debug)]
+struct MatchRanges<'a, S> {
+	haystack: &'a RawStr,
+	searcher: S,
+	position: usize,
+	last_end: Option<usize>,
+}
+
+impl<'a, S: RawSearcher> MatchRanges<'a, S> {
+	fn new(haystack: &'a RawStr, searcher: S) -> Self {
+		MatchRanges {
+			haystack,
+			searcher,
+			position: 0,
+			last_end: None,
+		}
+	}
+}
+
+impl<'a, S: RawSearcher> Iterator for MatchRanges<'a, S> {
+	type Item = Range<usize>;
+	fn next(&mut self) -> Option<Range<usize>> {
+		loop {
+			let found = self.searcher.find_at(self.haystack, self.position);
+			match found {
+				// Step over empty matches, so we don't find them again.
+				Some(ref m) if m.start == m.end => self.position = m.end + 1,
+				Some(ref m) => self.position = m.end,
+				None => self.position = self.haystack.len() + 1,
+			}
+			// An empty match right after the previous match doesn't count.
+			if let Some(ref m) = found {
+				if m.start == m.end && self.last_end == Some(m.end) {
+					continue;
+				}
+				self.last_end = Some(m.end);
+			}
+			return found;
+		}
+	}
+}
+
+/// The non-overlapping matches of a searcher, from the end.
+#[derive(Clone, Debug)]
+struct RMatchRanges<'a, S> {
+	haystack: &'a RawStr,
+	searcher: S,
+	end: Option<usize>,
+	last_start: Option<usize>,
+}
+
+impl<'a, S: RawSearcher> RMatchRanges<'a, S> {
+	fn new(haystack: &'a RawStr, searcher: S) -> Self {
+		RMatchRanges {
+			haystack,
+			searcher,
+			end: Some(haystack.len()),
+			last_start: None,
+		}
+	}
+}
+
+impl<'a, S: RawSearcher> Iterator for RMatchRanges<'a, S> {
+	type Item = Range<usize>;
+	fn next(&mut self) -> Option<Range<usize>> {
+		loop {
+			let found = self.searcher.rfind_at(self.haystack, self.end?);
+			self.end = match found {
+				Some(ref m) if m.start == m.end => m.start.checked_sub(1),
+				Some(ref m) => Some(m.start),
+				None => None,
+			};
+			// An empty match right before the previous match doesn't count.
+			if let Some(ref m) = found {
+				if m.start == m.end && self.last_start == Some(m.start) {
+					continue;
+				}
+				self.last_start = Some(m.start);
+			}
+			return found;
+		}
+	}
+}
+
+/// Iterator over the non-overlapping matches of a pattern.
+///
+/// See [`RawStr::matches`](struct.RawStr.html#method.matches).
+pub struct Matches<'a, P: RawPattern> {
+	inner: MatchRanges<'a, P::Searcher>,
+}
+
+impl<'a, P: RawPattern> Matches<'a, P> {
+	pub(super) fn new(haystack: &'a RawStr, pattern: P) -> Self {
+		Matches {
+			inner: MatchRanges::new(haystack, pattern.into_searcher()),
+		}
+	}
+}
+
+impl<'a, P: RawPattern> Iterator for Matches<'a, P> {
+	type Item = &'a RawStr;
+	#[inline]
+	fn next(&mut self) -> Option<&'a RawStr> {
+		let m = self.inner.next()?;
+		Some(&self.inner.haystack[m])
+	}
+}
+
+impl<'a, P: RawPattern> FusedIterator for Matches<'a, P> {}
+
+/// Iterator over the non-overlapping matches of a pattern, and their indices.
+///
+/// See [`RawStr::match_indices`](struct.RawStr.html#method.match_indices).
+pub struct MatchIndices<'a, P: RawPattern> {
+	inner: MatchRanges<'a, P::Searcher>,
+}
+
+impl<'a, P: RawPattern> MatchIndices<'a, P> {
+	pub(super) fn new(haystack: &'a RawStr, pattern: P) -> Self {
+		MatchIndices {
+			inner: MatchRanges::new(haystack, pattern.into_searcher()),
+		}
+	}
+}
+
+impl<'a, P: RawPattern> Iterator for MatchIndices<'a, P> {
+	type Item = (usize, &'a RawStr);
+	#[inline]
+	fn next(&mut self) -> Option<(usize, &'a RawStr)> {
+		let m = self.inner.next()?;
+		Some((m.start, &self.inner.haystack[m]))
+	}
+}
+
+impl<'a, P: RawPattern> FusedIterator for MatchIndices<'a, P> {}
+
+/// Iterator over the parts of a string, separated by a pattern.
+///
+/// See [`RawStr::split`](struct.RawStr.html#method.split) and
+/// [`RawStr::splitn`](struct.RawStr.html#method.splitn).
+pub struct Split<'a, P: RawPattern> {
+	matches: MatchRanges<'a, P::Searcher>,
+	start: usize,
+	remaining: usize,
+}
+
+impl<'a, P: RawPattern> Split<'a, P> {
+	pub(super) fn new(haystack: &'a RawStr, pattern: P, limit: usize) -> Self {
+		Split {
+			matches: MatchRanges::new(haystack, pattern.into_searcher()),
+			start: 0,
+			remaining: limit,
+		}
+	}
+}
+
+impl<'a, P: RawPattern> Iterator for Split<'a, P> {
+	type Item = &'a RawStr;
+	fn next(&mut self) -> Option<&'a RawStr> {
+		let haystack = self.matches.haystack;
+		match self.remaining {
+			0 => return None,
+			1 => {}
+			_ => {
+				if let Some(m) = self.matches.next() {
+					self.remaining -= 1;
+					let part = &haystack[self.start..m.start];
+					self.start = m.end;
+					return Some(part);
+				}
+			}
+		}
+		self.remaining = 0;
+		Some(&haystack[self.start..])
+	}
+}
+
+impl<'a, P: RawPattern> FusedIterator for Split<'a, P> {}
+
+/// Iterator over the parts of a string, separated by a pattern, from the end.
+///
+/// See [`RawStr::rsplit`](struct.RawStr.html#method.rsplit) and
+/// [`RawStr::rsplitn`](struct.RawStr.html#method.rsplitn).
+pub struct RSplit<'a, P: RawPattern> {
+	matches: RMatchRanges<'a, P::Searcher>,
+	end: usize,
+	remaining: usize,
+}
+
+impl<'a, P: RawPattern> RSplit<'a, P> {
+	pub(super) fn new(haystack: &'a RawStr, pattern: P, limit: usize) -> Self {
+		RSplit {
+			matches: RMatchRanges::new(haystack, pattern.into_searcher()),
+			end: haystack.len(),
+			remaining: limit,
+		}
+	}
+}
+
+impl<'a, P: RawPattern> Iterator for RSplit<'a, P> {
+	type Item = &'a RawStr;
+	fn next(&mut self) -> Option<&'a RawStr> {
+		let haystack = self.matches.haystack;
+		match self.remaining {
+			0 => return None,
+			1 => {}
+			_ => {
+				if let Some(m) = self.matches.next() {
+					self.remaining -= 1;
+					let part = &haystack[m.end..self.end];
+					self.end = m.start;
+					return Some(part);
+				}
+			}
+		}
+		self.remaining = 0;
+		Some(&haystack[..self.end])
+	}
+}
+
+impl<'a, P: RawPattern> FusedIterator for RSplit<'a, P> {}
+
+// }}}
+
+/// Replace at most `limit` matches of `pattern` in `haystack`.
+pub(super) fn replace<P: RawPattern>(
+	haystack: &RawStr,
+	pattern: P,
+	to: &RawStr,
+	limit: usize,
+) -> RawString {
+	let mut result = RawString::with_capacity(haystack.len());
+	let mut last_end = 0;
+	for m in MatchRanges::new(haystack, pattern.into_searcher()).take(limit) {
+		result.push_str(&haystack[last_end..m.start]);
+		result.push_str(to);
+		last_end = m.end;
+	}
+	result.push_str(&haystack[last_end..]);
+	result
+}
+
+// Tests {{{
+
+#[test]
+fn test_split() {
+	let s = RawStr::from_bytes(b"a,b\xFF,,c");
+	let parts: Vec<&RawStr> = s.split(b',').collect();
+	assert_eq!(parts, [&b"a"[..], &b"b\xFF"[..], &b""[..], &b"c"[..]]);
+	assert_eq!(s.rsplit(",,").collect::<Vec<_>>(), [&b"c"[..], &b"a,b\xFF"[..]]);
+	assert_eq!(s.splitn(2, ',').collect::<Vec<_>>(), [&b"a"[..], &b"b\xFF,,c"[..]]);
+	assert_eq!(s.rsplitn(2, ',').collect::<Vec<_>>(), [&b"c"[..], &b"a,b\xFF,"[..]]);
+	assert_eq!(s.splitn(0, ',').count(), 0);
+
+	let s = RawStr::from_str("ab");
+	assert_eq!(s.split("").collect::<Vec<_>>(), ["", "a", "b", ""]);
+	assert_eq!(s.rsplit("").collect::<Vec<_>>(), ["", "b", "a", ""]);
+	assert_eq!(RawStr::from_str("").split(',').collect::<Vec<_>>(), [""]);
+}
+
+#[test]
+fn test_matches_and_replace() {
+	let s = RawStr::from_str("one two one three");
+	assert_eq!(s.matches("one").count(), 2);
+	assert_eq!(
+		s.match_indices('t').map(|(i, _)| i).collect::<Vec<_>>(),
+		[4, 12]
+	);
+	assert_eq!(s.replace("one", "1"), "1 two 1 three");
+	assert_eq!(s.replacen("one", "1", 1), "1 two one three");
+	assert_eq!(s.replace(b' ', ""), "onetwoonethree");
+	assert_eq!(RawStr::from_str("ab").replace("", "-"), "-a-b-");
+	assert_eq!(s.find("two"), Some(4));
+	assert_eq!(s.rfind('o'), Some(8));
+	assert!(s.contains(RawFinder::new("three")));
+	assert!(s.contains(String::from("two")));
+
+	let s = RawStr::from_bytes(b"a\xE2\x82\xAC\xFF\xE2\x82\xAC");
+	assert_eq!(s.find('€'), Some(1));
+	assert_eq!(s.rfind('€'), Some(5));
+	assert_eq!(s.split('€').collect::<Vec<_>>(), [&b"a"[..], b"\xFF", b""]);
+}
+
+// }}}
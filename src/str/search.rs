@@ -0,0 +1,705 @@
+use std::cmp::{max, min};
+use std::convert::TryInto;
+use std::iter::FusedIterator;
+use std::mem::size_of;
+use str::RawStr;
+use string::RawString;
+
+// Byte search {{{
+
+const WORD: usize = size_of::<usize>();
+const LO: usize = usize::MAX / 255;
+const HI: usize = LO << 7;
+
+/// A word with every byte set to `b`.
+#[inline]
+fn splat(b: u8) -> usize {
+	LO * usize::from(b)
+}
+
+/// Whether any of the bytes in `x` is zero.
+#[inline]
+fn has_zero(x: usize) -> bool {
+	x.wrapping_sub(LO) & !x & HI != 0
+}
+
+#[inline]
+fn word(chunk: &[u8]) -> usize {
+	usize::from_ne_bytes(chunk.try_into().unwrap())
+}
+
+/// Find the first byte for which `matches` is true, a word at a time.
+///
+/// `any_in_word` must return true for a word if `matches` is true for any of
+/// its bytes, and may only return true if that is the case.
+#[inline(always)]
+fn forward<M, W>(haystack: &[u8], matches: M, any_in_word: W) -> Option<usize>
+where
+	M: Fn(u8) -> bool,
+	W: Fn(usize) -> bool,
+{
+	let mut chunks = haystack.chunks_exact(2 * WORD);
+	let mut offset = 0;
+	for chunk in &mut chunks {
+		let (a, b) = chunk.split_at(WORD);
+		if any_in_word(word(a)) || any_in_word(word(b)) {
+			break;
+		}
+		offset += 2 * WORD;
+	}
+	haystack[offset..].iter().position(|&b| matches(b)).map(|i| offset + i)
+}
+
+/// Find the last byte for which `matches` is true, a word at a time.
+///
+/// See [`forward`].
+#[inline(always)]
+fn backward<M, W>(haystack: &[u8], matches: M, any_in_word: W) -> Option<usize>
+where
+	M: Fn(u8) -> bool,
+	W: Fn(usize) -> bool,
+{
+	let mut chunks = haystack.rchunks_exact(2 * WORD);
+	let mut end = haystack.len();
+	for chunk in &mut chunks {
+		let (a, b) = chunk.split_at(WORD);
+		if any_in_word(word(a)) || any_in_word(word(b)) {
+			break;
+		}
+		end -= 2 * WORD;
+	}
+	haystack[..end].iter().rposition(|&b| matches(b))
+}
+
+/// Find the first occurrence of `x`.
+pub(crate) fn memchr(x: u8, haystack: &[u8]) -> Option<usize> {
+	let vx = splat(x);
+	forward(haystack, |b| b == x, |w| has_zero(w ^ vx))
+}
+
+/// Find the last occurrence of `x`.
+pub(crate) fn memrchr(x: u8, haystack: &[u8]) -> Option<usize> {
+	let vx = splat(x);
+	backward(haystack, |b| b == x, |w| has_zero(w ^ vx))
+}
+
+/// Find the first occurrence of `x` or `y`.
+pub(crate) fn memchr2(x: u8, y: u8, haystack: &[u8]) -> Option<usize> {
+	let (vx, vy) = (splat(x), splat(y));
+	forward(
+		haystack,
+		|b| b == x || b == y,
+		|w| has_zero(w ^ vx) || has_zero(w ^ vy),
+	)
+}
+
+/// Find the last occurrence of `x` or `y`.
+pub(crate) fn memrchr2(x: u8, y: u8, haystack: &[u8]) -> Option<usize> {
+	let (vx, vy) = (splat(x), splat(y));
+	backward(
+		haystack,
+		|b| b == x || b == y,
+		|w| has_zero(w ^ vx) || has_zero(w ^ vy),
+	)
+}
+
+/// Find the first occurrence of `x`, `y` or `z`.
+pub(crate) fn memchr3(x: u8, y: u8, z: u8, haystack: &[u8]) -> Option<usize> {
+	let (vx, vy, vz) = (splat(x), splat(y), splat(z));
+	forward(
+		haystack,
+		|b| b == x || b == y || b == z,
+		|w| has_zero(w ^ vx) || has_zero(w ^ vy) || has_zero(w ^ vz),
+	)
+}
+
+/// Find the last occurrence of `x`, `y` or `z`.
+pub(crate) fn memrchr3(x: u8, y: u8, z: u8, haystack: &[u8]) -> Option<usize> {
+	let (vx, vy, vz) = (splat(x), splat(y), splat(z));
+	backward(
+		haystack,
+		|b| b == x || b == y || b == z,
+		|w| has_zero(w ^ vx) || has_zero(w ^ vy) || has_zero(w ^ vz),
+	)
+}
+
+// }}}
+
+// Substring search {{{
+
+/// A precompiled substring searcher.
+///
+/// Searching for the same needle many times is faster with a `RawFinder` than
+/// with [`RawStr::find`](struct.RawStr.html#method.find), as the needle is
+/// only analyzed once.
+///
+/// It uses the Two-Way algorithm, which needs only constant extra space, and
+/// runs in linear time in the worst case.
+///
+/// # Example
+///
+/// ```
+/// # use raw_string::{RawFinder, RawStr};
+/// let finder = RawFinder::new("ab");
+/// let haystack = RawStr::from_bytes(b"xxab\xFFab");
+/// assert_eq!(finder.find(haystack), Some(2));
+/// assert_eq!(finder.rfind(haystack), Some(5));
+/// assert_eq!(finder.find_iter(haystack).collect::<Vec<_>>(), [2, 5]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RawFinder {
+	needle: RawString,
+	two_way: TwoWay,
+}
+
+impl RawFinder {
+	/// Analyze the needle.
+	pub fn new<T: AsRef<RawStr>>(needle: T) -> RawFinder {
+		Self::from_owned(needle.as_ref().to_owned())
+	}
+
+	/// Analyze the needle, without copying it.
+	pub(crate) fn from_owned(needle: RawString) -> RawFinder {
+		let two_way = TwoWay::new(needle.as_bytes());
+		RawFinder { needle, two_way }
+	}
+
+	/// The needle this finder searches for.
+	#[inline]
+	pub fn needle(&self) -> &RawStr {
+		&self.needle
+	}
+
+	/// Find the index of the first occurrence of the needle.
+	#[inline]
+	pub fn find<T: AsRef<RawStr>>(&self, haystack: T) -> Option<usize> {
+		self.find_from(haystack.as_ref().as_bytes(), 0)
+	}
+
+	/// Find the index of the last occurrence of the needle.
+	#[inline]
+	pub fn rfind<T: AsRef<RawStr>>(&self, haystack: T) -> Option<usize> {
+		let haystack = haystack.as_ref().as_bytes();
+		self.rfind_until(haystack, haystack.len())
+	}
+
+	/// Iterate over the indices of all non-overlapping occurrences of the
+	/// needle, from the start.
+	#[inline]
+	pub fn find_iter<'f, 'h>(&'f self, haystack: &'h RawStr) -> RawFinderIter<'f, 'h> {
+		RawFinderIter {
+			finder: self,
+			haystack: haystack.as_bytes(),
+			position: 0,
+		}
+	}
+
+	/// Find the first occurrence that starts at or after `start`.
+	#[inline]
+	pub(crate) fn find_from(&self, haystack: &[u8], start: usize) -> Option<usize> {
+		find_from(self.needle.as_bytes(), &self.two_way, haystack, start)
+	}
+
+	/// Find the last occurrence that ends at or before `end`.
+	#[inline]
+	pub(crate) fn rfind_until(&self, haystack: &[u8], end: usize) -> Option<usize> {
+		rfind_until(self.needle.as_bytes(), &self.two_way, haystack, end)
+	}
+}
+
+/// Find the first occurrence of `needle` that starts at or after `start`.
+fn find_from(needle: &[u8], two_way: &TwoWay, haystack: &[u8], start: usize) -> Option<usize> {
+	match needle.len() {
+		0 if start <= haystack.len() => Some(start),
+		0 => None,
+		1 => memchr(needle[0], haystack.get(start..)?).map(|i| start + i),
+		_ => two_way.find(needle, haystack, start),
+	}
+}
+
+/// Find the last occurrence of `needle` that ends at or before `end`.
+fn rfind_until(needle: &[u8], two_way: &TwoWay, haystack: &[u8], end: usize) -> Option<usize> {
+	match needle.len() {
+		0 if end <= haystack.len() => Some(end),
+		0 => None,
+		1 => memrchr(needle[0], haystack.get(..end)?),
+		_ => two_way.rfind(needle, haystack, end),
+	}
+}
+
+/// Find the first occurrence of `needle`, ignoring ASCII case.
+pub(crate) fn find_ignore_ascii_case(needle: &[u8], haystack: &[u8]) -> Option<usize> {
+	match needle.len() {
+		0 => Some(0),
+		1 => {
+			let b = needle[0];
+			memchr2(b.to_ascii_lowercase(), b.to_ascii_uppercase(), haystack)
+		}
+		_ => {
+			let needle = needle.to_ascii_lowercase();
+			TwoWay::new(&needle).find_by::<AsciiCase>(&needle, haystack, 0)
+		}
+	}
+}
+
+/// The searcher for string patterns, which borrows the needle.
+///
+/// Unlike a [`RawFinder`](struct.RawFinder.html), this doesn't copy the
+/// needle. See [`RawPattern`](trait.RawPattern.html).
+#[derive(Clone, Debug)]
+pub struct StrSearcher<'a> {
+	needle: &'a [u8],
+	two_way: TwoWay,
+}
+
+impl<'a> StrSearcher<'a> {
+	pub(crate) fn new(needle: &'a [u8]) -> Self {
+		StrSearcher {
+			needle,
+			two_way: TwoWay::new(needle),
+		}
+	}
+
+	/// Find the first occurrence that starts at or after `start`.
+	#[inline]
+	pub(crate) fn find_from(&self, haystack: &[u8], start: usize) -> Option<usize> {
+		find_from(self.needle, &self.two_way, haystack, start)
+	}
+
+	/// Find the last occurrence that ends at or before `end`.
+	#[inline]
+	pub(crate) fn rfind_until(&self, haystack: &[u8], end: usize) -> Option<usize> {
+		rfind_until(self.needle, &self.two_way, haystack, end)
+	}
+
+	#[inline]
+	pub(crate) fn needle_len(&self) -> usize {
+		self.needle.len()
+	}
+}
+
+/// The searcher for `char` patterns, which searches for their UTF-8
+/// encoding.
+///
+/// See [`RawPattern`](trait.RawPattern.html).
+#[derive(Clone, Debug)]
+pub struct CharSearcher {
+	utf8: [u8; 4],
+	len: usize,
+	two_way: TwoWay,
+}
+
+impl CharSearcher {
+	pub(crate) fn new(c: char) -> Self {
+		let mut utf8 = [0; 4];
+		let len = c.encode_utf8(&mut utf8).len();
+		CharSearcher {
+			utf8,
+			len,
+			two_way: TwoWay::new(&utf8[..len]),
+		}
+	}
+
+	/// Find the first occurrence that starts at or after `start`.
+	#[inline]
+	pub(crate) fn find_from(&self, haystack: &[u8], start: usize) -> Option<usize> {
+		find_from(&self.utf8[..self.len], &self.two_way, haystack, start)
+	}
+
+	/// Find the last occurrence that ends at or before `end`.
+	#[inline]
+	pub(crate) fn rfind_until(&self, haystack: &[u8], end: usize) -> Option<usize> {
+		rfind_until(&self.utf8[..self.len], &self.two_way, haystack, end)
+	}
+
+	#[inline]
+	pub(crate) fn needle_len(&self) -> usize {
+		self.len
+	}
+}
+
+/// Iterator over the matches of a [`RawFinder`](struct.RawFinder.html).
+///
+/// See [`RawFinder::find_iter`](struct.RawFinder.html#method.find_iter).
+#[derive(Clone, Debug)]
+pub struct RawFinderIter<'f, 'h> {
+	finder: &'f RawFinder,
+	haystack: &'h [u8],
+	position: usize,
+}
+
+impl<'f, 'h> Iterator for RawFinderIter<'f, 'h> {
+	type Item = usize;
+	fn next(&mut self) -> Option<usize> {
+		let found = self.finder.find_from(self.haystack, self.position);
+		match found {
+			// Step over empty matches, so we don't find them again.
+			Some(i) => self.position = i + max(self.finder.needle.len(), 1),
+			None => self.position = self.haystack.len() + 1,
+		}
+		found
+	}
+}
+
+impl<'f, 'h> FusedIterator for RawFinderIter<'f, 'h> {}
+
+/// How `TwoWay` compares the bytes of the haystack to those of the needle.
+trait Fold {
+	/// Map a byte of the haystack to the byte it matches in the needle.
+	fn fold(b: u8) -> u8;
+	/// Find the first byte of `haystack` that folds to `x`.
+	fn memchr(x: u8, haystack: &[u8]) -> Option<usize>;
+}
+
+/// Compare bytes exactly.
+enum Exact {}
+
+impl Fold for Exact {
+	#[inline]
+	fn fold(b: u8) -> u8 {
+		b
+	}
+	#[inline]
+	fn memchr(x: u8, haystack: &[u8]) -> Option<usize> {
+		memchr(x, haystack)
+	}
+}
+
+/// Compare bytes ignoring ASCII case, against a needle in ASCII lowercase.
+enum AsciiCase {}
+
+impl Fold for AsciiCase {
+	#[inline]
+	fn fold(b: u8) -> u8 {
+		b.to_ascii_lowercase()
+	}
+	#[inline]
+	fn memchr(x: u8, haystack: &[u8]) -> Option<usize> {
+		memchr2(x, x.to_ascii_uppercase(), haystack)
+	}
+}
+
+/// The parameters of the Two-Way algorithm for a needle.
+///
+/// The needle is split at a critical position into a left and right part.
+/// The right part is matched first, left to right, and then the left part,
+/// right to left. For reverse searching, the same happens in the mirrored way,
+/// around `crit_pos_back`.
+///
+/// See Crochemore and Perrin, "Two-way string-matching", J. ACM 38(3), 1991.
+#[derive(Clone, Debug)]
+struct TwoWay {
+	crit_pos: usize,
+	crit_pos_back: usize,
+	period: usize,
+	/// A bitmask of `b % 64` for all bytes `b` in the needle, to quickly skip
+	/// over windows that can't match.
+	byteset: u64,
+	/// The position of the byte in the needle that is probably the least
+	/// common in the haystack, to skip ahead to with `memchr`.
+	rare_pos: usize,
+	/// Whether the needle does not have a period shorter than half its length,
+	/// in which case no memory of earlier partial matches is used.
+	long_period: bool,
+}
+
+impl TwoWay {
+	fn new(needle: &[u8]) -> TwoWay {
+		let byteset = needle.iter().fold(0, |set, &b| set | 1 << (b & 63));
+		let rare_pos = (0..needle.len()).rev().max_by_key(|&i| rarity(needle[i])).unwrap_or(0);
+		if needle.len() < 2 {
+			// Not used: these are searched for with memchr.
+			return TwoWay {
+				crit_pos: 0,
+				crit_pos_back: 0,
+				period: 1,
+				byteset,
+				rare_pos,
+				long_period: true,
+			};
+		}
+		let (crit_pos_false, period_false) = maximal_suffix(needle, false);
+		let (crit_pos_true, period_true) = maximal_suffix(needle, true);
+		let (crit_pos, period) = if crit_pos_false > crit_pos_true {
+			(crit_pos_false, period_false)
+		} else {
+			(crit_pos_true, period_true)
+		};
+		if needle[..crit_pos] == needle[period..period + crit_pos] {
+			let crit_pos_back = needle.len()
+				- max(
+					reverse_maximal_suffix(needle, period, false),
+					reverse_maximal_suffix(needle, period, true),
+				);
+			TwoWay {
+				crit_pos,
+				crit_pos_back,
+				period,
+				byteset,
+				rare_pos,
+				long_period: false,
+			}
+		} else {
+			TwoWay {
+				crit_pos,
+				crit_pos_back: crit_pos,
+				period: max(crit_pos, needle.len() - crit_pos) + 1,
+				byteset,
+				rare_pos,
+				long_period: true,
+			}
+		}
+	}
+
+	#[inline]
+	fn byteset_contains(&self, b: u8) -> bool {
+		(self.byteset >> (b & 63)) & 1 != 0
+	}
+
+	#[inline]
+	fn find(&self, needle: &[u8], haystack: &[u8], start: usize) -> Option<usize> {
+		self.find_by::<Exact>(needle, haystack, start)
+	}
+
+	fn find_by<F: Fold>(&self, needle: &[u8], haystack: &[u8], start: usize) -> Option<usize> {
+		let n = needle.len();
+		let rare = needle[self.rare_pos];
+		let mut position = start;
+		// The length of the prefix of the needle that is known to match at
+		// `position`.
+		let mut memory = 0;
+		'search: loop {
+			let last = F::fold(*haystack.get(position + n - 1)?);
+			if memory == 0 && F::fold(haystack[position + self.rare_pos]) != rare {
+				// Skip ahead to the next occurrence of the rare byte.
+				position += F::memchr(rare, &haystack[position + self.rare_pos..])?;
+				continue;
+			}
+			if !self.byteset_contains(last) {
+				position += n;
+				memory = 0;
+				continue;
+			}
+			for i in max(self.crit_pos, memory)..n {
+				if needle[i] != F::fold(haystack[position + i]) {
+					position += i - self.crit_pos + 1;
+					memory = 0;
+					continue 'search;
+				}
+			}
+			for i in (memory..self.crit_pos).rev() {
+				if needle[i] != F::fold(haystack[position + i]) {
+					position += self.period;
+					if !self.long_period {
+						memory = n - self.period;
+					}
+					continue 'search;
+				}
+			}
+			return Some(position);
+		}
+	}
+
+	fn rfind(&self, needle: &[u8], haystack: &[u8], end: usize) -> Option<usize> {
+		let n = needle.len();
+		let rare = needle[self.rare_pos];
+		let mut end = min(end, haystack.len());
+		// The position in the needle from which the suffix is known to match
+		// before `end`.
+		let mut memory = n;
+		'search: loop {
+			let first = *haystack.get(end.checked_sub(n)?)?;
+			if memory == n && haystack[end - n + self.rare_pos] != rare {
+				// Skip back to the previous occurrence of the rare byte.
+				let i = memrchr(rare, &haystack[..end - n + self.rare_pos])?;
+				end = (i + n).checked_sub(self.rare_pos)?;
+				continue;
+			}
+			if !self.byteset_contains(first) {
+				end -= n;
+				memory = n;
+				continue;
+			}
+			let start = end - n;
+			for i in (0..min(self.crit_pos_back, memory)).rev() {
+				if needle[i] != haystack[start + i] {
+					end -= self.crit_pos_back - i;
+					memory = n;
+					continue 'search;
+				}
+			}
+			let matched_end = memory;
+			for i in self.crit_pos_back..matched_end {
+				if needle[i] != haystack[start + i] {
+					end -= self.period;
+					if !self.long_period {
+						memory = self.period;
+					}
+					continue 'search;
+				}
+			}
+			return Some(start);
+		}
+	}
+}
+
+/// A guess of how rare a byte is in typical text. Higher is rarer.
+fn rarity(b: u8) -> u8 {
+	match b {
+		b' ' => 0,
+		b'e' | b't' | b'a' | b'o' | b'i' | b'n' | b's' | b'h' | b'r' => 1,
+		b'a'..=b'z' => 2,
+		b'\n' | b'.' | b',' | b'0'..=b'9' | b'A'..=b'Z' | 0x80..=0xBF => 3,
+		_ => 4,
+	}
+}
+
+/// Compute the maximal suffix of `arr`, for the lexical order (or reverse
+/// lexical order if `order_greater` is true).
+///
+/// Returns the start of the suffix, and its period.
+fn maximal_suffix(arr: &[u8], order_greater: bool) -> (usize, usize) {
+	let mut left = 0;
+	let mut right = 1;
+	let mut offset = 0;
+	let mut period = 1;
+	while let Some(&a) = arr.get(right + offset) {
+		let b = arr[left + offset];
+		if (a < b && !order_greater) || (a > b && order_greater) {
+			right += offset + 1;
+			offset = 0;
+			period = right - left;
+		} else if a == b {
+			if offset + 1 == period {
+				right += offset + 1;
+				offset = 0;
+			} else {
+				offset += 1;
+			}
+		} else {
+			left = right;
+			right += 1;
+			offset = 0;
+			period = 1;
+		}
+	}
+	(left, period)
+}
+
+/// Like `maximal_suffix`, but for the reversed `arr`, stopping when the
+/// `known_period` of the whole `arr` is reached.
+///
+/// Returns the length of the suffix of the reversed `arr`.
+fn reverse_maximal_suffix(arr: &[u8], known_period: usize, order_greater: bool) -> usize {
+	let n = arr.len();
+	let mut left = 0;
+	let mut right = 1;
+	let mut offset = 0;
+	let mut period = 1;
+	while right + offset < n {
+		let a = arr[n - (1 + right + offset)];
+		let b = arr[n - (1 + left + offset)];
+		if (a < b && !order_greater) || (a > b && order_greater) {
+			right += offset + 1;
+			offset = 0;
+			period = right - left;
+		} else if a == b {
+			if offset + 1 == period {
+				right += offset + 1;
+				offset = 0;
+			} else {
+				offset += 1;
+			}
+		} else {
+			left = right;
+			right += 1;
+			offset = 0;
+			period = 1;
+		}
+		if period == known_period {
+			break;
+		}
+	}
+	left
+}
+
+// }}}
+
+// Tests {{{
+
+#[cfg(test)]
+fn pseudo_random_bytes(seed: u32, len: usize, alphabet: &[u8]) -> Vec<u8> {
+	let mut state = seed;
+	(0..len)
+		.map(|_| {
+			state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+			alphabet[(state >> 16) as usize % alphabet.len()]
+		})
+		.collect()
+}
+
+#[test]
+fn test_memchr() {
+	let alphabet = b"abc\0\x80\xFF";
+	for len in 0..70 {
+		let haystack = pseudo_random_bytes(len as u32, len, alphabet);
+		for &x in alphabet.iter().chain(b"z") {
+			let naive = haystack.iter().position(|&b| b == x);
+			assert_eq!(memchr(x, &haystack), naive);
+			let naive = haystack.iter().rposition(|&b| b == x);
+			assert_eq!(memrchr(x, &haystack), naive);
+
+			let naive = haystack.iter().position(|&b| b == x || b == b'z');
+			assert_eq!(memchr2(x, b'z', &haystack), naive);
+			let naive = haystack.iter().rposition(|&b| b == x || b == b'z');
+			assert_eq!(memrchr2(x, b'z', &haystack), naive);
+
+			let naive = haystack.iter().position(|&b| b == x || b == b'a' || b == 0x80);
+			assert_eq!(memchr3(x, b'a', 0x80, &haystack), naive);
+			let naive = haystack.iter().rposition(|&b| b == x || b == b'a' || b == 0x80);
+			assert_eq!(memrchr3(x, b'a', 0x80, &haystack), naive);
+		}
+	}
+}
+
+#[test]
+fn test_finder() {
+	let needles: &[&[u8]] = &[
+		b"", b"a", b"ab", b"aa", b"aab", b"aba", b"abab", b"abba", b"baaab", b"aaaa", b"abcab",
+		b"bcabcab", b"abacaba", b"aaabaaab", b"cbcbbabcba",
+	];
+	for seed in 0..200 {
+		let haystack = pseudo_random_bytes(seed, seed as usize % 50, b"aabc");
+		for &needle in needles {
+			let finder = RawFinder::new(RawStr::from_bytes(needle));
+			let naive: Vec<usize> = (0..(haystack.len() + 1).saturating_sub(needle.len()))
+				.filter(|&i| haystack[i..].starts_with(needle))
+				.collect();
+			let haystack = RawStr::from_bytes(&haystack);
+			assert_eq!(finder.find(haystack), naive.first().cloned());
+			assert_eq!(finder.rfind(haystack), naive.last().cloned());
+			let mut non_overlapping = Vec::new();
+			for &i in &naive {
+				if non_overlapping.last().map_or(true, |&last| i >= last + needle.len().max(1)) {
+					non_overlapping.push(i);
+				}
+			}
+			assert_eq!(finder.find_iter(haystack).collect::<Vec<_>>(), non_overlapping);
+		}
+	}
+}
+
+#[test]
+fn test_find_ignore_ascii_case() {
+	let needles: &[&[u8]] = &[b"", b"A", b"ab", b"aA", b"aBa", b"AbaB", b"baAAb", b"abCab", b"aaaBaaAb"];
+	for seed in 0..200 {
+		let haystack = pseudo_random_bytes(seed, seed as usize % 50, b"aAbBc@");
+		for &needle in needles {
+			let naive = (0..(haystack.len() + 1).saturating_sub(needle.len()))
+				.find(|&i| haystack[i..i + needle.len()].eq_ignore_ascii_case(needle));
+			assert_eq!(find_ignore_ascii_case(needle, &haystack), naive);
+		}
+	}
+}
+
+// }}}
@@ -1,11 +1,18 @@
+use std::fmt::{Display, Formatter};
 use std::iter::FusedIterator;
 use std::str::{from_utf8, from_utf8_unchecked};
+use str::RawStr;
 
 /// An iterator over chunks of valid UTF-8 in a RawStr.
 ///
 /// See [`RawStr::utf8_chunks`](struct.RawStr.html#method.utf8_chunks).
+///
+/// It can also be iterated in reverse, which produces exactly the same chunks
+/// in the opposite order.
 pub struct Utf8ChunksIter<'a> {
-	pub(super) bytes: &'a [u8],
+	bytes: &'a [u8],
+	start: usize,
+	end: usize,
 }
 
 /// A chunk of valid UTF-8, possibly followed by a broken character encoding.
@@ -23,34 +30,169 @@ pub struct Utf8Chunk<'a> {
 	pub broken: &'a [u8],
 }
 
+impl<'a> Utf8Chunk<'a> {
+	/// The byte offset of this chunk in `s`, the `RawStr` it came from.
+	///
+	/// This is where `valid` starts. `broken` starts at
+	/// `offset_in(s) + valid.len()`.
+	///
+	/// # Panics
+	///
+	/// Panics if the chunk is not part of `s`.
+	pub fn offset_in(&self, s: &RawStr) -> usize {
+		let start = s.as_bytes().as_ptr() as usize;
+		let chunk = self.valid.as_ptr() as usize;
+		let len = self.valid.len() + self.broken.len();
+		assert!(
+			chunk >= start && chunk + len <= start + s.len(),
+			"chunk is not part of the string"
+		);
+		chunk - start
+	}
+
+	/// Why `broken` is not valid UTF-8, given `s`, the `RawStr` this chunk
+	/// came from.
+	///
+	/// `None` if (and only if) `broken` is empty.
+	///
+	/// # Panics
+	///
+	/// Panics if the chunk is not part of `s`.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::{RawStr, Utf8ErrorKind};
+	/// let s = RawStr::from_bytes(b"ab\xC0\xAFc");
+	/// let chunk = s.utf8_chunks().next().unwrap();
+	/// assert_eq!(chunk.offset_in(s) + chunk.valid.len(), 2);
+	/// assert_eq!(chunk.error_in(s), Some(Utf8ErrorKind::OverlongEncoding));
+	/// ```
+	pub fn error_in(&self, s: &RawStr) -> Option<Utf8ErrorKind> {
+		if self.broken.is_empty() {
+			return None;
+		}
+		let broken_end = self.offset_in(s) + self.valid.len() + self.broken.len();
+		let next = s.as_bytes().get(broken_end).cloned();
+		Some(Utf8ErrorKind::classify(self.broken, next))
+	}
+}
+
+/// The reason a byte sequence is not valid UTF-8.
+///
+/// See [`Utf8Chunk::error_in`](struct.Utf8Chunk.html#method.error_in).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Utf8ErrorKind {
+	/// A continuation byte (`80` to `BF`) that does not follow a lead byte.
+	UnexpectedContinuation,
+	/// A lead byte followed by too few continuation bytes.
+	///
+	/// Either the input ended, or a byte that is not a continuation byte
+	/// follows.
+	TruncatedSequence,
+	/// A code point encoded with more bytes than necessary.
+	///
+	/// Lead bytes `C0` and `C1`, or `E0` and `F0` followed by a too small
+	/// second byte.
+	OverlongEncoding,
+	/// An encoded UTF-16 surrogate (`U+D800` to `U+DFFF`).
+	SurrogateCodePoint,
+	/// A code point above `U+10FFFF`.
+	///
+	/// Lead bytes `F5` to `F7`, or `F4` followed by a too large second byte.
+	OutOfRangeCodePoint,
+	/// A byte that can never occur in UTF-8 (`F8` to `FF`).
+	InvalidLeadByte,
+}
+
+impl Utf8ErrorKind {
+	/// Classify a broken sequence, as produced by `from_utf8`.
+	///
+	/// `next` is the byte following the sequence, if any.
+	fn classify(broken: &[u8], next: Option<u8>) -> Utf8ErrorKind {
+		use self::Utf8ErrorKind::*;
+		if broken.len() > 1 {
+			// The second byte was accepted, so only a continuation byte can
+			// have been missing.
+			return TruncatedSequence;
+		}
+		match broken[0] {
+			0x80..=0xBF => UnexpectedContinuation,
+			0xC0 | 0xC1 => OverlongEncoding,
+			0xF5..=0xF7 => OutOfRangeCodePoint,
+			0xF8..=0xFF => InvalidLeadByte,
+			lead => match next {
+				// A continuation byte that was rejected as the second byte.
+				Some(b) if is_continuation_byte(b) => match lead {
+					0xE0 | 0xF0 => OverlongEncoding,
+					0xED => SurrogateCodePoint,
+					0xF4 => OutOfRangeCodePoint,
+					_ => TruncatedSequence,
+				},
+				_ => TruncatedSequence,
+			},
+		}
+	}
+}
+
+impl Display for Utf8ErrorKind {
+	fn fmt(&self, f: &mut Formatter) -> ::std::fmt::Result {
+		use self::Utf8ErrorKind::*;
+		f.write_str(match *self {
+			UnexpectedContinuation => "unexpected continuation byte",
+			TruncatedSequence => "truncated sequence",
+			OverlongEncoding => "overlong encoding",
+			SurrogateCodePoint => "surrogate code point",
+			OutOfRangeCodePoint => "out-of-range code point",
+			InvalidLeadByte => "invalid lead byte",
+		})
+	}
+}
+
+impl<'a> Utf8ChunksIter<'a> {
+	#[inline]
+	pub(super) fn new(bytes: &'a [u8]) -> Self {
+		Utf8ChunksIter {
+			bytes,
+			start: 0,
+			end: bytes.len(),
+		}
+	}
+
+	fn chunk(&self, offset: usize, valid_end: usize, broken_end: usize) -> Utf8Chunk<'a> {
+		let valid = unsafe { from_utf8_unchecked(&self.bytes[offset..valid_end]) };
+		let broken = &self.bytes[valid_end..broken_end];
+		Utf8Chunk { valid, broken }
+	}
+}
+
 impl<'a> Iterator for Utf8ChunksIter<'a> {
 	type Item = Utf8Chunk<'a>;
 
 	fn next(&mut self) -> Option<Utf8Chunk<'a>> {
-		if self.bytes.is_empty() {
+		if self.start == self.end {
 			return None;
 		}
-		match from_utf8(self.bytes) {
-			Ok(s) => {
-				self.bytes = &self.bytes[s.len()..];
-				Some(Utf8Chunk {
-					valid: s,
-					broken: &self.bytes[..0],
-				})
-			}
+		let offset = self.start;
+		let rest = &self.bytes[offset..self.end];
+		let (valid_end, broken_end) = match from_utf8(rest) {
+			Ok(_) => (self.end, self.end),
 			Err(e) => {
-				let (valid, rest) = self.bytes.split_at(e.valid_up_to());
-				let valid = unsafe { from_utf8_unchecked(valid) };
-				let (broken, rest) = rest.split_at(e.error_len().unwrap_or(rest.len()));
-				self.bytes = rest;
-				Some(Utf8Chunk { valid, broken })
+				let valid_end = offset + e.valid_up_to();
+				let broken_end = match e.error_len() {
+					Some(n) => valid_end + n,
+					None => self.end,
+				};
+				(valid_end, broken_end)
 			}
-		}
+		};
+		self.start = broken_end;
+		Some(self.chunk(offset, valid_end, broken_end))
 	}
 
 	#[inline]
 	fn size_hint(&self) -> (usize, Option<usize>) {
-		if self.bytes.is_empty() {
+		if self.start == self.end {
 			(0, Some(0))
 		} else {
 			(1, None)
@@ -58,4 +200,189 @@ impl<'a> Iterator for Utf8ChunksIter<'a> {
 	}
 }
 
+impl<'a> DoubleEndedIterator for Utf8ChunksIter<'a> {
+	fn next_back(&mut self) -> Option<Utf8Chunk<'a>> {
+		if self.start == self.end {
+			return None;
+		}
+		let rest = &self.bytes[self.start..self.end];
+		let valid_end = match last_char(rest) {
+			(_, true) => rest.len(),
+			(start, false) => start,
+		};
+		let mut valid_start = valid_end;
+		while valid_start > 0 {
+			match last_char(&rest[..valid_start]) {
+				(s, true) => valid_start = s,
+				(_, false) => break,
+			}
+		}
+		let offset = self.start + valid_start;
+		let broken_end = self.end;
+		self.end = offset;
+		Some(self.chunk(offset, self.start + valid_end, broken_end))
+	}
+}
+
 impl<'a> FusedIterator for Utf8ChunksIter<'a> {}
+
+#[inline]
+fn is_continuation_byte(b: u8) -> bool {
+	b & 0xC0 == 0x80
+}
+
+/// Find the start of the last (possibly broken) character in `bytes`.
+///
+/// Returns the index at which it starts, and whether it is valid UTF-8.
+/// The boundaries are the same as the ones `from_utf8` would find when
+/// decoding all of `bytes` from the start.
+///
+/// `bytes` must not be empty.
+fn last_char(bytes: &[u8]) -> (usize, bool) {
+	let end = bytes.len();
+	// Every byte that is not a continuation byte starts a new (possibly
+	// broken) character, and no character is longer than four bytes.
+	let lead = match (end.saturating_sub(4)..end)
+		.rev()
+		.find(|&i| !is_continuation_byte(bytes[i]))
+	{
+		Some(i) => i,
+		None => return (end - 1, false),
+	};
+	match from_utf8(&bytes[lead..]) {
+		Ok(_) => (lead, true),
+		Err(e) => match e.error_len() {
+			Some(n) if lead + n < end => (end - 1, false),
+			_ => (lead, false),
+		},
+	}
+}
+
+// Tests {{{
+
+#[cfg(test)]
+type ChunkTuple<'a> = (&'a str, &'a [u8], usize, Option<Utf8ErrorKind>);
+
+#[cfg(test)]
+fn chunks_forward(bytes: &[u8]) -> Vec<ChunkTuple<'_>> {
+	let s = RawStr::from_bytes(bytes);
+	s.utf8_chunks()
+		.map(|c| (c.valid, c.broken, c.offset_in(s), c.error_in(s)))
+		.collect()
+}
+
+#[cfg(test)]
+fn chunks_backward(bytes: &[u8]) -> Vec<ChunkTuple<'_>> {
+	let s = RawStr::from_bytes(bytes);
+	let mut chunks: Vec<_> = s
+		.utf8_chunks()
+		.rev()
+		.map(|c| (c.valid, c.broken, c.offset_in(s), c.error_in(s)))
+		.collect();
+	chunks.reverse();
+	chunks
+}
+
+#[test]
+fn test_utf8_chunks_rev() {
+	let cases: &[&[u8]] = &[
+		b"",
+		b"hello",
+		b"\xFF",
+		b"1 \xFF \xce\xbcs / \xc2\xb0C",
+		b"ab\xFF\xFEcd",
+		b"abc\xE2\x82",
+		b"\xE2\x82\xAC\xE2\x82",
+		b"\xF0\x90\x80A\xF0\x80\x80\x80\x80",
+		b"\x80\x80\x80\x80\x80\x80",
+		b"\xED\xA0\x80\xF4\x90\x80\x80\xC0\xAF",
+		b"\xE0\x80\xE0\xA0\x80\xF8\x88\x80\x80\x80",
+		"μs/°C €𝄞".as_bytes(),
+	];
+	for &bytes in cases {
+		assert_eq!(chunks_backward(bytes), chunks_forward(bytes));
+	}
+}
+
+#[test]
+fn test_utf8_chunks_rev_exhaustive() {
+	// All sequences of up to four bytes made of a selection of
+	// interesting bytes, covering all kinds of broken sequences.
+	let interesting = [
+		0x00, 0x41, 0x7F, 0x80, 0x8F, 0x90, 0x9F, 0xA0, 0xBF, 0xC0, 0xC2, 0xDF, 0xE0, 0xE1,
+		0xED, 0xEF, 0xF0, 0xF1, 0xF4, 0xF5, 0xFF,
+	];
+	let mut bytes = Vec::new();
+	for len in 0..5 {
+		for i in 0..interesting.len().pow(len) {
+			bytes.clear();
+			let mut n = i;
+			for _ in 0..len {
+				bytes.push(interesting[n % interesting.len()]);
+				n /= interesting.len();
+			}
+			assert_eq!(chunks_backward(&bytes), chunks_forward(&bytes), "{:x?}", bytes);
+		}
+	}
+}
+
+#[test]
+fn test_utf8_chunks_mixed_directions() {
+	let s = RawStr::from_bytes(b"a\xFFb\xE2\x82c\x80d\xF0\x9F\x98\x80");
+	let forward = chunks_forward(s.as_bytes());
+	let mut iter = s.utf8_chunks();
+	let mut front = Vec::new();
+	let mut back = Vec::new();
+	while let Some(c) = iter.next() {
+		front.push((c.valid, c.broken, c.offset_in(s), c.error_in(s)));
+		match iter.next_back() {
+			Some(c) => back.push((c.valid, c.broken, c.offset_in(s), c.error_in(s))),
+			None => break,
+		}
+	}
+	back.reverse();
+	front.extend(back);
+	assert_eq!(front, forward);
+}
+
+#[test]
+fn test_utf8_chunks_offset_and_error() {
+	use self::Utf8ErrorKind::*;
+	let s = RawStr::from_bytes(
+		b"a\x80b\xE2\x82c\xC0\xAFd\xE0\x80\x80e\xED\xA0\x80f\xF4\x90\x80\x80g\xF5h\xFF\xE2",
+	);
+	let chunks: Vec<_> = s
+		.utf8_chunks()
+		.map(|c| (c.valid, c.offset_in(s), c.error_in(s)))
+		.collect();
+	assert_eq!(
+		chunks,
+		vec![
+			("a", 0, Some(UnexpectedContinuation)),
+			("b", 2, Some(TruncatedSequence)),
+			("c", 5, Some(OverlongEncoding)),
+			("", 7, Some(UnexpectedContinuation)),
+			("d", 8, Some(OverlongEncoding)),
+			("", 10, Some(UnexpectedContinuation)),
+			("", 11, Some(UnexpectedContinuation)),
+			("e", 12, Some(SurrogateCodePoint)),
+			("", 14, Some(UnexpectedContinuation)),
+			("", 15, Some(UnexpectedContinuation)),
+			("f", 16, Some(OutOfRangeCodePoint)),
+			("", 18, Some(UnexpectedContinuation)),
+			("", 19, Some(UnexpectedContinuation)),
+			("", 20, Some(UnexpectedContinuation)),
+			("g", 21, Some(OutOfRangeCodePoint)),
+			("h", 23, Some(InvalidLeadByte)),
+			("", 25, Some(TruncatedSequence)),
+		]
+	);
+	let valid = RawStr::from_str("μs");
+	let chunks: Vec<_> = valid
+		.utf8_chunks()
+		.map(|c| (c.valid, c.offset_in(valid), c.error_in(valid)))
+		.collect();
+	assert_eq!(chunks, vec![("μs", 0, None)]);
+}
+
+// }}}
@@ -0,0 +1,253 @@
+use std::iter::FusedIterator;
+use std::str::from_utf8;
+use str::RawStr;
+
+fn is_ascii_space(b: u8) -> bool {
+	b.is_ascii_whitespace()
+}
+
+/// The length of the ASCII whitespace byte at the start, if any.
+fn leading_ascii_space(s: &[u8]) -> usize {
+	s.first().map_or(0, |&b| is_ascii_space(b) as usize)
+}
+
+/// The length of the ASCII whitespace byte at the end, if any.
+fn trailing_ascii_space(s: &[u8]) -> usize {
+	s.last().map_or(0, |&b| is_ascii_space(b) as usize)
+}
+
+fn single_space_char(s: &[u8]) -> usize {
+	let mut chars = match from_utf8(s) {
+		Ok(s) => s.chars(),
+		Err(_) => return 0,
+	};
+	match (chars.next(), chars.next()) {
+		(Some(c), None) if c.is_whitespace() => s.len(),
+		_ => 0,
+	}
+}
+
+/// The length of the Unicode whitespace character at the start, if any.
+fn leading_space(s: &[u8]) -> usize {
+	let len = match s.first() {
+		None => return 0,
+		Some(&b) if b < 0x80 => return (b as char).is_whitespace() as usize,
+		Some(0xC2..=0xDF) => 2,
+		Some(0xE0..=0xEF) => 3,
+		// There is no whitespace outside of the BMP.
+		Some(_) => return 0,
+	};
+	s.get(..len).map_or(0, single_space_char)
+}
+
+/// The length of the Unicode whitespace character at the end, if any.
+fn trailing_space(s: &[u8]) -> usize {
+	let mut start = match s.len().checked_sub(1) {
+		Some(last) => last,
+		None => return 0,
+	};
+	if s[start] < 0x80 {
+		return (s[start] as char).is_whitespace() as usize;
+	}
+	while start > 0 && s.len() - start < 3 && s[start] & 0xC0 == 0x80 {
+		start -= 1;
+	}
+	single_space_char(&s[start..])
+}
+
+/// The fields of a string, separated by whitespace as recognised by the
+/// given functions.
+#[derive(Clone)]
+struct Fields<'a> {
+	rest: &'a [u8],
+	leading_space: fn(&[u8]) -> usize,
+	trailing_space: fn(&[u8]) -> usize,
+}
+
+impl<'a> Fields<'a> {
+	fn next(&mut self) -> Option<&'a RawStr> {
+		loop {
+			let n = (self.leading_space)(self.rest);
+			if n == 0 {
+				break;
+			}
+			self.rest = &self.rest[n..];
+		}
+		if self.rest.is_empty() {
+			return None;
+		}
+		let mut end = 1;
+		while end < self.rest.len() && (self.leading_space)(&self.rest[end..]) == 0 {
+			end += 1;
+		}
+		let (field, rest) = self.rest.split_at(end);
+		self.rest = rest;
+		Some(RawStr::from_bytes(field))
+	}
+
+	fn next_back(&mut self) -> Option<&'a RawStr> {
+		loop {
+			let n = (self.trailing_space)(self.rest);
+			if n == 0 {
+				break;
+			}
+			self.rest = &self.rest[..self.rest.len() - n];
+		}
+		if self.rest.is_empty() {
+			return None;
+		}
+		let mut start = self.rest.len() - 1;
+		while start > 0 && (self.trailing_space)(&self.rest[..start]) == 0 {
+			start -= 1;
+		}
+		let (rest, field) = self.rest.split_at(start);
+		self.rest = rest;
+		Some(RawStr::from_bytes(field))
+	}
+}
+
+macro_rules! fields_iterator {
+	($(#[$doc:meta])* $name:ident) => {
+		$(#[$doc])*
+		#[derive(Clone)]
+		pub struct $name<'a> {
+			inner: Fields<'a>,
+		}
+
+		impl<'a> Iterator for $name<'a> {
+			type Item = &'a RawStr;
+			#[inline]
+			fn next(&mut self) -> Option<&'a RawStr> {
+				self.inner.next()
+			}
+		}
+
+		impl<'a> DoubleEndedIterator for $name<'a> {
+			#[inline]
+			fn next_back(&mut self) -> Option<&'a RawStr> {
+				self.inner.next_back()
+			}
+		}
+
+		impl<'a> FusedIterator for $name<'a> {}
+	};
+}
+
+fields_iterator!(
+	/// Iterator over the parts of a string, separated by Unicode whitespace.
+	///
+	/// See [`RawStr::split_whitespace`](struct.RawStr.html#method.split_whitespace).
+	SplitWhitespace
+);
+
+fields_iterator!(
+	/// Iterator over the parts of a string, separated by ASCII whitespace.
+	///
+	/// See [`RawStr::split_ascii_whitespace`](struct.RawStr.html#method.split_ascii_whitespace).
+	SplitAsciiWhitespace
+);
+
+impl RawStr {
+	/// Iterate over the parts of the string, separated by any amount of
+	/// whitespace.
+	///
+	/// Whitespace is recognised in the valid UTF-8 parts of the string, as
+	/// defined by [`char::is_whitespace`]. Broken characters are never
+	/// whitespace.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b" a\xFF\xE3\x80\x80b\t c\n");
+	/// let parts: Vec<&RawStr> = s.split_whitespace().collect();
+	/// assert_eq!(parts, [&b"a\xFF"[..], b"b", b"c"]);
+	/// ```
+	#[inline]
+	pub fn split_whitespace(&self) -> SplitWhitespace<'_> {
+		SplitWhitespace {
+			inner: Fields {
+				rest: self.as_bytes(),
+				leading_space,
+				trailing_space,
+			},
+		}
+	}
+
+	/// Iterate over the parts of the string, separated by any amount of
+	/// ASCII whitespace.
+	///
+	/// This only looks at bytes, as defined by
+	/// [`u8::is_ascii_whitespace`].
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"cpu  10 \xFF\xA0 20\n");
+	/// let parts: Vec<&RawStr> = s.split_ascii_whitespace().rev().collect();
+	/// assert_eq!(parts, [&b"20"[..], b"\xFF\xA0", b"10", b"cpu"]);
+	/// ```
+	#[inline]
+	pub fn split_ascii_whitespace(&self) -> SplitAsciiWhitespace<'_> {
+		SplitAsciiWhitespace {
+			inner: Fields {
+				rest: self.as_bytes(),
+				leading_space: leading_ascii_space,
+				trailing_space: trailing_ascii_space,
+			},
+		}
+	}
+}
+
+// Tests {{{
+
+#[cfg(test)]
+fn fields(s: &[u8]) -> (Vec<&[u8]>, Vec<&[u8]>) {
+	let s = RawStr::from_bytes(s);
+	let forward = s.split_whitespace().map(RawStr::as_bytes).collect();
+	let mut backward: Vec<&[u8]> = s.split_whitespace().rev().map(RawStr::as_bytes).collect();
+	backward.reverse();
+	(forward, backward)
+}
+
+#[test]
+fn test_split_whitespace() {
+	for &(s, expected) in &[
+		(&b""[..], &[][..]),
+		(b" \t\n", &[]),
+		(b"a", &[&b"a"[..]]),
+		(b"  a  b ", &[b"a", b"b"]),
+		(b"a\xC2\xA0b\xE2\x80\x83c", &[b"a", b"b", b"c"]),
+		(b"\xE3\x80\x80x\xE3\x80\x80", &[b"x"]),
+		(b"\xFF \xE2\xE3\x80\x80\x80", &[b"\xFF", b"\xE2", b"\x80"]),
+		(b"\xC2 \xA0", &[b"\xC2", b"\xA0"]),
+		(b"\xA0\x85a\xE2\x80", &[b"\xA0\x85a\xE2\x80"]),
+		(b"caf\xC3\xA9 \xF0\x9F\x98\x80", &[b"caf\xC3\xA9", b"\xF0\x9F\x98\x80"]),
+	] {
+		let (forward, backward) = fields(s);
+		assert_eq!(forward, expected);
+		assert_eq!(backward, expected);
+	}
+
+	let s = RawStr::from_str("a b c d");
+	let mut iter = s.split_whitespace();
+	assert_eq!(iter.next(), Some(RawStr::from_str("a")));
+	assert_eq!(iter.next_back(), Some(RawStr::from_str("d")));
+	assert_eq!(iter.next_back(), Some(RawStr::from_str("c")));
+	assert_eq!(iter.next(), Some(RawStr::from_str("b")));
+	assert_eq!(iter.next(), None);
+	assert_eq!(iter.next_back(), None);
+}
+
+#[test]
+fn test_split_ascii_whitespace() {
+	let s = RawStr::from_bytes(b"\x0C a\xC2\xA0b \x0Bc\r\n");
+	let parts: Vec<&RawStr> = s.split_ascii_whitespace().collect();
+	assert_eq!(parts, [&b"a\xC2\xA0b"[..], b"\x0Bc"]);
+	let parts: Vec<&RawStr> = s.split_ascii_whitespace().rev().collect();
+	assert_eq!(parts, [&b"\x0Bc"[..], b"a\xC2\xA0b"]);
+	assert_eq!(RawStr::from_str("   ").split_ascii_whitespace().next(), None);
+}
+
+// }}}
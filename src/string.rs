@@ -1,7 +1,7 @@
 use std;
 use std::borrow::{Borrow, ToOwned};
 use std::cmp::Ordering;
-use std::ffi::OsString;
+use std::ffi::{CString, NulError, OsString};
 use std::fmt::{Debug, Display, Formatter};
 use std::ops::{Deref, DerefMut, RangeBounds};
 use std::path::PathBuf;
@@ -48,6 +48,22 @@ impl RawString {
 		self.inner
 	}
 
+	/// The contents of a C string, without the terminating NUL byte.
+	#[inline]
+	pub fn from_cstring(s: CString) -> Self {
+		Self::from_bytes(s.into_bytes())
+	}
+
+	/// Convert to a C string, by appending a NUL byte.
+	///
+	/// Fails if the string contains a NUL byte itself. The bytes can be
+	/// recovered from the error with
+	/// [`NulError::into_vec`](https://doc.rust-lang.org/std/ffi/struct.NulError.html#method.into_vec).
+	#[inline]
+	pub fn into_cstring(self) -> Result<CString, NulError> {
+		CString::new(self.into_bytes())
+	}
+
 	#[inline]
 	pub fn capacity(&self) -> usize {
 		self.inner.capacity()
@@ -116,7 +132,7 @@ impl RawString {
 	}
 
 	#[inline]
-	pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<u8> {
+	pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, u8> {
 		self.inner.drain(range)
 	}
 
@@ -150,6 +166,7 @@ impl RawString {
 		&mut self.inner
 	}
 
+	#[allow(clippy::wrong_self_convention)]
 	#[inline]
 	pub fn to_string(self) -> Result<String, FromUtf8Error> {
 		String::from_utf8(self.into_bytes())
@@ -162,6 +179,7 @@ impl RawString {
 	///
 	/// A never-failing version for Unix only is available as
 	/// [`unix::RawStringExt::into_osstring`](struct.RawString.html#method.into_osstring).
+	#[allow(clippy::wrong_self_convention)]
 	#[inline]
 	pub fn to_osstring(self) -> Result<OsString, FromUtf8Error> {
 		self.to_osstring_()
@@ -174,12 +192,14 @@ impl RawString {
 	///
 	/// A never-failing version for Unix only is available as
 	/// [`unix::RawStringExt::into_pathbuf`](struct.RawString.html#method.into_pathbuf).
+	#[allow(clippy::wrong_self_convention)]
 	#[inline]
 	pub fn to_pathbuf(self) -> Result<PathBuf, FromUtf8Error> {
 		Ok(PathBuf::from(self.to_osstring()?))
 	}
 
 	#[cfg(unix)]
+	#[allow(clippy::wrong_self_convention)]
 	#[inline]
 	fn to_osstring_(self) -> Result<OsString, FromUtf8Error> {
 		use std::os::unix::ffi::OsStringExt;
@@ -187,6 +207,7 @@ impl RawString {
 	}
 
 	#[cfg(not(unix))]
+	#[allow(clippy::wrong_self_convention)]
 	#[inline]
 	fn to_osstring_(self) -> Result<OsString, FromUtf8Error> {
 		Ok(OsString::from(self.to_string()?))
@@ -310,6 +331,13 @@ impl From<String> for RawString {
 	}
 }
 
+impl From<CString> for RawString {
+	#[inline]
+	fn from(src: CString) -> RawString {
+		RawString::from_cstring(src)
+	}
+}
+
 impl From<Vec<u8>> for RawString {
 	#[inline]
 	fn from(src: Vec<u8>) -> RawString {
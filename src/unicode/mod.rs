@@ -0,0 +1,34 @@
+//! Unicode algorithms that need tables, only available with the `unicode`
+//! feature.
+
+use std::cmp::Ordering;
+
+mod normalize;
+mod segment;
+mod tables;
+mod width;
+mod wrap;
+
+pub use self::segment::{GraphemeIndices, Graphemes, UnicodeWordIndices, UnicodeWords, WordBoundIndices, WordBounds};
+pub use self::width::WidthOptions;
+pub use self::wrap::{Wrap, WrapOptions};
+
+/// The index of the range that contains `c`, in a sorted table of ranges.
+fn find_range<T>(table: &[T], c: char, range: impl Fn(&T) -> (char, char)) -> Option<usize> {
+	table
+		.binary_search_by(|entry| {
+			let (first, last) = range(entry);
+			if last < c {
+				Ordering::Less
+			} else if first > c {
+				Ordering::Greater
+			} else {
+				Ordering::Equal
+			}
+		})
+		.ok()
+}
+
+fn in_ranges(c: char, ranges: &[(char, char)]) -> bool {
+	find_range(ranges, c, |&range| range).is_some()
+}
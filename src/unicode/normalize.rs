@@ -0,0 +1,351 @@
+//! Unicode normalization, as described in
+//! [UAX #15](https://www.unicode.org/reports/tr15/).
+
+use super::tables::*;
+use super::{find_range, in_ranges};
+use str::{RawStr, Utf8Chunk};
+use string::RawString;
+
+// Hangul syllables are decomposed and composed algorithmically.
+const S_BASE: u32 = 0xAC00;
+const L_BASE: u32 = 0x1100;
+const V_BASE: u32 = 0x1161;
+const T_BASE: u32 = 0x11A7;
+const L_COUNT: u32 = 19;
+const V_COUNT: u32 = 21;
+const T_COUNT: u32 = 28;
+const N_COUNT: u32 = V_COUNT * T_COUNT;
+const S_COUNT: u32 = L_COUNT * N_COUNT;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Form {
+	Nfc,
+	Nfd,
+	Nfkc,
+	Nfkd,
+}
+
+impl Form {
+	fn compatibility(self) -> bool {
+		self == Form::Nfkc || self == Form::Nfkd
+	}
+
+	fn composed(self) -> bool {
+		self == Form::Nfc || self == Form::Nfkc
+	}
+}
+
+fn combining_class(c: char) -> u8 {
+	if c < '\u{300}' {
+		return 0;
+	}
+	find_range(&CANONICAL_COMBINING_CLASS, c, |&(first, last, _)| (first, last))
+		.map_or(0, |i| CANONICAL_COMBINING_CLASS[i].2)
+}
+
+fn lookup(table: &'static [(char, &'static [char])], c: char) -> Option<&'static [char]> {
+	table.binary_search_by_key(&c, |&(from, _)| from).ok().map(|i| table[i].1)
+}
+
+/// Append the full decomposition of `c`.
+fn decompose(c: char, compatibility: bool, out: &mut Vec<char>) {
+	let s = (c as u32).wrapping_sub(S_BASE);
+	if s < S_COUNT {
+		let l = L_BASE + s / N_COUNT;
+		let v = V_BASE + s % N_COUNT / T_COUNT;
+		let t = T_BASE + s % T_COUNT;
+		out.push(char::from_u32(l).unwrap());
+		out.push(char::from_u32(v).unwrap());
+		if t != T_BASE {
+			out.push(char::from_u32(t).unwrap());
+		}
+		return;
+	}
+	if c < '\u{A0}' {
+		out.push(c);
+		return;
+	}
+	let mapping = if compatibility {
+		lookup(&COMPATIBILITY_DECOMPOSITION, c).or_else(|| lookup(&CANONICAL_DECOMPOSITION, c))
+	} else {
+		lookup(&CANONICAL_DECOMPOSITION, c)
+	};
+	match mapping {
+		Some(mapping) => out.extend_from_slice(mapping),
+		None => out.push(c),
+	}
+}
+
+/// Put all sequences of non-starters in canonical order.
+fn reorder(chars: &mut [char]) {
+	let mut i = 0;
+	while i < chars.len() {
+		if combining_class(chars[i]) == 0 {
+			i += 1;
+			continue;
+		}
+		let start = i;
+		while i < chars.len() && combining_class(chars[i]) != 0 {
+			i += 1;
+		}
+		// A stable sort, so characters with the same class keep their order.
+		chars[start..i].sort_by_key(|&c| combining_class(c));
+	}
+}
+
+/// The primary composite of the two characters, if there is one.
+fn compose_pair(a: char, b: char) -> Option<char> {
+	let (a32, b32) = (a as u32, b as u32);
+	if (L_BASE..L_BASE + L_COUNT).contains(&a32) && (V_BASE..V_BASE + V_COUNT).contains(&b32) {
+		let lv = S_BASE + ((a32 - L_BASE) * V_COUNT + (b32 - V_BASE)) * T_COUNT;
+		return char::from_u32(lv);
+	}
+	let s = a32.wrapping_sub(S_BASE);
+	if s < S_COUNT && s % T_COUNT == 0 && (T_BASE + 1..T_BASE + T_COUNT).contains(&b32) {
+		return char::from_u32(a32 + (b32 - T_BASE));
+	}
+	COMPOSITION
+		.binary_search_by_key(&(a, b), |&(x, y, _)| (x, y))
+		.ok()
+		.map(|i| COMPOSITION[i].2)
+}
+
+/// Canonically compose a decomposed and reordered sequence.
+fn compose(chars: &mut Vec<char>) {
+	let mut out = 0;
+	let mut starter: Option<usize> = None;
+	// The combining class of the last character after the starter that
+	// wasn't composed with it, if any.
+	let mut last_class: Option<u8> = None;
+	for i in 0..chars.len() {
+		let c = chars[i];
+		let class = combining_class(c);
+		if let Some(s) = starter {
+			let blocked = last_class.is_some_and(|last| last == 0 || last >= class);
+			if !blocked {
+				if let Some(composite) = compose_pair(chars[s], c) {
+					chars[s] = composite;
+					continue;
+				}
+			}
+		}
+		if class == 0 {
+			starter = Some(out);
+			last_class = None;
+		} else {
+			last_class = Some(class);
+		}
+		chars[out] = c;
+		out += 1;
+	}
+	chars.truncate(out);
+}
+
+fn normalize_str(s: &str, form: Form, buf: &mut Vec<char>, result: &mut RawString) {
+	buf.clear();
+	for c in s.chars() {
+		decompose(c, form.compatibility(), buf);
+	}
+	reorder(buf);
+	if form.composed() {
+		compose(buf);
+	}
+	let mut utf8 = [0; 4];
+	for &c in buf.iter() {
+		result.push_str(c.encode_utf8(&mut utf8));
+	}
+}
+
+/// The result of a quick check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum QuickCheck {
+	Yes,
+	No,
+	Maybe,
+}
+
+fn quick_check(s: &str, form: Form) -> QuickCheck {
+	let mut last_class = 0;
+	let mut result = QuickCheck::Yes;
+	for c in s.chars() {
+		if c < '\u{300}' && (form == Form::Nfc || c < '\u{C0}') {
+			last_class = 0;
+			continue;
+		}
+		let class = combining_class(c);
+		if last_class > class && class != 0 {
+			return QuickCheck::No;
+		}
+		last_class = class;
+		match form {
+			Form::Nfc => {
+				if in_ranges(c, &NFC_QUICK_CHECK_NO) {
+					return QuickCheck::No;
+				}
+				if in_ranges(c, &NFC_QUICK_CHECK_MAYBE) {
+					result = QuickCheck::Maybe;
+				}
+			}
+			Form::Nfd => {
+				let s = (c as u32).wrapping_sub(S_BASE);
+				if s < S_COUNT || lookup(&CANONICAL_DECOMPOSITION, c).is_some() {
+					return QuickCheck::No;
+				}
+			}
+			_ => unreachable!(),
+		}
+	}
+	result
+}
+
+fn normalize(s: &RawStr, form: Form) -> RawString {
+	let mut result = RawString::with_capacity(s.len());
+	let mut buf = Vec::new();
+	for Utf8Chunk { valid, broken } in s.utf8_chunks() {
+		let already = match form {
+			Form::Nfc | Form::Nfd => quick_check(valid, form) == QuickCheck::Yes,
+			_ => valid.is_ascii(),
+		};
+		if already {
+			result.push_str(valid);
+		} else {
+			normalize_str(valid, form, &mut buf, &mut result);
+		}
+		result.push_str(RawStr::from_bytes(broken));
+	}
+	result
+}
+
+fn is_normalized(s: &RawStr, form: Form) -> bool {
+	let mut buf = Vec::new();
+	let mut normalized = RawString::new();
+	s.utf8_chunks().all(|Utf8Chunk { valid, .. }| match quick_check(valid, form) {
+		QuickCheck::Yes => true,
+		QuickCheck::No => false,
+		QuickCheck::Maybe => {
+			normalized.clear();
+			normalize_str(valid, form, &mut buf, &mut normalized);
+			normalized == valid
+		}
+	})
+}
+
+/// Unicode normalization. Only available with the `unicode` feature.
+///
+/// These only normalize the chunks of valid UTF-8 (see
+/// [`utf8_chunks`](#method.utf8_chunks)), each on their own. Bytes that aren't
+/// part of valid UTF-8 are copied unchanged, and characters are never
+/// combined across them.
+impl RawStr {
+	/// Convert to Normalization Form C: canonical decomposition, followed by
+	/// canonical composition.
+	///
+	/// This is the form most text is in, and the one that most systems
+	/// (except for macOS file names) use.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"e\xCC\x81\xFF");
+	/// assert_eq!(s.nfc(), RawStr::from_bytes(b"\xC3\xA9\xFF"));
+	/// ```
+	pub fn nfc(&self) -> RawString {
+		normalize(self, Form::Nfc)
+	}
+
+	/// Convert to Normalization Form D: canonical decomposition.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"\xC3\xA9\xFF");
+	/// assert_eq!(s.nfd(), RawStr::from_bytes(b"e\xCC\x81\xFF"));
+	/// ```
+	pub fn nfd(&self) -> RawString {
+		normalize(self, Form::Nfd)
+	}
+
+	/// Convert to Normalization Form KC: compatibility decomposition, followed
+	/// by canonical composition.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_str("\u{FB01}le\u{2075}");
+	/// assert_eq!(s.nfkc(), "file5");
+	/// ```
+	pub fn nfkc(&self) -> RawString {
+		normalize(self, Form::Nfkc)
+	}
+
+	/// Convert to Normalization Form KD: compatibility decomposition.
+	pub fn nfkd(&self) -> RawString {
+		normalize(self, Form::Nfkd)
+	}
+
+	/// Whether this is in Normalization Form C: whether `s.nfc() == s`.
+	///
+	/// This is much faster than normalizing, as only a few characters need a
+	/// closer look.
+	pub fn is_nfc(&self) -> bool {
+		is_normalized(self, Form::Nfc)
+	}
+
+	/// Whether this is in Normalization Form D: whether `s.nfd() == s`.
+	pub fn is_nfd(&self) -> bool {
+		is_normalized(self, Form::Nfd)
+	}
+}
+
+// Tests {{{
+
+#[test]
+fn test_normalize() {
+	let s = RawStr::from_str;
+	// Composition and decomposition, with reordering of combining marks.
+	assert_eq!(s("\u{1E0B}\u{323}").nfd(), "d\u{323}\u{307}");
+	assert_eq!(s("d\u{307}\u{323}").nfc(), "\u{1E0D}\u{307}");
+	assert_eq!(s("\u{212B}").nfc(), "\u{C5}");
+	assert_eq!(s("\u{212B}").nfd(), "A\u{30A}");
+	// Blocked composition.
+	assert_eq!(s("a\u{5AE}\u{302}\u{301}").nfc(), "\u{1EA5}\u{5AE}");
+	assert_eq!(s("a\u{301}\u{301}").nfc(), "\u{E1}\u{301}");
+	// Composition exclusions.
+	assert_eq!(s("\u{915}\u{93C}").nfc(), "\u{915}\u{93C}");
+	assert_eq!(s("\u{958}").nfc(), "\u{915}\u{93C}");
+	// Hangul.
+	assert_eq!(s("\u{D4DB}").nfd(), "\u{1111}\u{1171}\u{11B6}");
+	assert_eq!(s("\u{1111}\u{1171}\u{11B6}").nfc(), "\u{D4DB}");
+	assert_eq!(s("\u{AC00}\u{11A8}").nfc(), "\u{AC01}");
+	// Compatibility.
+	assert_eq!(s("\u{1E9B}\u{323}").nfkd(), "s\u{323}\u{307}");
+	assert_eq!(s("\u{1E9B}\u{323}").nfkc(), "\u{1E69}");
+	assert_eq!(s("\u{1E9B}\u{323}").nfc(), "\u{1E9B}\u{323}");
+	assert_eq!(s("\u{3300}").nfkc(), "\u{30A2}\u{30D1}\u{30FC}\u{30C8}");
+	// Broken bytes are kept, and nothing combines across them.
+	let raw = RawStr::from_bytes(b"e\xCC\x81\xFF\xCC\x81 \xC3\xA9\xC3");
+	assert_eq!(raw.nfc(), RawStr::from_bytes(b"\xC3\xA9\xFF\xCC\x81 \xC3\xA9\xC3"));
+	assert_eq!(raw.nfd(), RawStr::from_bytes(b"e\xCC\x81\xFF\xCC\x81 e\xCC\x81\xC3"));
+}
+
+#[test]
+fn test_is_normalized() {
+	let s = RawStr::from_str;
+	assert!(s("").is_nfc());
+	assert!(s("caf\u{E9}").is_nfc());
+	assert!(!s("cafe\u{301}").is_nfc());
+	assert!(s("cafe\u{301}").is_nfd());
+	assert!(!s("caf\u{E9}").is_nfd());
+	assert!(!s("\u{D4DB}").is_nfd());
+	// Maybe in the quick check, but decided by normalizing.
+	assert!(s("\u{300}").is_nfc());
+	assert!(!s("a\u{300}").is_nfc());
+	assert!(!s("\u{1100}\u{1100}\u{1161}").is_nfc());
+	assert!(!s("a\u{323}\u{300}x\u{300}\u{323}").is_nfd());
+	assert!(RawStr::from_bytes(b"caf\xC3\xA9\xFF\xCC\x81").is_nfc());
+}
+
+// }}}
@@ -0,0 +1,499 @@
+//! Grapheme cluster and word boundaries, as described in
+//! [UAX #29](https://www.unicode.org/reports/tr29/).
+
+use super::tables::GraphemeClusterBreak as G;
+use super::tables::WordBreak as W;
+use super::tables::{EXTENDED_PICTOGRAPHIC, GRAPHEME_CLUSTER_BREAK, WORD_BREAK};
+use super::{find_range, in_ranges};
+use std::iter::FusedIterator;
+use std::mem::take;
+use str::{RawStr, Utf8Chunk, Utf8ChunksIter};
+
+fn grapheme_cluster_break(c: char) -> G {
+	match c {
+		'\r' => G::CR,
+		'\n' => G::LF,
+		'\0'..='\x1F' | '\x7F' => G::Control,
+		' '..='~' => G::Other,
+		_ => find_range(&GRAPHEME_CLUSTER_BREAK, c, |&(first, last, _)| (first, last))
+			.map_or(G::Other, |i| GRAPHEME_CLUSTER_BREAK[i].2),
+	}
+}
+
+fn word_break(c: char) -> W {
+	find_range(&WORD_BREAK, c, |&(first, last, _)| (first, last)).map_or(W::Other, |i| WORD_BREAK[i].2)
+}
+
+pub(super) fn is_extended_pictographic(c: char) -> bool {
+	c >= '\u{A9}' && in_ranges(c, &EXTENDED_PICTOGRAPHIC)
+}
+
+// Grapheme clusters {{{
+
+/// Where we are in an emoji ZWJ sequence, for rule GB11.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Emoji {
+	None,
+	/// After an Extended_Pictographic character and any Extend characters.
+	Pictographic,
+	/// After a ZWJ that follows `Pictographic`.
+	Zwj,
+}
+
+/// The end of the grapheme cluster that starts at `start`.
+pub(super) fn grapheme_end(s: &str, start: usize) -> usize {
+	let mut chars = s[start..].char_indices();
+	let first = match chars.next() {
+		Some((_, c)) => c,
+		None => return start,
+	};
+	let mut prev = grapheme_cluster_break(first);
+	let mut emoji = if is_extended_pictographic(first) {
+		Emoji::Pictographic
+	} else {
+		Emoji::None
+	};
+	// The number of regional indicators right before this position.
+	let mut regional_indicators = (prev == G::RegionalIndicator) as usize;
+	for (i, c) in chars {
+		let next = grapheme_cluster_break(c);
+		let pictographic = is_extended_pictographic(c);
+		let boundary = match (prev, next) {
+			(G::CR, G::LF) => false,
+			(G::CR | G::LF | G::Control, _) | (_, G::CR | G::LF | G::Control) => true,
+			(G::L, G::L | G::V | G::LV | G::LVT) => false,
+			(G::LV | G::V, G::V | G::T) => false,
+			(G::LVT | G::T, G::T) => false,
+			(_, G::Extend | G::ZWJ | G::SpacingMark) => false,
+			(G::Prepend, _) => false,
+			(G::ZWJ, _) if emoji == Emoji::Zwj && pictographic => false,
+			(G::RegionalIndicator, G::RegionalIndicator) => regional_indicators % 2 == 0,
+			_ => true,
+		};
+		if boundary {
+			return start + i;
+		}
+		emoji = match (emoji, next) {
+			_ if pictographic => Emoji::Pictographic,
+			(Emoji::Pictographic, G::Extend) => Emoji::Pictographic,
+			(Emoji::Pictographic, G::ZWJ) => Emoji::Zwj,
+			_ => Emoji::None,
+		};
+		if next == G::RegionalIndicator {
+			regional_indicators += 1;
+		} else {
+			regional_indicators = 0;
+		}
+		prev = next;
+	}
+	s.len()
+}
+
+// }}}
+
+// Words {{{
+
+/// Whether the character is ignored by rule WB4.
+fn is_ignorable(w: W) -> bool {
+	matches!(w, W::Extend | W::Format | W::ZWJ)
+}
+
+fn is_newline(w: W) -> bool {
+	matches!(w, W::CR | W::LF | W::Newline)
+}
+
+fn is_ahletter(w: W) -> bool {
+	matches!(w, W::ALetter | W::HebrewLetter)
+}
+
+fn is_mid_num_let_q(w: W) -> bool {
+	matches!(w, W::MidNumLet | W::SingleQuote)
+}
+
+/// The property of the character before `i`, after applying rule WB4, and the
+/// position of that character.
+fn effective_before(s: &str, i: usize) -> Option<(usize, W)> {
+	let mut chars = s[..i].char_indices().rev();
+	let (last_index, last) = chars.next()?;
+	let last = (last_index, word_break(last));
+	if !is_ignorable(last.1) {
+		return Some(last);
+	}
+	for (j, c) in chars {
+		let w = word_break(c);
+		if !is_ignorable(w) {
+			// Ignorable characters after a newline aren't absorbed by it.
+			return Some(if is_newline(w) { last } else { (j, w) });
+		}
+	}
+	Some(last)
+}
+
+/// The property of the first character after the one at `i` that isn't
+/// ignored by rule WB4.
+fn effective_after(s: &str, i: usize) -> Option<W> {
+	s[i..].chars().skip(1).map(word_break).find(|&w| !is_ignorable(w))
+}
+
+/// Whether there is a word boundary at `i`, which must be a character
+/// boundary strictly within `s`.
+///
+/// `regional_indicators` is the number of regional indicators right before
+/// `i` (after applying rule WB4), for rules WB15 and WB16.
+fn is_word_boundary(s: &str, i: usize, regional_indicators: usize) -> bool {
+	let before = s[..i].chars().next_back().unwrap();
+	let after = s[i..].chars().next().unwrap();
+	let (raw_before, raw_after) = (word_break(before), word_break(after));
+	// WB3 - WB4
+	if raw_before == W::CR && raw_after == W::LF {
+		return false;
+	}
+	if is_newline(raw_before) || is_newline(raw_after) {
+		return true;
+	}
+	if raw_before == W::ZWJ && is_extended_pictographic(after) {
+		return false;
+	}
+	if raw_before == W::WSegSpace && raw_after == W::WSegSpace {
+		return false;
+	}
+	if is_ignorable(raw_after) {
+		return false;
+	}
+	let (before_index, p1) = effective_before(s, i).unwrap();
+	let p2 = effective_before(s, before_index).map(|(_, w)| w);
+	let n1 = raw_after;
+	let n2 = effective_after(s, i);
+	let p2_is = |f: fn(W) -> bool| p2.is_some_and(f);
+	let n2_is = |f: fn(W) -> bool| n2.is_some_and(f);
+	let is_mid_letter = |w: W| w == W::MidLetter || is_mid_num_let_q(w);
+	let is_mid_num = |w: W| w == W::MidNum || is_mid_num_let_q(w);
+	let is_hebrew = |w: W| w == W::HebrewLetter;
+	let is_numeric = |w: W| w == W::Numeric;
+	let no_break =
+		// WB5 - WB7c
+		(is_ahletter(p1) && is_ahletter(n1))
+		|| (is_ahletter(p1) && is_mid_letter(n1) && n2_is(is_ahletter))
+		|| (p2_is(is_ahletter) && is_mid_letter(p1) && is_ahletter(n1))
+		|| (p1 == W::HebrewLetter && n1 == W::SingleQuote)
+		|| (p1 == W::HebrewLetter && n1 == W::DoubleQuote && n2_is(is_hebrew))
+		|| (p2_is(is_hebrew) && p1 == W::DoubleQuote && n1 == W::HebrewLetter)
+		// WB8 - WB12
+		|| (p1 == W::Numeric && n1 == W::Numeric)
+		|| (is_ahletter(p1) && n1 == W::Numeric)
+		|| (p1 == W::Numeric && is_ahletter(n1))
+		|| (p2_is(is_numeric) && is_mid_num(p1) && n1 == W::Numeric)
+		|| (p1 == W::Numeric && is_mid_num(n1) && n2_is(is_numeric))
+		// WB13 - WB13b
+		|| (p1 == W::Katakana && n1 == W::Katakana)
+		|| (matches!(p1, W::ALetter | W::HebrewLetter | W::Numeric | W::Katakana | W::ExtendNumLet)
+			&& n1 == W::ExtendNumLet)
+		|| (p1 == W::ExtendNumLet && matches!(n1, W::ALetter | W::HebrewLetter | W::Numeric | W::Katakana));
+	if no_break {
+		return false;
+	}
+	// WB15 - WB16
+	if p1 == W::RegionalIndicator && n1 == W::RegionalIndicator {
+		return regional_indicators % 2 == 0;
+	}
+	true
+}
+
+/// The end of the segment between word boundaries that starts at `start`.
+fn word_bound_end(s: &str, start: usize) -> usize {
+	let mut chars = s[start..].char_indices();
+	let first = match chars.next() {
+		Some((_, c)) => c,
+		None => return start,
+	};
+	// A segment never starts in the middle of a pair of regional indicators,
+	// so only the ones from `start` on count.
+	let mut regional_indicators = (word_break(first) == W::RegionalIndicator) as usize;
+	for (i, c) in chars {
+		if is_word_boundary(s, start + i, regional_indicators) {
+			return start + i;
+		}
+		match word_break(c) {
+			W::RegionalIndicator => regional_indicators += 1,
+			w if is_ignorable(w) => {}
+			_ => regional_indicators = 0,
+		}
+	}
+	s.len()
+}
+
+fn is_word(s: &RawStr) -> bool {
+	s.to_str().is_ok_and(|s| s.chars().any(char::is_alphanumeric))
+}
+
+// }}}
+
+// Iterators {{{
+
+/// The segments of a `RawStr`: those of each chunk of valid UTF-8, each
+/// followed by the broken bytes after it as a segment of their own.
+struct Segments<'a> {
+	s: &'a RawStr,
+	chunks: Utf8ChunksIter<'a>,
+	offset: usize,
+	valid: &'a str,
+	position: usize,
+	broken: &'a [u8],
+	/// Given a chunk and the start of a segment, returns its end.
+	segment_end: fn(&str, usize) -> usize,
+}
+
+impl<'a> Segments<'a> {
+	fn new(s: &'a RawStr, segment_end: fn(&str, usize) -> usize) -> Self {
+		Segments {
+			s,
+			chunks: s.utf8_chunks(),
+			offset: 0,
+			valid: "",
+			position: 0,
+			broken: &[],
+			segment_end,
+		}
+	}
+}
+
+impl<'a> Iterator for Segments<'a> {
+	type Item = (usize, &'a RawStr);
+	fn next(&mut self) -> Option<(usize, &'a RawStr)> {
+		loop {
+			if self.position < self.valid.len() {
+				let start = self.position;
+				self.position = (self.segment_end)(self.valid, start);
+				return Some((self.offset + start, RawStr::from_str(&self.valid[start..self.position])));
+			}
+			if !self.broken.is_empty() {
+				let broken = take(&mut self.broken);
+				return Some((self.offset + self.valid.len(), RawStr::from_bytes(broken)));
+			}
+			let chunk = self.chunks.next()?;
+			self.offset = chunk.offset_in(self.s);
+			let Utf8Chunk { valid, broken } = chunk;
+			self.valid = valid;
+			self.position = 0;
+			self.broken = broken;
+		}
+	}
+}
+
+macro_rules! segment_iterator {
+	($(#[$doc:meta])* $name:ident, $item:ty, $map:expr) => {
+		$(#[$doc])*
+		pub struct $name<'a> {
+			inner: Segments<'a>,
+		}
+
+		impl<'a> Iterator for $name<'a> {
+			type Item = $item;
+			#[inline]
+			fn next(&mut self) -> Option<$item> {
+				self.inner.by_ref().filter_map($map).next()
+			}
+		}
+
+		impl<'a> FusedIterator for $name<'a> {}
+	};
+}
+
+segment_iterator!(
+	/// Iterator over the grapheme clusters of a `RawStr`.
+	///
+	/// See [`RawStr::graphemes`](struct.RawStr.html#method.graphemes).
+	Graphemes, &'a RawStr, |(_, s)| Some(s)
+);
+
+segment_iterator!(
+	/// Iterator over the grapheme clusters of a `RawStr`, and their offsets.
+	///
+	/// See [`RawStr::grapheme_indices`](struct.RawStr.html#method.grapheme_indices).
+	GraphemeIndices, (usize, &'a RawStr), Some
+);
+
+segment_iterator!(
+	/// Iterator over the parts of a `RawStr` between word boundaries.
+	///
+	/// See [`RawStr::split_word_bounds`](struct.RawStr.html#method.split_word_bounds).
+	WordBounds, &'a RawStr, |(_, s)| Some(s)
+);
+
+segment_iterator!(
+	/// Iterator over the parts of a `RawStr` between word boundaries, and
+	/// their offsets.
+	///
+	/// See [`RawStr::split_word_bound_indices`](struct.RawStr.html#method.split_word_bound_indices).
+	WordBoundIndices, (usize, &'a RawStr), Some
+);
+
+segment_iterator!(
+	/// Iterator over the words of a `RawStr`.
+	///
+	/// See [`RawStr::unicode_words`](struct.RawStr.html#method.unicode_words).
+	UnicodeWords, &'a RawStr, |(_, s): (usize, &'a RawStr)| if is_word(s) { Some(s) } else { None }
+);
+
+segment_iterator!(
+	/// Iterator over the words of a `RawStr`, and their offsets.
+	///
+	/// See [`RawStr::unicode_word_indices`](struct.RawStr.html#method.unicode_word_indices).
+	UnicodeWordIndices, (usize, &'a RawStr), |(i, s): (usize, &'a RawStr)| if is_word(s) { Some((i, s)) } else { None }
+);
+
+// }}}
+
+/// Text segmentation. Only available with the `unicode` feature.
+///
+/// These segment the chunks of valid UTF-8 (see
+/// [`utf8_chunks`](#method.utf8_chunks)) each on their own. Every broken
+/// character is a segment of its own.
+impl RawStr {
+	/// Iterate over the extended grapheme clusters: what users perceive as
+	/// characters.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"e\xCC\x81\xF0\x9F\x87\xB3\xF0\x9F\x87\xB1\xFF\r\n");
+	/// let graphemes: Vec<&RawStr> = s.graphemes().collect();
+	/// assert_eq!(graphemes, [&b"e\xCC\x81"[..], b"\xF0\x9F\x87\xB3\xF0\x9F\x87\xB1", b"\xFF", b"\r\n"]);
+	/// ```
+	#[inline]
+	pub fn graphemes(&self) -> Graphemes<'_> {
+		Graphemes {
+			inner: Segments::new(self, grapheme_end),
+		}
+	}
+
+	/// Iterate over the extended grapheme clusters, and their byte offsets.
+	#[inline]
+	pub fn grapheme_indices(&self) -> GraphemeIndices<'_> {
+		GraphemeIndices {
+			inner: Segments::new(self, grapheme_end),
+		}
+	}
+
+	/// Split at all word boundaries.
+	///
+	/// This includes the words themselves, but also the spaces and punctuation
+	/// between them. Use [`unicode_words`](#method.unicode_words) to only get
+	/// the words.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_str("Don't panic, it's 3.14!");
+	/// let parts: Vec<&RawStr> = s.split_word_bounds().collect();
+	/// assert_eq!(parts, ["Don't", " ", "panic", ",", " ", "it's", " ", "3.14", "!"]);
+	/// ```
+	#[inline]
+	pub fn split_word_bounds(&self) -> WordBounds<'_> {
+		WordBounds {
+			inner: Segments::new(self, word_bound_end),
+		}
+	}
+
+	/// Split at all word boundaries, with the byte offsets of the parts.
+	#[inline]
+	pub fn split_word_bound_indices(&self) -> WordBoundIndices<'_> {
+		WordBoundIndices {
+			inner: Segments::new(self, word_bound_end),
+		}
+	}
+
+	/// Iterate over the words: the parts between word boundaries that contain
+	/// a letter or a number.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_bytes(b"Don't panic, \xFF it's 3.14!");
+	/// let words: Vec<&RawStr> = s.unicode_words().collect();
+	/// assert_eq!(words, ["Don't", "panic", "it's", "3.14"]);
+	/// ```
+	#[inline]
+	pub fn unicode_words(&self) -> UnicodeWords<'_> {
+		UnicodeWords {
+			inner: Segments::new(self, word_bound_end),
+		}
+	}
+
+	/// Iterate over the words, and their byte offsets.
+	#[inline]
+	pub fn unicode_word_indices(&self) -> UnicodeWordIndices<'_> {
+		UnicodeWordIndices {
+			inner: Segments::new(self, word_bound_end),
+		}
+	}
+}
+
+// Tests {{{
+
+#[cfg(test)]
+fn graphemes(s: &[u8]) -> Vec<(usize, &[u8])> {
+	RawStr::from_bytes(s).grapheme_indices().map(|(i, g)| (i, g.as_bytes())).collect()
+}
+
+#[cfg(test)]
+fn word_bounds(s: &str) -> Vec<&str> {
+	RawStr::from_str(s).split_word_bounds().map(|w| w.to_str().unwrap()).collect()
+}
+
+#[test]
+fn test_graphemes() {
+	assert_eq!(graphemes(b""), []);
+	assert_eq!(graphemes(b"ab\r\n\n"), [(0, &b"a"[..]), (1, b"b"), (2, b"\r\n"), (4, b"\n")]);
+	// Combining marks, Hangul syllables, and a prepended character.
+	let s = "e\u{301}\u{308}\u{1100}\u{1161}\u{11A8}\u{AC00}\u{110C}\u{600}x";
+	let expected = ["e\u{301}\u{308}", "\u{1100}\u{1161}\u{11A8}", "\u{AC00}", "\u{110C}", "\u{600}x"];
+	assert_eq!(RawStr::from_str(s).graphemes().collect::<Vec<_>>(), expected);
+	// Emoji ZWJ sequences, modifiers and flags.
+	let s = "\u{1F469}\u{1F3FD}\u{200D}\u{1F52C}a\u{200D}\u{1F52C}\u{1F1F3}\u{1F1F1}\u{1F1F3}";
+	let expected = ["\u{1F469}\u{1F3FD}\u{200D}\u{1F52C}", "a\u{200D}", "\u{1F52C}", "\u{1F1F3}\u{1F1F1}", "\u{1F1F3}"];
+	assert_eq!(RawStr::from_str(s).graphemes().collect::<Vec<_>>(), expected);
+	// Broken characters are segments of their own, and split combining marks
+	// from what comes before.
+	assert_eq!(
+		graphemes(b"a\xFF\xCC\x81\xE2\x82b\xF0\x9F\x98"),
+		[(0, &b"a"[..]), (1, b"\xFF"), (2, b"\xCC\x81"), (4, b"\xE2\x82"), (6, b"b"), (7, b"\xF0\x9F\x98")]
+	);
+}
+
+#[test]
+fn test_words() {
+	assert_eq!(word_bounds(""), Vec::<&str>::new());
+	assert_eq!(word_bounds("The quick (\"brown\") fox"), [
+		"The", " ", "quick", " ", "(", "\"", "brown", "\"", ")", " ", "fox"
+	]);
+	assert_eq!(word_bounds("a.b 1,000.5 e.g. a_b x1"), ["a.b", " ", "1,000.5", " ", "e.g", ".", " ", "a_b", " ", "x1"]);
+	assert_eq!(word_bounds("  \r\n\n\u{301}x"), ["  ", "\r\n", "\n", "\u{301}", "x"]);
+	assert_eq!(word_bounds("ca\u{301}fe\u{301}'s"), ["ca\u{301}fe\u{301}'s"]);
+	assert_eq!(word_bounds("\u{30AB}\u{30BF}\u{30AB}\u{30CA}\u{3042}"), ["\u{30AB}\u{30BF}\u{30AB}\u{30CA}", "\u{3042}"]);
+	assert_eq!(word_bounds("\u{5D0}\"\u{5D1} \u{5D0}'"), ["\u{5D0}\"\u{5D1}", " ", "\u{5D0}'"]);
+	assert_eq!(word_bounds("\u{1F1F3}\u{1F1F1}\u{1F1F3}"), ["\u{1F1F3}\u{1F1F1}", "\u{1F1F3}"]);
+	assert_eq!(word_bounds("a\u{1F1F3}\u{301}\u{1F1F1}\u{1F1F3}\u{200D}\u{1F1F1}\u{1F1F3}"), [
+		"a", "\u{1F1F3}\u{301}\u{1F1F1}", "\u{1F1F3}\u{200D}\u{1F1F1}", "\u{1F1F3}"
+	]);
+	let flags = "\u{1F1F3}\u{1F1F1}".repeat(10_000);
+	assert_eq!(RawStr::from_str(&flags).split_word_bounds().count(), 10_000);
+	assert_eq!(word_bounds("a\u{200D}\u{1F52C}"), ["a\u{200D}\u{1F52C}"]);
+
+	let s = RawStr::from_bytes(b"l'\xC3\xA9t\xC3\xA9 \xFF42, x\xFFy");
+	let words: Vec<(usize, &RawStr)> = s.unicode_word_indices().collect();
+	let expected: Vec<(usize, &RawStr)> = vec![
+		(0, RawStr::from_str("l'\u{E9}t\u{E9}")),
+		(9, RawStr::from_str("42")),
+		(13, RawStr::from_str("x")),
+		(15, RawStr::from_str("y")),
+	];
+	assert_eq!(words, expected);
+	assert_eq!(s.unicode_words().count(), 4);
+	assert_eq!(s.split_word_bound_indices().map(|(i, _)| i).collect::<Vec<_>>(), [0, 7, 8, 9, 11, 12, 13, 14, 15]);
+}
+
+// }}}
@@ -0,0 +1,264 @@
+//! The width of text in a terminal, following
+//! [UAX #11](https://www.unicode.org/reports/tr11/) and common terminal
+//! behaviour.
+
+use super::in_ranges;
+use super::segment::{grapheme_end, is_extended_pictographic};
+use super::tables::{AMBIGUOUS, WIDE, ZERO_WIDTH};
+use ansi::Token;
+use std::iter::from_fn;
+use str::{RawStr, Utf8Chunk};
+
+/// Options for [`RawStr::display_width_with`](struct.RawStr.html#method.display_width_with)
+/// and [`RawStr::truncate_to_width_with`](struct.RawStr.html#method.truncate_to_width_with).
+///
+/// The default counts ambiguous characters as one column, and every broken
+/// character as one column, like U+FFFD.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WidthOptions {
+	/// Count characters of ambiguous East Asian width (like `±` and `Ω`) as
+	/// two columns, like terminals in CJK locales do.
+	pub ambiguous_wide: bool,
+
+	/// Count every byte of a broken character as four columns: the length of
+	/// its `\xNN` escape, as written by the `Debug` implementation.
+	pub escape_broken: bool,
+
+	/// Skip ANSI escape sequences (see the [`ansi`](ansi/index.html) module),
+	/// so colored text is measured the way it is displayed.
+	pub ignore_escapes: bool,
+}
+
+fn char_width(c: char, options: WidthOptions) -> usize {
+	match c {
+		'\0'..='\x1F' | '\x7F'..='\u{9F}' => 0,
+		' '..='~' | '\u{AD}' => 1,
+		_ if in_ranges(c, &ZERO_WIDTH) => 0,
+		_ if in_ranges(c, &WIDE) => 2,
+		_ if options.ambiguous_wide && in_ranges(c, &AMBIGUOUS) => 2,
+		_ => 1,
+	}
+}
+
+fn is_regional_indicator(c: char) -> bool {
+	('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// Whether the character is a skin tone modifier (Emoji_Modifier).
+fn is_emoji_modifier(c: char) -> bool {
+	('\u{1F3FB}'..='\u{1F3FF}').contains(&c)
+}
+
+/// The width of a single grapheme cluster.
+///
+/// Emoji sequences (flags, ZWJ sequences, and pictographs followed by U+FE0F
+/// or a skin tone modifier) are a single wide glyph. Anything else is as wide
+/// as its characters together.
+fn grapheme_width(g: &str, options: WidthOptions) -> usize {
+	let first = match g.chars().next() {
+		Some(c) => c,
+		None => return 0,
+	};
+	if g.len() > first.len_utf8() {
+		let emoji = if is_regional_indicator(first) {
+			true
+		} else if is_extended_pictographic(first) {
+			char_width(first, options) == 2
+				|| g.contains('\u{FE0F}')
+				|| g[first.len_utf8()..].starts_with(is_emoji_modifier)
+		} else {
+			false
+		};
+		if emoji {
+			return 2;
+		}
+	}
+	g.chars().map(|c| char_width(c, options)).sum()
+}
+
+fn broken_width(broken: &[u8], options: WidthOptions) -> usize {
+	if options.escape_broken {
+		broken.len() * 4
+	} else {
+		char_width('\u{FFFD}', options)
+	}
+}
+
+/// The end offset and width of every grapheme cluster and broken character
+/// of text without escape sequences, starting at `start`.
+fn text_widths(s: &RawStr, start: usize, options: WidthOptions) -> impl Iterator<Item = (usize, usize)> + '_ {
+	s.utf8_chunks().flat_map(move |chunk| {
+		let offset = start + chunk.offset_in(s);
+		let Utf8Chunk { valid, broken } = chunk;
+		let mut start = 0;
+		let graphemes = from_fn(move || {
+			if start == valid.len() {
+				return None;
+			}
+			let end = grapheme_end(valid, start);
+			let width = grapheme_width(&valid[start..end], options);
+			start = end;
+			Some((offset + end, width))
+		});
+		let broken = if broken.is_empty() {
+			None
+		} else {
+			Some((offset + valid.len() + broken.len(), broken_width(broken, options)))
+		};
+		graphemes.chain(broken)
+	})
+}
+
+/// The end offset and width of every grapheme cluster, broken character, and
+/// (if ignored) escape sequence.
+pub(super) fn widths(s: &RawStr, options: WidthOptions) -> Box<dyn Iterator<Item = (usize, usize)> + '_> {
+	if !options.ignore_escapes {
+		return Box::new(text_widths(s, 0, options));
+	}
+	let mut start = 0;
+	Box::new(s.ansi_tokens().flat_map(move |token| {
+		let raw = token.as_raw_str();
+		let token_start = start;
+		start += raw.len();
+		let (text, escape) = match token {
+			Token::Text(text) => (Some(text_widths(text, token_start, options)), None),
+			_ => (None, Some((start, 0))),
+		};
+		text.into_iter().flatten().chain(escape)
+	}))
+}
+
+/// Display width. Only available with the `unicode` feature.
+impl RawStr {
+	/// The number of columns this takes up in a terminal.
+	///
+	/// Wide and fullwidth East Asian characters and emoji take two columns.
+	/// Control characters, combining marks, and other zero-width characters
+	/// take none. Every broken character is counted like U+FFFD, which
+	/// usually takes one column.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// assert_eq!(RawStr::from_str("abc").display_width(), 3);
+	/// assert_eq!(RawStr::from_str("日本語").display_width(), 6);
+	/// assert_eq!(RawStr::from_str("e\u{301}").display_width(), 1);
+	/// assert_eq!(RawStr::from_bytes(b"a\xFFb").display_width(), 3);
+	/// ```
+	#[inline]
+	pub fn display_width(&self) -> usize {
+		self.display_width_with(WidthOptions::default())
+	}
+
+	/// The number of columns this takes up in a terminal, with custom
+	/// options.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::{RawStr, WidthOptions};
+	/// let options = WidthOptions { escape_broken: true, ..Default::default() };
+	/// assert_eq!(RawStr::from_bytes(b"a\xFFb").display_width_with(options), 6);
+	/// ```
+	pub fn display_width_with(&self, options: WidthOptions) -> usize {
+		widths(self, options).map(|(_, width)| width).sum()
+	}
+
+	/// The longest prefix that takes up at most `width` columns.
+	///
+	/// This never splits a grapheme cluster or a broken character. Zero-width
+	/// characters right after the cut are kept, so combining marks stay with
+	/// their base character.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::RawStr;
+	/// let s = RawStr::from_str("日本語.txt");
+	/// assert_eq!(s.truncate_to_width(5), "日本");
+	/// assert_eq!(s.truncate_to_width(7), "日本語.");
+	/// assert_eq!(s.truncate_to_width(100), s);
+	/// ```
+	#[inline]
+	pub fn truncate_to_width(&self, width: usize) -> &RawStr {
+		self.truncate_to_width_with(width, WidthOptions::default())
+	}
+
+	/// The longest prefix that takes up at most `width` columns, with custom
+	/// options.
+	pub fn truncate_to_width_with(&self, width: usize, options: WidthOptions) -> &RawStr {
+		let mut total = 0;
+		let mut prefix_end = 0;
+		for (end, w) in widths(self, options) {
+			total += w;
+			if total > width {
+				break;
+			}
+			prefix_end = end;
+		}
+		&self[..prefix_end]
+	}
+}
+
+// Tests {{{
+
+#[test]
+fn test_display_width() {
+	let width = |s: &str| RawStr::from_str(s).display_width();
+	assert_eq!(width(""), 0);
+	assert_eq!(width("hello"), 5);
+	assert_eq!(width("a\tb\x1B"), 2);
+	assert_eq!(width("ｈｅｌｌｏ"), 10);
+	assert_eq!(width("한국어"), 6);
+	assert_eq!(width("\u{1100}\u{1161}\u{11A8}"), 2);
+	assert_eq!(width("a\u{200B}\u{200D}b"), 2);
+	assert_eq!(width("\u{1F600}"), 2);
+	assert_eq!(width("\u{1F44D}\u{1F3FD}"), 2);
+	assert_eq!(width("\u{1F469}\u{200D}\u{1F4BB}"), 2);
+	assert_eq!(width("\u{1F1F3}\u{1F1F1}"), 2);
+	assert_eq!(width("\u{2764}"), 1);
+	assert_eq!(width("\u{2764}\u{FE0F}"), 2);
+	assert_eq!(width("\u{261D}\u{1F3FD}"), 2);
+	assert_eq!(width("\u{270C}\u{1F3FB}x"), 3);
+	assert_eq!(width("±Ω"), 2);
+
+	let cjk = WidthOptions { ambiguous_wide: true, ..Default::default() };
+	assert_eq!(RawStr::from_str("±Ω").display_width_with(cjk), 4);
+	assert_eq!(RawStr::from_bytes(b"\xFF").display_width_with(cjk), 2);
+
+	let broken = RawStr::from_bytes(b"\xE2\x82\xFF\xFF");
+	assert_eq!(broken.display_width(), 3);
+	let escape = WidthOptions { escape_broken: true, ..Default::default() };
+	assert_eq!(broken.display_width_with(escape), 16);
+	assert_eq!(format!("{:?}", broken).len(), 18);
+
+	let colored = RawStr::from_str("\x1b[1;31m日本\x1b[0m\x1b]8;;x\x07ok\x1b]8;;\x07");
+	assert_eq!(colored.display_width(), 24);
+	let ansi = WidthOptions { ignore_escapes: true, ..Default::default() };
+	assert_eq!(colored.display_width_with(ansi), 6);
+	assert_eq!(colored.truncate_to_width_with(2, ansi), "\x1b[1;31m日");
+	assert_eq!(colored.truncate_to_width_with(4, ansi), "\x1b[1;31m日本\x1b[0m\x1b]8;;x\x07");
+}
+
+#[test]
+fn test_truncate_to_width() {
+	let s = RawStr::from_bytes("ae\u{301}日\u{1F469}\u{200D}\u{1F4BB}\u{FF}".as_bytes());
+	assert_eq!(s.truncate_to_width(0), "");
+	assert_eq!(s.truncate_to_width(1), "a");
+	assert_eq!(s.truncate_to_width(2), "ae\u{301}");
+	assert_eq!(s.truncate_to_width(3), "ae\u{301}");
+	assert_eq!(s.truncate_to_width(4), "ae\u{301}日");
+	assert_eq!(s.truncate_to_width(5), "ae\u{301}日");
+	assert_eq!(s.truncate_to_width(6), "ae\u{301}日\u{1F469}\u{200D}\u{1F4BB}");
+	assert_eq!(s.truncate_to_width(7), s);
+
+	let s = RawStr::from_bytes(b"ab\xFF\xFEcd");
+	assert_eq!(s.truncate_to_width(3), &b"ab\xFF"[..]);
+	let escape = WidthOptions { escape_broken: true, ..Default::default() };
+	assert_eq!(s.truncate_to_width_with(9, escape), &b"ab\xFF"[..]);
+	assert_eq!(s.truncate_to_width_with(10, escape), &b"ab\xFF\xFE"[..]);
+	assert_eq!(RawStr::from_str("a\u{301}\u{302}").truncate_to_width(1), "a\u{301}\u{302}");
+}
+
+// }}}
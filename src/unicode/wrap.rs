@@ -0,0 +1,332 @@
+//! Word wrapping by display width.
+
+use super::width::{widths, WidthOptions};
+use std::borrow::Cow;
+use std::iter::{from_fn, FusedIterator, Peekable};
+use std::str::from_utf8;
+use str::RawStr;
+use string::RawString;
+
+/// Options for [`RawStr::wrap`](struct.RawStr.html#method.wrap) and
+/// [`RawStr::fill`](struct.RawStr.html#method.fill).
+#[derive(Clone, Copy, Debug)]
+pub struct WrapOptions<'a> {
+	/// Prepended to the first line.
+	pub initial_indent: &'a RawStr,
+
+	/// Prepended to all lines but the first.
+	pub subsequent_indent: &'a RawStr,
+
+	/// Also allow breaks right after a hyphen between two letters, like in
+	/// `well-known`.
+	pub break_on_hyphens: bool,
+
+	/// Break words that do not fit on a line of their own. Words are only
+	/// broken between grapheme clusters, so a broken character is never
+	/// split. If this is false, such words overflow the line instead.
+	pub break_long_words: bool,
+
+	/// How to measure the width of the text and the indents.
+	pub width: WidthOptions,
+}
+
+impl<'a> Default for WrapOptions<'a> {
+	fn default() -> Self {
+		WrapOptions {
+			initial_indent: RawStr::from_str(""),
+			subsequent_indent: RawStr::from_str(""),
+			break_on_hyphens: false,
+			break_long_words: true,
+			width: WidthOptions::default(),
+		}
+	}
+}
+
+/// The characters of a string with their offsets, and `None` for every byte
+/// that isn't part of valid UTF-8.
+///
+/// Unlike `utf8_chunks`, this only decodes as far as it is iterated, so
+/// splitting a word off a long paragraph doesn't look at the whole paragraph.
+fn units(s: &RawStr) -> impl Iterator<Item = (usize, Option<char>)> + '_ {
+	let bytes = s.as_bytes();
+	let mut i = 0;
+	from_fn(move || {
+		let start = i;
+		let len = match *bytes.get(i)? {
+			0xC2..=0xDF => 2,
+			0xE0..=0xEF => 3,
+			0xF0..=0xF4 => 4,
+			_ => 1,
+		};
+		match bytes.get(i..i + len).and_then(|b| from_utf8(b).ok()) {
+			Some(c) => {
+				i += len;
+				Some((start, c.chars().next()))
+			}
+			None => {
+				i += 1;
+				Some((start, None))
+			}
+		}
+	})
+}
+
+fn is_breaking_space(c: char) -> bool {
+	c.is_whitespace() && !matches!(c, '\u{A0}' | '\u{2007}' | '\u{202F}')
+}
+
+/// Split the word and the whitespace after it off the start of `s`.
+///
+/// Returns the end of the word and the end of the whitespace.
+fn fragment(s: &RawStr, break_on_hyphens: bool) -> (usize, usize) {
+	let mut units = units(s).peekable();
+	let mut prev = None;
+	let mut word_end = None;
+	while let Some((i, c)) = units.next() {
+		let space = c.is_some_and(is_breaking_space);
+		if let Some(word_end) = word_end {
+			if !space {
+				return (word_end, i);
+			}
+		} else if space {
+			word_end = Some(i);
+		} else if c == Some('-') && break_on_hyphens {
+			let before = prev.is_some_and(char::is_alphabetic);
+			let after = matches!(units.peek(), Some(&(_, Some(c))) if c.is_alphabetic());
+			if before && after {
+				return (i + 1, i + 1);
+			}
+		}
+		prev = c;
+	}
+	(word_end.unwrap_or(s.len()), s.len())
+}
+
+/// A word that doesn't fit on a line of its own, while it is being broken.
+struct LongWord<'a> {
+	start: usize,
+	/// The end of the word.
+	end: usize,
+	/// The end of the whitespace after the word.
+	space_end: usize,
+	/// The end offset (from `start`) and width of the rest of the word, as
+	/// returned by `widths`.
+	widths: Peekable<Box<dyn Iterator<Item = (usize, usize)> + 'a>>,
+}
+
+/// Iterator over the lines of wrapped text.
+///
+/// See [`RawStr::wrap`](struct.RawStr.html#method.wrap).
+pub struct Wrap<'a> {
+	s: &'a RawStr,
+	/// Where the next line starts, or `None` when done.
+	position: Option<usize>,
+	/// The end of the current paragraph: the next `\n` or the end of `s`.
+	line_end: Option<usize>,
+	/// The word that is being broken over several lines.
+	long_word: Option<LongWord<'a>>,
+	first: bool,
+	width: usize,
+	options: WrapOptions<'a>,
+}
+
+impl<'a> Wrap<'a> {
+	/// Find the next line: its end, and where the line after it starts.
+	fn next_line(&mut self, start: usize, available: usize) -> (usize, Option<usize>) {
+		let options = self.options;
+		let measure = |s: &RawStr| s.display_width_with(options.width);
+		let line_end = match self.line_end {
+			Some(line_end) if line_end >= start => line_end,
+			_ => self.s[start..].find(b'\n').map_or(self.s.len(), |i| start + i),
+		};
+		self.line_end = Some(line_end);
+		let mut end = start;
+		let mut used = 0;
+		let mut space = None;
+		let mut position = start;
+		if let Some(mut word) = self.long_word.take() {
+			// Take as much of the word as fits, but at least one grapheme
+			// cluster.
+			while let Some(&(offset, width)) = word.widths.peek() {
+				if used + width > available && end > start {
+					self.long_word = Some(word);
+					return (end, Some(end));
+				}
+				used += width;
+				end = word.start + offset;
+				word.widths.next();
+			}
+			// The rest of the word fits.
+			end = word.end;
+			space = Some(measure(&self.s[word.end..word.space_end]));
+			position = word.space_end;
+		}
+		while position < line_end {
+			let rest = &self.s[position..line_end];
+			let (word_end, space_end) = fragment(rest, options.break_on_hyphens);
+			let word_width = measure(&rest[..word_end]);
+			if let Some(space) = space {
+				if used + space + word_width > available {
+					return (end, Some(position));
+				}
+				used += space;
+			} else if word_width > available && options.break_long_words {
+				self.long_word = Some(LongWord {
+					start: position,
+					end: position + word_end,
+					space_end: position + space_end,
+					widths: widths(&rest[..word_end], options.width).peekable(),
+				});
+				return self.next_line(start, available);
+			}
+			used += word_width;
+			end = position + word_end;
+			space = Some(measure(&rest[word_end..space_end]));
+			position += space_end;
+		}
+		if line_end == self.s.len() {
+			(end, None)
+		} else {
+			(end, Some(line_end + 1))
+		}
+	}
+}
+
+impl<'a> Iterator for Wrap<'a> {
+	type Item = Cow<'a, RawStr>;
+	fn next(&mut self) -> Option<Cow<'a, RawStr>> {
+		let start = self.position?;
+		let indent = if self.first {
+			self.options.initial_indent
+		} else {
+			self.options.subsequent_indent
+		};
+		self.first = false;
+		let available = self.width.saturating_sub(indent.display_width_with(self.options.width));
+		let (end, next) = self.next_line(start, available);
+		self.position = next;
+		let line = &self.s[start..end];
+		if indent.is_empty() || line.is_empty() {
+			Some(Cow::Borrowed(line))
+		} else {
+			let mut result = RawString::with_capacity(indent.len() + line.len());
+			result.push_str(indent);
+			result.push_str(line);
+			Some(Cow::Owned(result))
+		}
+	}
+}
+
+impl<'a> FusedIterator for Wrap<'a> {}
+
+/// Word wrapping. Only available with the `unicode` feature.
+impl RawStr {
+	/// Wrap the text into lines of at most `width` columns.
+	///
+	/// Lines are broken at whitespace, which is removed at the end of lines.
+	/// Existing line breaks are kept. Widths are measured with
+	/// [`display_width_with`](#method.display_width_with), so wide characters
+	/// and broken characters are taken into account.
+	///
+	/// Lines without indent borrow from `self`.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::{RawStr, WrapOptions};
+	/// let s = RawStr::from_bytes(b"The quick brown \xFF jumps over the lazy dog.");
+	/// let options = WrapOptions { subsequent_indent: RawStr::from_str("  "), ..Default::default() };
+	/// let lines: Vec<_> = s.wrap(15, options).map(|line| line.into_owned()).collect();
+	/// assert_eq!(lines, [&b"The quick brown"[..], b"  \xFF jumps over", b"  the lazy dog."]);
+	/// ```
+	#[inline]
+	pub fn wrap<'a>(&'a self, width: usize, options: WrapOptions<'a>) -> Wrap<'a> {
+		Wrap {
+			s: self,
+			position: Some(0),
+			line_end: None,
+			long_word: None,
+			first: true,
+			width,
+			options,
+		}
+	}
+
+	/// Wrap the text into lines of at most `width` columns, and join them
+	/// with `\n`.
+	///
+	/// See [`wrap`](#method.wrap).
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use raw_string::{RawStr, WrapOptions};
+	/// let s = RawStr::from_str("A well-known example.");
+	/// let options = WrapOptions { break_on_hyphens: true, ..Default::default() };
+	/// assert_eq!(s.fill(10, options), "A well-\nknown\nexample.");
+	/// ```
+	pub fn fill(&self, width: usize, options: WrapOptions) -> RawString {
+		let mut result = RawString::with_capacity(self.len());
+		for (i, line) in self.wrap(width, options).enumerate() {
+			if i > 0 {
+				result.push(b'\n');
+			}
+			result.push_str(line);
+		}
+		result
+	}
+}
+
+// Tests {{{
+
+#[cfg(test)]
+fn wrap(s: &str, width: usize, options: WrapOptions) -> Vec<String> {
+	RawStr::from_str(s).wrap(width, options).map(|line| line.to_str().unwrap().to_string()).collect()
+}
+
+#[test]
+fn test_wrap() {
+	let default = WrapOptions::default();
+	assert_eq!(wrap("", 10, default), [""]);
+	assert_eq!(wrap("hello world", 11, default), ["hello world"]);
+	assert_eq!(wrap("hello world", 10, default), ["hello", "world"]);
+	assert_eq!(wrap("hello   world  ", 7, default), ["hello", "world"]);
+	assert_eq!(wrap("  indented text", 10, default), ["  indented", "text"]);
+	assert_eq!(wrap("one\n\ntwo three\n", 5, default), ["one", "", "two", "three", ""]);
+	assert_eq!(wrap("a\u{A0}b c", 3, default), ["a\u{A0}b", "c"]);
+	assert_eq!(wrap("日本語 テキスト", 8, default), ["日本語", "テキスト"]);
+	assert_eq!(wrap("abcdefgh ij", 3, default), ["abc", "def", "gh", "ij"]);
+	assert_eq!(wrap("日本語", 1, default), ["日", "本", "語"]);
+
+	let no_break = WrapOptions { break_long_words: false, ..default };
+	assert_eq!(wrap("a abcdefgh ij", 3, no_break), ["a", "abcdefgh", "ij"]);
+
+	let hyphens = WrapOptions { break_on_hyphens: true, ..default };
+	assert_eq!(wrap("well-known -- x-1", 6, hyphens), ["well-", "known", "-- x-1"]);
+	assert_eq!(wrap("well-known", 6, default), ["well-k", "nown"]);
+
+	let indent = WrapOptions {
+		initial_indent: RawStr::from_str("* "),
+		subsequent_indent: RawStr::from_str("  "),
+		..default
+	};
+	assert_eq!(wrap("aaa bbb ccc\n\nddd", 7, indent), ["* aaa", "  bbb", "  ccc", "", "  ddd"]);
+	assert_eq!(wrap("abcdefg h\nij", 5, indent), ["* abc", "  def", "  g h", "  ij"]);
+}
+
+#[test]
+fn test_wrap_broken() {
+	let s = RawStr::from_bytes(b"ab\xFF\xFEcd ef\xE2\x82");
+	let lines: Vec<_> = s.wrap(3, WrapOptions::default()).map(|line| line.into_owned()).collect();
+	assert_eq!(lines, [&b"ab\xFF"[..], b"\xFEcd", b"ef\xE2\x82"]);
+
+	let escape = WrapOptions {
+		width: WidthOptions { escape_broken: true, ..Default::default() },
+		..Default::default()
+	};
+	let lines: Vec<_> = s.wrap(6, escape).map(|line| line.into_owned()).collect();
+	assert_eq!(lines, [&b"ab\xFF"[..], b"\xFEcd", b"ef", b"\xE2\x82"]);
+	assert_eq!(s.fill(6, escape), &b"ab\xFF\n\xFEcd\nef\n\xE2\x82"[..]);
+}
+
+// }}}
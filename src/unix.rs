@@ -38,3 +38,21 @@ impl RawStringExt for RawString {
 		PathBuf::from(self.into_osstring())
 	}
 }
+
+/// Conversions only available on unix.
+impl RawStr {
+	/// Convert from an `OsStr`, without copying.
+	#[inline]
+	pub fn from_osstr(s: &OsStr) -> &RawStr {
+		RawStr::from_bytes(s.as_bytes())
+	}
+}
+
+/// Conversions only available on unix.
+impl RawString {
+	/// Convert from an `OsString`, without copying.
+	#[inline]
+	pub fn from_osstring(s: OsString) -> RawString {
+		RawString::from_bytes(s.into_vec())
+	}
+}
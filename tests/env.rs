@@ -0,0 +1,24 @@
+//! Tests that change the environment of the process.
+//!
+//! These are in their own test binary, so no other tests run at the same time
+//! in the same process. Keep this to a single test for the same reason.
+
+#![cfg(unix)]
+
+extern crate raw_string;
+
+use raw_string::unix::RawStrExt;
+use raw_string::{raw_var, raw_vars, set_raw_var, RawStr};
+use std::env;
+
+#[test]
+fn test_raw_vars() {
+	let key = RawStr::from_bytes(b"RAW_STRING_TEST_\xFF");
+	let value = RawStr::from_bytes(b"a\xFE=b");
+	assert_eq!(raw_var(key), None);
+	unsafe { set_raw_var(key, value) };
+	assert_eq!(raw_var(key).unwrap(), value);
+	assert!(raw_vars().any(|(k, v)| k == key && v == value));
+	env::remove_var(key.as_osstr());
+	assert_eq!(raw_var(key), None);
+}